@@ -0,0 +1,70 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Read when `--config` isn't given, if it exists.
+pub const DEFAULT_CONFIG: &str = "jenpack.config.toml";
+
+/// Loads import path aliases from a jenpack config: the shared `[aliases]`
+/// table, overridden by `[stylepack.aliases]`. Targets are relative to the
+/// config file.
+///
+/// ```toml
+/// [aliases]
+/// "@styles" = "src/styles"
+///
+/// [stylepack.aliases]
+/// "@tokens" = "design/tokens"
+/// ```
+pub fn load(path: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read: {}", path.display()))?;
+    let table: toml::Table = toml::from_str(&text).with_context(|| format!("parse: {}", path.display()))?;
+    let root = path.parent().unwrap_or(Path::new(""));
+
+    let mut out: Vec<(String, PathBuf)> = Vec::new();
+    let shared = table.get("aliases");
+    let own = table.get("stylepack").and_then(|s| s.get("aliases"));
+    for section in [shared, own].into_iter().flatten() {
+        let Some(section) = section.as_table() else {
+            bail!("{}: aliases must be a table", path.display());
+        };
+        for (alias, target) in section {
+            let Some(target) = target.as_str() else {
+                bail!("{}: alias {} must be a path string", path.display(), alias);
+            };
+            set(&mut out, alias.clone(), root.join(target));
+        }
+    }
+    Ok(out)
+}
+
+/// Parses a `--alias @name=dir` flag.
+pub fn parse_flag(s: &str) -> Result<(String, PathBuf)> {
+    match s.split_once('=') {
+        Some((alias, dir)) if !alias.is_empty() && !dir.is_empty() => Ok((alias.to_string(), PathBuf::from(dir))),
+        _ => bail!("--alias must look like @name=dir: {}", s),
+    }
+}
+
+/// Adds or replaces `alias`, keeping first-definition order.
+pub fn set(aliases: &mut Vec<(String, PathBuf)>, alias: String, target: PathBuf) {
+    match aliases.iter_mut().find(|(a, _)| *a == alias) {
+        Some(entry) => entry.1 = target,
+        None => aliases.push((alias, target)),
+    }
+}
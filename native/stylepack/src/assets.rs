@@ -0,0 +1,410 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::css;
+
+pub struct AssetOptions {
+    /// Subdirectory of the output directory the assets are copied into.
+    pub dir: String,
+    pub hash: bool,
+    pub hash_length: usize,
+}
+
+pub struct Emitted {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+}
+
+/// Points every local `url()` and `inline-asset()` in `text`, written
+/// relative to `from_dir`, at the same file relative to `to_dir`. Targets
+/// that don't exist are left alone.
+pub fn rebase_urls(text: &str, from_dir: &Path, to_dir: &Path) -> String {
+    let text = css::rewrite_urls(text, |u| rebase_one(u, from_dir, to_dir));
+    css::rewrite_calls(&text, "inline-asset", |u| {
+        rebase_one(u, from_dir, to_dir).map(|p| format!("inline-asset(\"{}\")", p))
+    })
+}
+
+fn rebase_one(url: &str, from_dir: &Path, to_dir: &Path) -> Option<String> {
+    if !css::is_local_url(url) {
+        return None;
+    }
+    let (path, suffix) = css::split_url_suffix(url);
+    let abs = std::fs::canonicalize(from_dir.join(path)).ok()?;
+    if !abs.is_file() {
+        return None;
+    }
+    Some(format!("{}{}", url_path(&relative_path(to_dir, &abs)), suffix))
+}
+
+/// Files referenced through `url()` or `inline-asset()` in a stylesheet
+/// written to `out_dir`.
+pub fn local_refs(css: &str, out_dir: &Path) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    let refs = css::call_refs(css, "url").into_iter().chain(css::call_refs(css, "inline-asset"));
+    for u in refs.filter(|u| css::is_local_url(u)) {
+        let Ok(p) = std::fs::canonicalize(out_dir.join(css::split_url_suffix(&u).0)) else {
+            continue;
+        };
+        if p.is_file() && !out.contains(&p) {
+            out.push(p);
+        }
+    }
+    out
+}
+
+pub struct Inlined {
+    pub source: PathBuf,
+    pub size: usize,
+}
+
+/// Turns every `inline-asset()` call, and every local `url()` whose file is
+/// at most `limit` bytes, into a `data:` URI. Paths are relative to
+/// `out_dir`, as left by [`rebase_urls`].
+pub fn inline(css: &str, out_dir: &Path, limit: Option<u64>) -> Result<(String, Vec<Inlined>)> {
+    let mut inlined = Vec::new();
+    let mut err = None;
+
+    let css = css::rewrite_calls(css, "inline-asset", |u| {
+        let src = out_dir.join(css::split_url_suffix(u).0);
+        match read_asset(&src, &mut inlined) {
+            Ok(uri) => Some(format!("url(\"{}\")", uri)),
+            Err(e) => {
+                err.get_or_insert(e.context(format!("inline-asset({})", u)));
+                None
+            }
+        }
+    });
+    if let Some(e) = err {
+        return Err(e);
+    }
+
+    let Some(limit) = limit else {
+        return Ok((css, inlined));
+    };
+    let css = css::rewrite_urls(&css, |u| {
+        if !css::is_local_url(u) {
+            return None;
+        }
+        // A fragment usually addresses a symbol inside a sprite, which a
+        // data URI can't express.
+        let (path, suffix) = css::split_url_suffix(u);
+        if !suffix.is_empty() {
+            return None;
+        }
+        let src = out_dir.join(path);
+        let size = std::fs::metadata(&src).ok().filter(|m| m.is_file())?.len();
+        if size > limit {
+            return None;
+        }
+        read_asset(&src, &mut inlined).ok()
+    });
+    Ok((css, inlined))
+}
+
+fn read_asset(src: &Path, inlined: &mut Vec<Inlined>) -> Result<String> {
+    let bytes = std::fs::read(src).with_context(|| format!("read: {}", src.display()))?;
+    let src = std::fs::canonicalize(src)?;
+    let uri = data_uri(&src, &bytes);
+    if !inlined.iter().any(|i| i.source == src) {
+        inlined.push(Inlined {
+            source: src,
+            size: bytes.len(),
+        });
+    }
+    Ok(uri)
+}
+
+/// URL-encoded for SVG, which stays smaller and gzips better than base64;
+/// base64 for everything else.
+fn data_uri(path: &Path, bytes: &[u8]) -> String {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if ext == "svg" {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return format!("data:image/svg+xml,{}", encode_svg(text));
+        }
+    }
+    let mime = match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "svg" => "image/svg+xml",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        _ => "application/octet-stream",
+    };
+    format!("data:{};base64,{}", mime, BASE64.encode(bytes))
+}
+
+/// Collapses whitespace and percent-encodes only what isn't safe inside a
+/// `url()`, quoted or not.
+fn encode_svg(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut prev_space = false;
+    for c in text.trim().chars() {
+        if c.is_whitespace() {
+            if !prev_space {
+                out.push_str("%20");
+            }
+            prev_space = true;
+            continue;
+        }
+        prev_space = false;
+        match c {
+            // Attribute quotes are interchangeable in XML; single ones
+            // encode the same as double but survive HTML attribute use.
+            '"' | '\'' => out.push_str("%27"),
+            '%' | '#' | '<' | '>' | '{' | '}' | '(' | ')' | '\\' | '^' | '`' | '|' => {
+                out.push_str(&format!("%{:02X}", c as u32))
+            }
+            c if c.is_ascii() => out.push(c),
+            c => {
+                let mut buf = [0; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    out.push_str(&format!("%{:02X}", b));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Copies every local asset referenced by the stylesheet written to
+/// `out_dir` into `out_dir/<dir>`, content-hashing the names if asked, and
+/// rewrites the `url()`s to the copies.
+pub fn emit(css: &str, out_dir: &Path, opts: &AssetOptions) -> Result<(String, Vec<Emitted>)> {
+    let mut urls: HashMap<String, String> = HashMap::new();
+    let mut claimed: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let mut emitted = Vec::new();
+
+    for u in css::call_refs(css, "url") {
+        if urls.contains_key(&u) || !css::is_local_url(&u) {
+            continue;
+        }
+        let (path, suffix) = css::split_url_suffix(&u);
+        let src = out_dir.join(path);
+        if !src.is_file() {
+            eprintln!("stylepack: asset not found: {}", src.display());
+            continue;
+        }
+        let src = std::fs::canonicalize(&src)
+            .with_context(|| format!("canonicalize: {}", src.display()))?;
+        let bytes = std::fs::read(&src).with_context(|| format!("read: {}", src.display()))?;
+
+        let name = asset_name(&src, &bytes, opts);
+        let rel = if opts.dir.is_empty() {
+            name
+        } else {
+            format!("{}/{}", opts.dir.trim_end_matches('/'), name)
+        };
+        let dest = out_dir.join(&rel);
+
+        match claimed.get(&dest) {
+            Some(prev) if *prev != src => bail!(
+                "assets {} and {} would both be written to {}; enable hashing to keep them apart",
+                prev.display(),
+                src.display(),
+                dest.display()
+            ),
+            Some(_) => {}
+            None => {
+                if dest != src {
+                    if let Some(parent) = dest.parent() {
+                        std::fs::create_dir_all(parent)
+                            .with_context(|| format!("mkdir: {}", parent.display()))?;
+                    }
+                    std::fs::write(&dest, &bytes)
+                        .with_context(|| format!("write: {}", dest.display()))?;
+                }
+                claimed.insert(dest.clone(), src.clone());
+                emitted.push(Emitted {
+                    source: src,
+                    dest,
+                });
+            }
+        }
+        urls.insert(u.clone(), format!("{}{}", rel, suffix));
+    }
+
+    let css = css::rewrite_urls(css, |u| urls.get(u).cloned());
+    Ok((css, emitted))
+}
+
+/// Merges the emitted assets into the JSON manifest at `path`, in the
+/// `{ "original": "hashed" }` shape the JS `AssetManifest` reads. Keys are
+/// relative to the working directory, values to the manifest's directory.
+pub fn write_manifest(path: &Path, emitted: &[Emitted]) -> Result<()> {
+    let mut manifest: serde_json::Map<String, serde_json::Value> = if path.exists() {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("read: {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("parse: {}", path.display()))?
+    } else {
+        serde_json::Map::new()
+    };
+
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    std::fs::create_dir_all(&dir).with_context(|| format!("mkdir: {}", dir.display()))?;
+    let dir = std::fs::canonicalize(&dir)
+        .with_context(|| format!("canonicalize: {}", dir.display()))?;
+    let cwd = std::env::current_dir()?;
+
+    for e in emitted {
+        let key = url_path(e.source.strip_prefix(&cwd).unwrap_or(&e.source));
+        let value = url_path(&relative_path(&dir, &e.dest));
+        manifest.insert(key, serde_json::Value::String(value));
+    }
+
+    let text = serde_json::to_string_pretty(&manifest)?;
+    std::fs::write(path, text).with_context(|| format!("write: {}", path.display()))
+}
+
+fn asset_name(src: &Path, bytes: &[u8], opts: &AssetOptions) -> String {
+    let file_name = src.file_name().unwrap_or_default().to_string_lossy();
+    if !opts.hash {
+        return file_name.into_owned();
+    }
+    let hex: String = Md5::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    let hash = &hex[..opts.hash_length.min(hex.len())];
+    let stem = src.file_stem().unwrap_or_default().to_string_lossy();
+    match src.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, hash, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, hash),
+    }
+}
+
+/// Path from directory `from` to `to`; both absolute and normalized.
+pub fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut out = PathBuf::new();
+    for _ in common..from.len() {
+        out.push("..");
+    }
+    for c in &to[common..] {
+        out.push(c);
+    }
+    out
+}
+
+pub fn url_path(p: &Path) -> String {
+    p.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir, removed on drop.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Scratch {
+            let dir = std::env::temp_dir().join(format!("stylepack-assets-{}-{}", name, std::process::id()));
+            std::fs::remove_dir_all(&dir).ok();
+            std::fs::create_dir_all(dir.join("img")).unwrap();
+            let dir = std::fs::canonicalize(dir).unwrap();
+            std::fs::write(dir.join("img/logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+            std::fs::write(dir.join("img/dot.svg"), "<svg xmlns=\"http://www.w3.org/2000/svg\">\n  <circle r=\"1\"/></svg>").unwrap();
+            Scratch(dir)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(relative_path(Path::new("/a/b"), Path::new("/a/c/d.png")), PathBuf::from("../c/d.png"));
+        assert_eq!(url_path(Path::new("x/y.png")), "x/y.png");
+    }
+
+    #[test]
+    fn rebases_existing_files_only() {
+        let s = Scratch::new("rebase");
+        let css = "a { b: url(logo.png#x) url(missing.png) url(https://x/y.png); }";
+        let out = rebase_urls(css, &s.0.join("img"), &s.0);
+        assert_eq!(out, "a { b: url(img/logo.png#x) url(missing.png) url(https://x/y.png); }");
+    }
+
+    #[test]
+    fn emits_hashed_copies_and_keeps_suffixes() {
+        let s = Scratch::new("emit");
+        let opts = AssetOptions { dir: "assets".to_string(), hash: true, hash_length: 8 };
+        let (out, emitted) = emit("a { b: url(img/logo.png?v=2); c: url(img/logo.png?v=2); }", &s.0, &opts).unwrap();
+        assert_eq!(emitted.len(), 1);
+        let name = emitted[0].dest.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("logo.") && name.ends_with(".png") && name.len() == "logo..png".len() + 8, "{}", name);
+        assert!(emitted[0].dest.is_file());
+        assert_eq!(out.matches(&format!("url(assets/{}?v=2)", name)).count(), 2, "{}", out);
+    }
+
+    #[test]
+    fn unhashed_name_clash_is_an_error() {
+        let s = Scratch::new("clash");
+        std::fs::create_dir_all(s.0.join("other")).unwrap();
+        std::fs::write(s.0.join("other/logo.png"), b"different").unwrap();
+        let opts = AssetOptions { dir: "assets".to_string(), hash: false, hash_length: 8 };
+        let err = emit("a { b: url(img/logo.png) url(other/logo.png); }", &s.0, &opts).err().unwrap();
+        assert!(err.to_string().contains("would both be written"), "{}", err);
+    }
+
+    #[test]
+    fn inlines_by_size_limit_and_inline_asset() {
+        let s = Scratch::new("inline");
+        let css = "a { b: url(img/logo.png); c: inline-asset(\"img/dot.svg\"); }";
+        let (out, inlined) = inline(css, &s.0, None).unwrap();
+        assert!(out.contains("url(img/logo.png)"), "{}", out);
+        assert!(out.contains("url(\"data:image/svg+xml,%3Csvg%20xmlns=%27http://www.w3.org/2000/svg%27%3E%20%3Ccircle"), "{}", out);
+        assert_eq!(inlined.len(), 1);
+
+        let (out, inlined) = inline(css, &s.0, Some(4)).unwrap();
+        assert!(out.contains("url(data:image/png;base64,iVBORw==)"), "{}", out);
+        assert_eq!(inlined.len(), 2);
+        let (out, _) = inline(css, &s.0, Some(3)).unwrap();
+        assert!(out.contains("url(img/logo.png)"), "{}", out);
+    }
+
+    #[test]
+    fn missing_inline_asset_is_an_error() {
+        let s = Scratch::new("missing");
+        assert!(inline("a { b: inline-asset(\"nope.svg\"); }", &s.0, None).is_err());
+    }
+}
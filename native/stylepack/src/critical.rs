@@ -0,0 +1,520 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::css::{self, AtRule, Decl, Item, Rule, Stylesheet};
+use crate::html::{self, Document};
+use crate::scss;
+
+#[derive(clap::Args, Debug)]
+pub struct CriticalArgs {
+    #[arg(long)]
+    css: PathBuf,
+
+    #[arg(required = true)]
+    html: Vec<PathBuf>,
+
+    #[arg(short, long)]
+    output: PathBuf,
+
+    #[arg(long)]
+    rest: Option<PathBuf>,
+
+    #[arg(long, default_value_t = 4096)]
+    budget: usize,
+
+    /// Height in CSS pixels of the initial viewport. Rules only count as
+    /// critical when they match an element estimated to start above it; 0
+    /// takes the whole page.
+    #[arg(long, default_value_t = 900)]
+    fold: usize,
+
+    #[arg(long)]
+    inline: bool,
+
+    #[arg(long)]
+    minify: bool,
+}
+
+const INLINE_MARKER: &str = "data-stylepack-critical";
+
+pub fn run(args: &CriticalArgs) -> Result<()> {
+    let src = std::fs::read_to_string(&args.css)
+        .with_context(|| format!("read: {}", args.css.display()))?;
+    let sheet = css::parse_stylesheet(&src)
+        .with_context(|| format!("parse: {}", args.css.display()))?;
+
+    let mut docs = Vec::new();
+    for p in &args.html {
+        let text = std::fs::read_to_string(p).with_context(|| format!("read: {}", p.display()))?;
+        docs.push(Document::parse(&text));
+    }
+
+    let split = extract(&sheet, &docs, args.budget, args.fold);
+    let render = |s: &Stylesheet| {
+        let out = css::to_css(s);
+        if args.minify {
+            scss::minify_css(&out)
+        } else {
+            out
+        }
+    };
+
+    let critical = render(&split.critical);
+    write(&args.output, &critical)?;
+    if let Some(rest) = &args.rest {
+        write(rest, &render(&split.rest))?;
+    }
+
+    if args.inline {
+        let inline_css = scss::minify_css(&css::to_css(&split.critical));
+        for p in &args.html {
+            let text =
+                std::fs::read_to_string(p).with_context(|| format!("read: {}", p.display()))?;
+            match inline_into_head(&text, &inline_css) {
+                Some(updated) => write(p, &updated)?,
+                None => eprintln!("stylepack critical: no <head> in {}, skipped", p.display()),
+            }
+        }
+    }
+
+    eprintln!(
+        "stylepack critical: {} rule(s), {} of {} byte budget",
+        split.rules, split.bytes, args.budget
+    );
+    Ok(())
+}
+
+pub struct Split {
+    pub critical: Stylesheet,
+    pub rest: Stylesheet,
+    pub rules: usize,
+    pub bytes: usize,
+}
+
+struct Candidate {
+    path: Vec<usize>,
+    position: usize,
+    cost: usize,
+    vars: BTreeSet<String>,
+    fonts: BTreeSet<String>,
+}
+
+/// Splits `sheet` into the rules that style the top of the given pages and
+/// everything else.
+///
+/// Only elements estimated to start within the first `fold` pixels of a
+/// page count, or every element when `fold` is 0. Rules are ranked by the
+/// document position of the first such element they match and taken
+/// greedily until `budget` bytes (minified) are used. The `:root` custom
+/// properties and `@font-face` blocks the chosen rules rely on are carried
+/// along and count against the budget.
+pub fn extract(sheet: &Stylesheet, docs: &[Document], budget: usize, fold: usize) -> Split {
+    let root_vars = root_custom_properties(&sheet.items);
+    let font_faces = font_faces(&sheet.items);
+    let pages: Vec<(&Document, usize)> = docs.iter().map(|d| (d, above_fold(d, fold))).collect();
+
+    let mut candidates = Vec::new();
+    collect_candidates(&sheet.items, &mut Vec::new(), &pages, &root_vars, &mut candidates);
+    candidates.sort_by_key(|c| c.position);
+
+    let mut used = 0usize;
+    let mut chosen = HashSet::<Vec<usize>>::new();
+    let mut vars = BTreeSet::<String>::new();
+    let mut fonts = BTreeSet::<String>::new();
+    let mut opened = HashSet::<Vec<usize>>::new();
+
+    for c in &candidates {
+        let mut extra = c.cost;
+        for depth in 1..c.path.len() {
+            let prefix = &c.path[..depth];
+            if !opened.contains(prefix) {
+                extra += wrapper_cost(&sheet.items, prefix);
+            }
+        }
+        let new_vars: Vec<&String> = c.vars.difference(&vars).collect();
+        if !new_vars.is_empty() && vars.is_empty() {
+            extra += ":root{}".len();
+        }
+        extra += new_vars
+            .iter()
+            .map(|v| root_vars.get(*v).map(|val| v.len() + val.len() + 2).unwrap_or(0))
+            .sum::<usize>();
+        extra += c
+            .fonts
+            .difference(&fonts)
+            .filter_map(|f| font_faces.get(f))
+            .map(|items| items.iter().map(minified_len).sum::<usize>())
+            .sum::<usize>();
+
+        if used + extra > budget {
+            continue;
+        }
+        used += extra;
+        for depth in 1..c.path.len() {
+            opened.insert(c.path[..depth].to_vec());
+        }
+        vars.extend(c.vars.iter().cloned());
+        fonts.extend(c.fonts.iter().cloned());
+        chosen.insert(c.path.clone());
+    }
+
+    let mut critical = Vec::new();
+    for f in &fonts {
+        if let Some(items) = font_faces.get(f) {
+            critical.extend(items.iter().cloned());
+        }
+    }
+    if !vars.is_empty() {
+        let body = vars
+            .iter()
+            .filter_map(|v| {
+                root_vars.get(v).map(|val| {
+                    Item::Decl(Decl {
+                        prop: v.clone(),
+                        value: val.clone(),
+                    })
+                })
+            })
+            .collect();
+        critical.push(Item::Rule(Rule {
+            selectors: vec![":root".to_string()],
+            body,
+        }));
+    }
+    critical.extend(filter_items(&sheet.items, &mut Vec::new(), &chosen, true));
+    let rest = filter_items(&sheet.items, &mut Vec::new(), &chosen, false);
+
+    Split {
+        critical: Stylesheet { items: critical },
+        rest: Stylesheet { items: rest },
+        rules: chosen.len(),
+        bytes: used,
+    }
+}
+
+fn collect_candidates(
+    items: &[Item],
+    path: &mut Vec<usize>,
+    pages: &[(&Document, usize)],
+    root_vars: &HashMap<String, String>,
+    out: &mut Vec<Candidate>,
+) {
+    for (i, item) in items.iter().enumerate() {
+        path.push(i);
+        match item {
+            Item::Rule(r) if !is_root_vars(r) => {
+                if let Some(position) = first_position(r, pages) {
+                    out.push(Candidate {
+                        path: path.clone(),
+                        position,
+                        cost: minified_len(item),
+                        vars: var_closure(r, root_vars),
+                        fonts: font_families(r),
+                    });
+                }
+            }
+            Item::At(a) if is_grouping(a) => {
+                if let Some(body) = &a.body {
+                    collect_candidates(body, path, pages, root_vars, out);
+                }
+            }
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+/// Document position of the first element above the fold that `rule`
+/// matches, over `pages` of documents and their fold indexes.
+fn first_position(rule: &Rule, pages: &[(&Document, usize)]) -> Option<usize> {
+    rule.selectors
+        .iter()
+        .filter_map(|s| html::parse_selector(s))
+        .flat_map(|sel| {
+            pages
+                .iter()
+                .filter_map(move |(d, fold)| d.first_match(&sel).filter(|i| i < fold))
+        })
+        .min()
+}
+
+/// Height of a line box, for elements whose height the markup doesn't give.
+const LINE_PX: usize = 24;
+/// Elements that take no space on the page.
+const NO_BOX: &[&str] = &[
+    "head", "script", "style", "link", "meta", "title", "template", "noscript", "br", "wbr", "source",
+    "track", "param",
+];
+/// Elements that flow within a line rather than starting one.
+const INLINE: &[&str] = &[
+    "a", "abbr", "b", "cite", "code", "em", "i", "kbd", "label", "mark", "q", "s", "small", "span",
+    "strong", "sub", "sup", "time", "u", "var",
+];
+
+/// How many of `doc`'s elements, in document order, start above `fold`
+/// pixels. There is no real layout: heights come from `style` and `height`
+/// attributes, a block without child elements is one line, and everything
+/// else stacks its children.
+fn above_fold(doc: &Document, fold: usize) -> usize {
+    if fold == 0 {
+        return doc.elements.len();
+    }
+    let mut tops = vec![0; doc.elements.len()];
+    let mut y = 0;
+    for root in (0..doc.elements.len()).filter(|&i| doc.elements[i].parent.is_none()) {
+        y += estimate_layout(doc, root, y, &mut tops);
+    }
+    tops.iter().position(|&top| top >= fold).unwrap_or(tops.len())
+}
+
+/// Records the estimated top of `idx` and its descendants, returning its
+/// height.
+fn estimate_layout(doc: &Document, idx: usize, top: usize, tops: &mut [usize]) -> usize {
+    tops[idx] = top;
+    let el = &doc.elements[idx];
+    let mut inner = 0;
+    for &child in &el.children {
+        inner += estimate_layout(doc, child, top + inner, tops);
+    }
+    if NO_BOX.contains(&el.tag.as_str()) {
+        return 0;
+    }
+    match explicit_height(el) {
+        Some(h) => h,
+        None if inner == 0 && !INLINE.contains(&el.tag.as_str()) => LINE_PX,
+        None => inner,
+    }
+}
+
+/// The larger of a `height`/`min-height` in pixels from the inline style
+/// and a `height` attribute.
+fn explicit_height(el: &html::Element) -> Option<usize> {
+    let from_style = el.attr("style").into_iter().flat_map(|s| s.split(';')).filter_map(|decl| {
+        let (prop, value) = decl.split_once(':')?;
+        matches!(prop.trim(), "height" | "min-height")
+            .then(|| value.trim().strip_suffix("px"))
+            .flatten()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+    });
+    let from_attr = el.attr("height").and_then(|h| h.trim().trim_end_matches("px").parse::<f64>().ok());
+    from_style.chain(from_attr).map(|h| h.max(0.0) as usize).max()
+}
+
+fn is_grouping(a: &AtRule) -> bool {
+    matches!(
+        a.name.as_str(),
+        "media" | "supports" | "layer" | "container" | "document" | "-moz-document"
+    )
+}
+
+fn filter_items(
+    items: &[Item],
+    path: &mut Vec<usize>,
+    chosen: &HashSet<Vec<usize>>,
+    keep_chosen: bool,
+) -> Vec<Item> {
+    let mut out = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        path.push(i);
+        match item {
+            Item::Rule(_) => {
+                if chosen.contains(path) == keep_chosen {
+                    out.push(item.clone());
+                }
+            }
+            Item::At(a) if is_grouping(a) && a.body.is_some() => {
+                let body = filter_items(a.body.as_ref().unwrap(), path, chosen, keep_chosen);
+                if !body.is_empty() {
+                    out.push(Item::At(AtRule {
+                        name: a.name.clone(),
+                        prelude: a.prelude.clone(),
+                        body: Some(body),
+                    }));
+                }
+            }
+            _ => {
+                if !keep_chosen {
+                    out.push(item.clone());
+                }
+            }
+        }
+        path.pop();
+    }
+    out
+}
+
+fn wrapper_cost(items: &[Item], prefix: &[usize]) -> usize {
+    let mut cur = items;
+    let mut item = None;
+    for &i in prefix {
+        item = cur.get(i);
+        if let Some(Item::At(a)) = item {
+            cur = a.body.as_deref().unwrap_or(&[]);
+        }
+    }
+    match item {
+        Some(Item::At(a)) => a.name.len() + a.prelude.len() + 4,
+        _ => 0,
+    }
+}
+
+fn minified_len(item: &Item) -> usize {
+    scss::minify_css(&css::item_to_css(item)).trim().len()
+}
+
+fn is_root_selector(s: &str) -> bool {
+    s == ":root" || s == "html"
+}
+
+/// `:root { --x: ... }` blocks are only pulled in through the variables the
+/// chosen rules reference, never wholesale.
+fn is_root_vars(rule: &Rule) -> bool {
+    rule.selectors.iter().all(|s| is_root_selector(s))
+        && rule.decls().all(|d| d.is_custom_property())
+}
+
+fn root_custom_properties(items: &[Item]) -> HashMap<String, String> {
+    let mut out = HashMap::new();
+    for item in items {
+        if let Item::Rule(r) = item {
+            if r.selectors.iter().any(|s| is_root_selector(s)) {
+                for d in r.decls().filter(|d| d.is_custom_property()) {
+                    out.insert(d.prop.clone(), d.value.clone());
+                }
+            }
+        }
+    }
+    out
+}
+
+fn font_faces(items: &[Item]) -> HashMap<String, Vec<Item>> {
+    let mut out: HashMap<String, Vec<Item>> = HashMap::new();
+    for item in items {
+        if let Item::At(a) = item {
+            if a.name != "font-face" {
+                continue;
+            }
+            let family = a.body.iter().flatten().find_map(|i| match i {
+                Item::Decl(d) if d.prop == "font-family" => Some(normalize_family(&d.value)),
+                _ => None,
+            });
+            if let Some(f) = family {
+                out.entry(f).or_default().push(item.clone());
+            }
+        }
+    }
+    out
+}
+
+fn normalize_family(s: &str) -> String {
+    s.trim().trim_matches(|c| c == '"' || c == '\'').to_ascii_lowercase()
+}
+
+fn font_families(rule: &Rule) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    for d in rule.decls() {
+        if d.prop == "font-family" || d.prop == "font" {
+            for f in css::split_top_level(&d.value, ',') {
+                let last = if d.prop == "font" {
+                    f.rsplit(' ').next().unwrap_or(&f).to_string()
+                } else {
+                    f
+                };
+                out.insert(normalize_family(&last));
+            }
+        }
+    }
+    out
+}
+
+fn var_closure(rule: &Rule, root_vars: &HashMap<String, String>) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    let mut stack: Vec<String> = rule.decls().flat_map(|d| css::var_refs(&d.value)).collect();
+    while let Some(v) = stack.pop() {
+        if !root_vars.contains_key(&v) || !out.insert(v.clone()) {
+            continue;
+        }
+        stack.extend(css::var_refs(&root_vars[&v]));
+    }
+    out
+}
+
+fn inline_into_head(html: &str, css: &str) -> Option<String> {
+    let tag = format!("<style {}>{}</style>", INLINE_MARKER, css);
+    if let Some(start) = html.find(&format!("<style {}>", INLINE_MARKER)) {
+        let end = html[start..].find("</style>")? + start + "</style>".len();
+        return Some(format!("{}{}{}", &html[..start], tag, &html[end..]));
+    }
+    let lower = html.to_ascii_lowercase();
+    let at = lower.find("</head>")?;
+    Some(format!("{}{}\n{}", &html[..at], tag, &html[at..]))
+}
+
+fn write(path: &Path, text: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("mkdir: {}", parent.display()))?;
+    }
+    std::fs::write(path, text).with_context(|| format!("write: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head><title>x</title></head><body>
+        <header class="header"><a class="logo" href="/">x</a></header>
+        <div class="spacer" style="height: 9999px"></div>
+        <footer class="footer">y</footer>
+    </body></html>"#;
+
+    const CSS: &str = ".header { color: red; } .footer { color: blue; } .missing { color: green; }";
+
+    fn critical_css(fold: usize) -> String {
+        let sheet = css::parse_stylesheet(CSS).unwrap();
+        let split = extract(&sheet, &[Document::parse(PAGE)], 4096, fold);
+        css::to_css(&split.critical)
+    }
+
+    #[test]
+    fn rules_below_the_fold_are_not_critical() {
+        let out = critical_css(900);
+        assert!(out.contains(".header"), "{}", out);
+        assert!(!out.contains(".footer"), "{}", out);
+        assert!(!out.contains(".missing"), "{}", out);
+    }
+
+    #[test]
+    fn zero_fold_takes_the_whole_page() {
+        let out = critical_css(0);
+        assert!(out.contains(".header") && out.contains(".footer"), "{}", out);
+    }
+
+    #[test]
+    fn estimates_heights_from_markup() {
+        let doc = Document::parse(
+            r#"<body><p>a</p><p><span>b</span></p><img height="300"><div style="min-height:50px"></div><p>c</p></body>"#,
+        );
+        // body, p, p, span start above 60px; the img at 48px too; the div
+        // starts at 348px.
+        assert_eq!(above_fold(&doc, 60), 5);
+        assert_eq!(above_fold(&doc, 349), 6);
+        assert_eq!(above_fold(&doc, 0), doc.elements.len());
+    }
+}
@@ -0,0 +1,575 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    Rule(Rule),
+    At(AtRule),
+    Decl(Decl),
+    Comment(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub selectors: Vec<String>,
+    pub body: Vec<Item>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: String,
+    pub body: Option<Vec<Item>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decl {
+    pub prop: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Stylesheet {
+    pub items: Vec<Item>,
+}
+
+impl Rule {
+    pub fn decls(&self) -> impl Iterator<Item = &Decl> {
+        self.body.iter().filter_map(|i| match i {
+            Item::Decl(d) => Some(d),
+            _ => None,
+        })
+    }
+}
+
+impl Decl {
+    pub fn is_custom_property(&self) -> bool {
+        self.prop.starts_with("--")
+    }
+}
+
+pub fn parse_stylesheet(src: &str) -> Result<Stylesheet> {
+    let mut p = Parser { s: src, i: 0 };
+    let items = p.parse_items(false)?;
+    Ok(Stylesheet { items })
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    i: usize,
+}
+
+impl Parser<'_> {
+    fn parse_items(&mut self, nested: bool) -> Result<Vec<Item>> {
+        let mut out = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.eof() {
+                if nested {
+                    bail!("unexpected end of stylesheet, missing '}}'");
+                }
+                break;
+            }
+            if self.starts_with("/*") {
+                let start = self.i + 2;
+                let end = self.s[start..].find("*/").map(|e| start + e).unwrap_or(self.s.len());
+                out.push(Item::Comment(self.s[start..end].to_string()));
+                self.i = (end + 2).min(self.s.len());
+                continue;
+            }
+            if self.starts_with("<!--") || self.starts_with("-->") {
+                self.i += if self.starts_with("<!--") { 4 } else { 3 };
+                continue;
+            }
+            match self.peek() {
+                Some('}') => {
+                    if !nested {
+                        bail!("unmatched '}}' at byte {}", self.i);
+                    }
+                    self.i += 1;
+                    break;
+                }
+                Some(';') => {
+                    self.i += 1;
+                    continue;
+                }
+                Some('@') => {
+                    out.push(self.parse_at()?);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let head = if self.starts_with("--") {
+                self.read_custom_property()
+            } else {
+                self.read_until(&['{', ';', '}'])
+            };
+            match self.peek() {
+                Some('{') => {
+                    self.i += 1;
+                    let body = self.parse_items(true)?;
+                    out.push(Item::Rule(Rule {
+                        selectors: split_top_level(head.trim(), ','),
+                        body,
+                    }));
+                }
+                _ => {
+                    if self.peek() == Some(';') {
+                        self.i += 1;
+                    }
+                    if let Some(d) = parse_decl(&head) {
+                        out.push(Item::Decl(d));
+                    } else if !head.trim().is_empty() {
+                        bail!("invalid declaration: {}", head.trim());
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn parse_at(&mut self) -> Result<Item> {
+        self.i += 1;
+        let start = self.i;
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                self.i += c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        let name = self.s[start..self.i].to_string();
+        let prelude = self.read_until(&['{', ';', '}']).trim().to_string();
+        let body = match self.peek() {
+            Some('{') => {
+                self.i += 1;
+                Some(self.parse_items(true)?)
+            }
+            Some(';') => {
+                self.i += 1;
+                None
+            }
+            _ => None,
+        };
+        Ok(Item::At(AtRule { name, prelude, body }))
+    }
+
+    fn read_until(&mut self, stops: &[char]) -> String {
+        let start = self.i;
+        let mut depth = 0i32;
+        let mut in_str: Option<char> = None;
+        while let Some(c) = self.peek() {
+            if let Some(q) = in_str {
+                self.i += c.len_utf8();
+                if c == q {
+                    in_str = None;
+                } else if c == '\\' {
+                    if let Some(n) = self.peek() {
+                        self.i += n.len_utf8();
+                    }
+                }
+                continue;
+            }
+            match c {
+                '"' | '\'' => in_str = Some(c),
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth -= 1,
+                '/' if self.starts_with("/*") && depth == 0 => {
+                    let end = self.s[self.i + 2..].find("*/").map(|e| self.i + 4 + e);
+                    self.i = end.unwrap_or(self.s.len());
+                    continue;
+                }
+                _ if depth <= 0 && stops.contains(&c) => break,
+                _ => {}
+            }
+            self.i += c.len_utf8();
+        }
+        self.s[start..self.i].to_string()
+    }
+
+    /// A `--name: value` declaration, whose value may hold `{}` and `;`
+    /// inside brackets.
+    fn read_custom_property(&mut self) -> String {
+        let start = self.i;
+        let mut depth = 0i32;
+        let mut in_str: Option<char> = None;
+        while let Some(c) = self.peek() {
+            if let Some(q) = in_str {
+                self.i += c.len_utf8();
+                if c == q {
+                    in_str = None;
+                } else if c == '\\' {
+                    if let Some(n) = self.peek() {
+                        self.i += n.len_utf8();
+                    }
+                }
+                continue;
+            }
+            match c {
+                '"' | '\'' => in_str = Some(c),
+                '(' | '[' | '{' => depth += 1,
+                ';' | '}' if depth <= 0 => break,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+            self.i += c.len_utf8();
+        }
+        self.s[start..self.i].to_string()
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.i += c.len_utf8();
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.i..].chars().next()
+    }
+
+    fn eof(&self) -> bool {
+        self.i >= self.s.len()
+    }
+
+    fn starts_with(&self, t: &str) -> bool {
+        self.s[self.i..].starts_with(t)
+    }
+}
+
+fn parse_decl(s: &str) -> Option<Decl> {
+    let idx = s.find(':')?;
+    let prop = s[..idx].trim();
+    if prop.is_empty() {
+        return None;
+    }
+    let value = s[idx + 1..].trim();
+    let value = match prop.starts_with("--") {
+        true => value.to_string(),
+        false => strip_comments(value).trim().to_string(),
+    };
+    Some(Decl {
+        prop: prop.to_string(),
+        value,
+    })
+}
+
+/// Drops `/* ... */` spans outside strings, keeping the text around them.
+fn strip_comments(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_str: Option<char> = None;
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        if in_str.is_none() && rest.starts_with("/*") {
+            rest = rest[2..].find("*/").map_or("", |end| &rest[end + 4..]);
+            if out.is_empty() || out.ends_with(char::is_whitespace) {
+                rest = rest.trim_start();
+            } else if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+                out.push(' ');
+            }
+            continue;
+        }
+        match (in_str, c) {
+            (None, '"' | '\'') => in_str = Some(c),
+            (Some(q), _) if c == q => in_str = None,
+            _ => {}
+        }
+        if c == '\\' && in_str.is_some() {
+            let escaped = rest[1..].chars().next().map_or(0, char::len_utf8);
+            out.push_str(&rest[..1 + escaped]);
+            rest = &rest[1 + escaped..];
+            continue;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Splits `s` on `sep` outside of strings, parentheses and brackets.
+pub fn split_top_level(s: &str, sep: char) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut depth = 0i32;
+    let mut in_str: Option<char> = None;
+    let mut cur = String::new();
+    let mut escaped = false;
+    for c in s.chars() {
+        if escaped {
+            escaped = false;
+            cur.push(c);
+            continue;
+        }
+        if c == '\\' {
+            escaped = true;
+            cur.push(c);
+            continue;
+        }
+        if let Some(q) = in_str {
+            if c == q {
+                in_str = None;
+            }
+            cur.push(c);
+            continue;
+        }
+        match c {
+            '"' | '\'' => in_str = Some(c),
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ if c == sep && depth == 0 => {
+                let t = cur.trim();
+                if !t.is_empty() {
+                    out.push(t.to_string());
+                }
+                cur.clear();
+                continue;
+            }
+            _ => {}
+        }
+        cur.push(c);
+    }
+    let t = cur.trim();
+    if !t.is_empty() {
+        out.push(t.to_string());
+    }
+    out
+}
+
+/// Names of the custom properties referenced through `var()` in `value`.
+pub fn var_refs(value: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = value;
+    while let Some(idx) = rest.find("var(") {
+        rest = &rest[idx + 4..];
+        let name: String = rest
+            .trim_start()
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+        if name.starts_with("--") {
+            out.push(name);
+        }
+    }
+    out
+}
+
+/// Rewrites the target of every `url()` in `text` for which `f` returns a
+/// replacement, keeping the original quoting.
+pub fn rewrite_urls(text: &str, mut f: impl FnMut(&str) -> Option<String>) -> String {
+    scan_calls(text, "url", |quote, target| {
+        let new = f(target)?;
+        Some(match quote {
+            Some(q) => format!("url({}{}{})", q, new, q),
+            None => format!("url({})", new),
+        })
+    })
+}
+
+/// Replaces every call of the single-argument function `name` (such as
+/// `inline-asset("x.svg")`) with what `f` returns for its unquoted argument.
+pub fn rewrite_calls(text: &str, name: &str, mut f: impl FnMut(&str) -> Option<String>) -> String {
+    scan_calls(text, name, |_, target| f(target))
+}
+
+fn scan_calls(
+    text: &str,
+    name: &str,
+    mut f: impl FnMut(Option<char>, &str) -> Option<String>,
+) -> String {
+    let open = name.len() + 1;
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = find_call(rest, name) {
+        out.push_str(&rest[..idx]);
+        let after = &rest[idx + open..];
+        let Some(end) = url_end(after) else {
+            break;
+        };
+        let inner = after[..end].trim();
+        let (quote, target) = match inner.chars().next() {
+            Some(q @ ('"' | '\'')) if inner.len() >= 2 && inner.ends_with(q) => {
+                (Some(q), &inner[1..inner.len() - 1])
+            }
+            _ => (None, inner),
+        };
+        match f(quote, target) {
+            Some(new) => out.push_str(&new),
+            None => out.push_str(&rest[idx..idx + open + end + 1]),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Unquoted arguments of every call of `name` in `text`, e.g. the targets of
+/// every `url()`.
+pub fn call_refs(text: &str, name: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    rewrite_calls(text, name, |u| {
+        out.push(u.to_string());
+        None
+    });
+    out
+}
+
+/// True for `url()` targets that name a file next to the stylesheet rather
+/// than a remote, root-relative, fragment-only, inline or interpolated one.
+pub fn is_local_url(url: &str) -> bool {
+    !(url.is_empty()
+        || url.starts_with('/')
+        || url.starts_with('#')
+        || url.starts_with('$')
+        || url.starts_with("data:")
+        || url.contains("://")
+        || url.contains("#{")
+        || url.contains("var("))
+}
+
+/// Splits a `?query` or `#fragment` suffix off a URL.
+pub fn split_url_suffix(url: &str) -> (&str, &str) {
+    match url.find(['?', '#']) {
+        Some(idx) => url.split_at(idx),
+        None => (url, ""),
+    }
+}
+
+fn find_call(s: &str, name: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut from = 0;
+    while let Some(rel) = s[from..].find(name) {
+        let idx = from + rel;
+        from = idx + name.len();
+        let prev = if idx == 0 { b' ' } else { bytes[idx - 1] };
+        if bytes.get(from) == Some(&b'(')
+            && !(prev.is_ascii_alphanumeric() || prev == b'-' || prev == b'_')
+        {
+            return Some(idx);
+        }
+    }
+    None
+}
+
+fn url_end(s: &str) -> Option<usize> {
+    let trimmed = s.trim_start();
+    let lead = s.len() - trimmed.len();
+    match trimmed.chars().next() {
+        Some(q @ ('"' | '\'')) => {
+            let close = trimmed[1..].find(q)? + 1;
+            Some(lead + close + trimmed[close..].find(')')?)
+        }
+        _ => s.find(')'),
+    }
+}
+
+pub fn to_css(sheet: &Stylesheet) -> String {
+    let mut out = String::new();
+    write_items(&sheet.items, 0, &mut out);
+    out
+}
+
+pub fn item_to_css(item: &Item) -> String {
+    let mut out = String::new();
+    write_items(std::slice::from_ref(item), 0, &mut out);
+    out
+}
+
+fn write_items(items: &[Item], depth: usize, out: &mut String) {
+    let pad = "  ".repeat(depth);
+    for item in items {
+        match item {
+            Item::Decl(d) => {
+                out.push_str(&pad);
+                out.push_str(&d.prop);
+                out.push_str(": ");
+                out.push_str(&d.value);
+                out.push_str(";\n");
+            }
+            Item::Comment(c) => {
+                out.push_str(&pad);
+                out.push_str("/*");
+                out.push_str(c);
+                out.push_str("*/\n");
+            }
+            Item::Rule(r) => {
+                out.push_str(&pad);
+                out.push_str(&r.selectors.join(", "));
+                out.push_str(" {\n");
+                write_items(&r.body, depth + 1, out);
+                out.push_str(&pad);
+                out.push_str("}\n");
+                if depth == 0 {
+                    out.push('\n');
+                }
+            }
+            Item::At(a) => {
+                out.push_str(&pad);
+                out.push('@');
+                out.push_str(&a.name);
+                if !a.prelude.is_empty() {
+                    out.push(' ');
+                    out.push_str(&a.prelude);
+                }
+                match &a.body {
+                    Some(body) => {
+                        out.push_str(" {\n");
+                        write_items(body, depth + 1, out);
+                        out.push_str(&pad);
+                        out.push_str("}\n");
+                    }
+                    None => out.push_str(";\n"),
+                }
+                if depth == 0 {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decl_value(css: &str) -> String {
+        let sheet = parse_stylesheet(css).unwrap();
+        let Item::Rule(rule) = &sheet.items[0] else { panic!("not a rule: {:?}", sheet.items) };
+        let value = rule.decls().next().unwrap().value.clone();
+        value
+    }
+
+    #[test]
+    fn inline_comment_keeps_rest_of_value() {
+        assert_eq!(decl_value(".a { margin: 0 /* keep */ auto; }"), "0 auto");
+        assert_eq!(decl_value(".a { margin: 0/* keep */auto; }"), "0 auto");
+        assert_eq!(decl_value(".a { color: red /* note */; }"), "red");
+    }
+
+    #[test]
+    fn comment_markers_in_strings_are_kept() {
+        assert_eq!(decl_value(r#".a { content: "/* x */" /* y */; }"#), r#""/* x */""#);
+        assert_eq!(decl_value(r#".a { content: "a\"/*" b; }"#), r#""a\"/*" b"#);
+    }
+
+    #[test]
+    fn custom_property_values_are_raw() {
+        assert_eq!(decl_value(".a { --x: 1 /* a */ 2; }"), "1 /* a */ 2");
+    }
+}
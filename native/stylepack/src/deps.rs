@@ -0,0 +1,122 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Everything an output depends on, for build tools that invalidate on
+/// their own.
+#[derive(Serialize)]
+pub struct Deps {
+    pub output: PathBuf,
+    /// Stylesheets read, entry first.
+    pub inputs: Vec<PathBuf>,
+    /// Files referenced through `url()` or `inline-asset()`.
+    pub assets: Vec<PathBuf>,
+    /// Import candidates that were checked and didn't exist; creating one
+    /// can change what an import resolves to.
+    pub probed: Vec<PathBuf>,
+}
+
+impl Deps {
+    pub fn new(output: &Path, inputs: Vec<PathBuf>, assets: Vec<PathBuf>, probed: Vec<PathBuf>) -> Self {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let rel = |p: PathBuf| p.strip_prefix(&cwd).map(Path::to_path_buf).unwrap_or(p);
+        let mut unique: Vec<PathBuf> = Vec::new();
+        for p in probed.into_iter().map(rel) {
+            if !unique.contains(&p) {
+                unique.push(p);
+            }
+        }
+        Self {
+            output: output.to_path_buf(),
+            inputs: inputs.into_iter().map(rel).collect(),
+            assets: assets.into_iter().map(rel).collect(),
+            probed: unique,
+        }
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let text = serde_json::to_string_pretty(self)? + "\n";
+        std::fs::write(path, text).with_context(|| format!("write: {}", path.display()))
+    }
+
+    /// Make/ninja depfile. Missing files can't be prerequisites, so probed
+    /// locations are represented by their directories, whose mtime changes
+    /// when a file is created in them.
+    pub fn write_depfile(&self, path: &Path) -> Result<()> {
+        let mut prereqs: Vec<&Path> = Vec::new();
+        let dirs = self.probed.iter().filter_map(|p| p.parent()).filter(|d| d.is_dir());
+        for p in self.inputs.iter().chain(&self.assets).map(PathBuf::as_path).chain(dirs) {
+            if !prereqs.contains(&p) {
+                prereqs.push(p);
+            }
+        }
+
+        let mut text = format!("{}:", escape(&self.output));
+        for p in prereqs {
+            text.push_str(" \\\n  ");
+            text.push_str(&escape(p));
+        }
+        text.push('\n');
+        std::fs::write(path, text).with_context(|| format!("write: {}", path.display()))
+    }
+}
+
+fn escape(p: &Path) -> String {
+    let s = p.to_string_lossy();
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            ' ' | '#' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '$' => out.push_str("$$"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depfile_lists_inputs_assets_and_probed_dirs() {
+        let dir = std::env::temp_dir().join(format!("stylepack-deps-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("my styles")).unwrap();
+        let deps = Deps::new(
+            &dir.join("out.css"),
+            vec![dir.join("my styles/a.scss"), dir.join("b$.scss")],
+            vec![dir.join("my styles/a.scss")],
+            vec![dir.join("my styles/_c.scss"), dir.join("my styles/c.scss"), dir.join("gone/d.scss")],
+        );
+        assert_eq!(deps.probed.len(), 3);
+        let path = dir.join("out.d");
+        deps.write_depfile(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let d = dir.display();
+        let expected = format!(
+            "{d}/out.css: \\\n  {d}/my\\ styles/a.scss \\\n  {d}/b$$.scss \\\n  {d}/my\\ styles\n"
+        );
+        assert_eq!(text, expected);
+    }
+}
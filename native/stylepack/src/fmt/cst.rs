@@ -0,0 +1,311 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Comment-preserving syntax tree for the formatter.
+//!
+//! Unlike `scss::parser`, nothing is evaluated or dropped: every comment,
+//! string and interpolation survives, and the blank lines between nodes are
+//! recorded so the printer can keep the author's grouping.
+
+use anyhow::{bail, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub kind: Kind,
+    /// Blank lines before this node in the source.
+    pub blank_lines: usize,
+    /// Byte range in the source, terminator included.
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Kind {
+    /// `prop: value`, including `$variables` and custom properties.
+    Decl { prop: String, value: String },
+    /// Any other `;`-terminated statement: `@include x;`, `@use "y";`, ...
+    Statement(String),
+    /// A rule, at-rule or nested property with a `{}` body.
+    Block { prelude: String, children: Vec<Node> },
+    /// `//` or `/* */` comment, verbatim. `trailing` ones started on the line
+    /// the previous node ended on.
+    Comment { text: String, trailing: bool },
+}
+
+pub fn parse(src: &str) -> Result<Vec<Node>> {
+    let mut p = Parser { s: src, i: 0 };
+    let nodes = p.block(false)?;
+    if p.i < p.s.len() {
+        bail!("unexpected '}}' at line {}", p.line());
+    }
+    Ok(nodes)
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    i: usize,
+}
+
+impl Parser<'_> {
+    fn block(&mut self, nested: bool) -> Result<Vec<Node>> {
+        let mut out: Vec<Node> = Vec::new();
+        loop {
+            let newlines = self.skip_ws();
+            let rest = &self.s[self.i..];
+            if rest.is_empty() {
+                if nested {
+                    bail!("unclosed block at end of file");
+                }
+                return Ok(out);
+            }
+            let blank_lines = if out.is_empty() { 0 } else { newlines.saturating_sub(1) };
+            let start = self.i;
+            let same_line = !out.is_empty() && newlines == 0;
+
+            if rest.starts_with('}') {
+                if !nested {
+                    return Ok(out);
+                }
+                self.i += 1;
+                return Ok(out);
+            }
+            if rest.starts_with("//") || rest.starts_with("/*") {
+                let text = self.comment()?;
+                // A comment right after `{` belongs on the opening line.
+                let trailing = same_line || (out.is_empty() && nested && newlines == 0);
+                out.push(Node {
+                    kind: Kind::Comment { text, trailing },
+                    blank_lines,
+                    start,
+                    end: self.i,
+                });
+                continue;
+            }
+
+            let start_line = self.line();
+            let (text, end) = self.statement()?;
+            let text = text.trim().to_string();
+            let kind = match end {
+                Some('{') => Kind::Block {
+                    prelude: text,
+                    children: self.block(true)?,
+                },
+                _ => {
+                    if end == Some('}') {
+                        // Last declaration without `;`; let the block see the `}`.
+                        self.i -= 1;
+                    }
+                    if text.is_empty() {
+                        continue;
+                    }
+                    statement_kind(text)
+                }
+            };
+            if end.is_none() && nested {
+                bail!("unclosed block after line {}", start_line);
+            }
+            out.push(Node {
+                kind,
+                blank_lines,
+                start,
+                end: self.i,
+            });
+        }
+    }
+
+    /// Skips whitespace, returning how many newlines it crossed.
+    fn skip_ws(&mut self) -> usize {
+        let rest = &self.s[self.i..];
+        let trimmed = rest.trim_start();
+        let skipped = &rest[..rest.len() - trimmed.len()];
+        self.i += skipped.len();
+        skipped.matches('\n').count()
+    }
+
+    fn comment(&mut self) -> Result<String> {
+        let rest = &self.s[self.i..];
+        let len = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else {
+            match rest[2..].find("*/") {
+                Some(idx) => idx + 4,
+                None => bail!("unclosed comment at line {}", self.line()),
+            }
+        };
+        self.i += len;
+        Ok(rest[..len].trim_end().to_string())
+    }
+
+    /// Reads up to the next `;`, `{` or `}` outside strings, parentheses,
+    /// interpolation and comments, consuming the terminator.
+    fn statement(&mut self) -> Result<(String, Option<char>)> {
+        let bytes = self.s.as_bytes();
+        let start = self.i;
+        let mut depth = 0i32;
+        let mut i = self.i;
+        while i < bytes.len() {
+            let c = bytes[i];
+            match c {
+                b'"' | b'\'' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != c {
+                        if bytes[i] == b'\\' {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    i += self.s[i + 2..].find("*/").map(|n| n + 3).unwrap_or(bytes.len() - i);
+                }
+                // `url()` may hold `//` unquoted, as in `url(//cdn.x/y.png)`.
+                b'u' | b'U' if is_url_call(self.s, i) => {
+                    i = url_call_end(bytes, i + 4);
+                    continue;
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                    let eol = i + self.s[i..].find('\n').unwrap_or(bytes.len() - i);
+                    // Sass lets the last statement before a `}` skip its
+                    // `;`, so a line comment there ends it. Anywhere else,
+                    // selector lists included, the statement goes on and the
+                    // comment stays in its text.
+                    if depth == 0 && closes_block(self.s, eol) {
+                        self.i = i;
+                        return Ok((self.s[start..i].to_string(), Some(';')));
+                    }
+                    i = eol;
+                    continue;
+                }
+                b'#' if bytes.get(i + 1) == Some(&b'{') => {
+                    depth += 1;
+                    i += 1;
+                }
+                b'(' | b'[' => depth += 1,
+                b')' | b']' => depth -= 1,
+                // A custom property's value is raw tokens, braces included.
+                b'{' if self.s[start..i].trim_start().starts_with("--") && self.s[start..i].contains(':') => {
+                    depth += 1
+                }
+                b'}' if depth > 0 => depth -= 1,
+                b'{' | b';' | b'}' if depth == 0 => {
+                    self.i = i + 1;
+                    return Ok((self.s[start..i].to_string(), Some(c as char)));
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        self.i = bytes.len();
+        Ok((self.s[start..].to_string(), None))
+    }
+
+    fn line(&self) -> usize {
+        line_col(self.s, self.i).0
+    }
+}
+
+/// Whether `url(` starts at `at`, rather than inside a longer name.
+pub fn is_url_call(s: &str, at: usize) -> bool {
+    let before = s[..at].chars().next_back();
+    s.get(at..at + 4).is_some_and(|t| t.eq_ignore_ascii_case("url("))
+        && !before.is_some_and(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The offset just past the `)` closing a `url(` whose contents start at
+/// `i`, skipping quoted strings.
+pub fn url_call_end(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            b')' => return i + 1,
+            q @ (b'"' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != q {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'\\' => i += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Whether only whitespace and comments stand between `at` and a `}` or the
+/// end of the source.
+fn closes_block(s: &str, mut at: usize) -> bool {
+    loop {
+        let rest = s[at..].trim_start();
+        at = s.len() - rest.len();
+        if rest.starts_with("//") {
+            at += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(body) = rest.strip_prefix("/*") {
+            match body.find("*/") {
+                Some(end) => at += end + 4,
+                None => return true,
+            }
+        } else {
+            return rest.is_empty() || rest.starts_with('}');
+        }
+    }
+}
+
+/// 1-based line and column of byte offset `at`.
+pub fn line_col(src: &str, at: usize) -> (usize, usize) {
+    let before = &src[..at];
+    let line = before.matches('\n').count() + 1;
+    let col = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, col)
+}
+
+/// `font: {` and `margin: 0 {` are nested properties, not selectors.
+pub fn is_nested_property(prelude: &str) -> bool {
+    let Some((prop, value)) = prelude.split_once(':') else {
+        return false;
+    };
+    let is_prop = !prop.is_empty() && prop.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    // `a:hover` has no space after the colon; `font:` and `margin: 0` do, or
+    // nothing follows at all.
+    is_prop && (value.is_empty() || value.starts_with(char::is_whitespace))
+}
+
+fn statement_kind(text: String) -> Kind {
+    if text.starts_with('@') {
+        return Kind::Statement(text);
+    }
+    match split_decl(&text) {
+        Some((prop, value)) => Kind::Decl { prop, value },
+        None => Kind::Statement(text),
+    }
+}
+
+fn split_decl(text: &str) -> Option<(String, String)> {
+    let mut depth = 0i32;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ':' if depth == 0 => {
+                return Some((text[..idx].trim().to_string(), text[idx + 1..].trim().to_string()))
+            }
+            _ => {}
+        }
+    }
+    None
+}
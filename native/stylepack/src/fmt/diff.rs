@@ -0,0 +1,89 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Line-based unified diff of `old` against `new`, three lines of context.
+pub fn unified(path: &str, old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = edit_script(&a, &b);
+
+    let mut out = format!("--- {}\n+++ {} (formatted)\n", path, path);
+    let mut i = 0;
+    while i < ops.len() {
+        if ops[i].0 == ' ' {
+            i += 1;
+            continue;
+        }
+        // Grow the hunk while changes are within 2 * CONTEXT lines.
+        let start = i.saturating_sub(CONTEXT);
+        let mut last_change = i;
+        let mut k = i;
+        while k < ops.len() && k <= last_change + 2 * CONTEXT {
+            if ops[k].0 != ' ' {
+                last_change = k;
+            }
+            k += 1;
+        }
+        let end = (last_change + CONTEXT + 1).min(ops.len());
+
+        let (a_start, b_start) = (ops[start].1, ops[start].2);
+        let a_len = ops[start..end].iter().filter(|o| o.0 != '+').count();
+        let b_len = ops[start..end].iter().filter(|o| o.0 != '-').count();
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", a_start + 1, a_len, b_start + 1, b_len));
+        for (op, ai, bi) in &ops[start..end] {
+            let line = if *op == '+' { b[*bi] } else { a[*ai] };
+            out.push(*op);
+            out.push_str(line);
+            out.push('\n');
+        }
+        i = end;
+    }
+    out
+}
+
+const CONTEXT: usize = 3;
+
+/// `(op, index in a, index in b)` with op one of ' ', '-', '+', from a
+/// longest-common-subsequence table.
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<(char, usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::new();
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            ops.push((' ', i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', i, j));
+            i += 1;
+        } else {
+            ops.push(('+', i, j));
+            j += 1;
+        }
+    }
+    ops
+}
@@ -0,0 +1,187 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub mod cst;
+mod diff;
+mod print;
+
+pub use print::{Order, Quote, Style};
+
+const SKIP_DIRS: &[&str] = &["node_modules", ".git", "dist", "target", ".jen"];
+
+#[derive(clap::Args, Debug)]
+pub struct FmtArgs {
+    /// Files, or directories searched for `.scss` files.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Don't write; print a diff and fail if any file isn't formatted.
+    #[arg(long)]
+    check: bool,
+
+    #[arg(long, default_value_t = 2)]
+    indent: usize,
+
+    #[arg(long)]
+    use_tabs: bool,
+
+    #[arg(long, value_enum, default_value_t = Order::Preserve)]
+    order: Order,
+
+    #[arg(long, value_enum, default_value_t = Quote::Double)]
+    quote: Quote,
+
+    #[arg(long, default_value_t = 1)]
+    max_blank_lines: usize,
+
+    /// Don't force a blank line before nested rules and at-rule blocks.
+    #[arg(long)]
+    no_blank_before_blocks: bool,
+}
+
+pub fn run(args: &FmtArgs) -> Result<()> {
+    let style = Style {
+        indent: if args.use_tabs { "\t".to_string() } else { " ".repeat(args.indent) },
+        order: args.order,
+        quote: args.quote,
+        max_blank_lines: args.max_blank_lines,
+        blank_before_blocks: !args.no_blank_before_blocks,
+    };
+
+    let files = collect(&args.paths)?;
+    let mut changed = 0;
+    for path in &files {
+        let src = std::fs::read_to_string(path)
+            .with_context(|| format!("read: {}", path.display()))?;
+        let formatted = format(&src, &style).with_context(|| format!("parse: {}", path.display()))?;
+        if formatted == src {
+            continue;
+        }
+        changed += 1;
+        if args.check {
+            print!("{}", diff::unified(&path.display().to_string(), &src, &formatted));
+        } else {
+            std::fs::write(path, formatted).with_context(|| format!("write: {}", path.display()))?;
+        }
+    }
+
+    if args.check {
+        if changed > 0 {
+            eprintln!("stylepack fmt: {} of {} file(s) need formatting", changed, files.len());
+            std::process::exit(1);
+        }
+        eprintln!("stylepack fmt: {} file(s) formatted correctly", files.len());
+    } else {
+        eprintln!("stylepack fmt: formatted {} of {} file(s)", changed, files.len());
+    }
+    Ok(())
+}
+
+pub fn format(src: &str, style: &Style) -> Result<String> {
+    let nodes = cst::parse(src)?;
+    Ok(print::print(&nodes, style))
+}
+
+/// Files named in `paths`, plus the `.scss` files under the directories.
+pub fn collect(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    for root in paths {
+        if !root.exists() {
+            bail!("path does not exist: {}", root.display());
+        }
+        if root.is_file() {
+            out.push(root.clone());
+            continue;
+        }
+        let walker = WalkDir::new(root).sort_by_file_name().into_iter().filter_entry(|e| {
+            e.depth() == 0 || !SKIP_DIRS.contains(&e.file_name().to_string_lossy().as_ref())
+        });
+        for entry in walker {
+            let entry = entry?;
+            if entry.file_type().is_file() && is_scss(entry.path()) {
+                out.push(entry.into_path());
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn is_scss(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == "scss")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style() -> Style {
+        Style {
+            indent: "  ".to_string(),
+            order: Order::Preserve,
+            quote: Quote::Double,
+            max_blank_lines: 1,
+            blank_before_blocks: true,
+        }
+    }
+
+    fn fmt(src: &str) -> String {
+        format(src, &style()).unwrap()
+    }
+
+    #[test]
+    fn line_comment_in_selector_list_stays_on_its_line() {
+        let src = ".a, // first\n.b {\n  color: red;\n}\n";
+        assert_eq!(fmt(src), src);
+        assert_eq!(fmt(".a, .b // last\n{\n  x: y;\n}\n"), ".a,\n.b { // last\n  x: y;\n}\n");
+    }
+
+    #[test]
+    fn line_comment_in_selector_may_hold_a_comma() {
+        assert_eq!(fmt(".a, // one, two\n.b { x: y; }\n"), ".a, // one, two\n.b {\n  x: y;\n}\n");
+    }
+
+    #[test]
+    fn protocol_relative_url() {
+        let src = ".a {\n  background: url(//cdn.x/y.png);\n  mask: URL( \"//cdn.x/m.svg\" );\n}\n";
+        assert_eq!(fmt(src), ".a {\n  background: url(//cdn.x/y.png);\n  mask: URL(\"//cdn.x/m.svg\");\n}\n");
+    }
+
+    #[test]
+    fn line_comment_ends_last_declaration_before_brace() {
+        assert_eq!(fmt(".a {\n  color: red // note\n}\n"), ".a {\n  color: red; // note\n}\n");
+    }
+
+    #[test]
+    fn idempotent() {
+        let cases = [
+            ".a, // first\n.b {\n  color: red; // why\n}\n",
+            ".c {\n  background: url(//cdn.x/y.png) // cdn\n}\n",
+            "@media screen // narrow\n{\n  .d { e: f; }\n}\n",
+            "$map: (\n  a: 1, // one\n  b: 2\n);\n",
+        ];
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../src/css/framework");
+        let files = collect(&[dir]).unwrap();
+        let sources = files.iter().map(|f| std::fs::read_to_string(f).unwrap());
+        for src in cases.into_iter().map(str::to_string).chain(sources) {
+            let once = fmt(&src);
+            assert_eq!(fmt(&once), once, "not idempotent for:\n{}", src);
+        }
+    }
+}
@@ -0,0 +1,569 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use clap::ValueEnum;
+
+use super::cst::{is_nested_property, is_url_call, url_call_end, Kind, Node};
+use crate::css::split_top_level;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Order {
+    /// Keep declarations as written.
+    Preserve,
+    Alphabetical,
+    /// Positioning, box model, typography, visual, then everything else.
+    Grouped,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Quote {
+    Preserve,
+    Double,
+    Single,
+}
+
+#[derive(Clone, Debug)]
+pub struct Style {
+    pub indent: String,
+    pub order: Order,
+    pub quote: Quote,
+    /// Longest run of blank lines kept from the source.
+    pub max_blank_lines: usize,
+    /// Always separate a block from the node before it with a blank line.
+    pub blank_before_blocks: bool,
+}
+
+/// Property order for `Order::Grouped`. Longhands not listed sort with the
+/// nearest listed shorthand, e.g. `border-top-left-radius` with `border-top`.
+const GROUPED: &[&str] = &[
+    "position", "inset", "top", "right", "bottom", "left", "z-index",
+    "display", "flex", "flex-direction", "flex-wrap", "flex-flow", "flex-grow", "flex-shrink",
+    "flex-basis", "grid", "grid-template", "grid-template-columns", "grid-template-rows",
+    "grid-template-areas", "grid-area", "grid-column", "grid-row", "gap", "row-gap", "column-gap",
+    "place-items", "place-content", "align-items", "align-content", "align-self", "justify-items",
+    "justify-content", "justify-self", "order", "float", "clear",
+    "box-sizing", "width", "min-width", "max-width", "height", "min-height", "max-height",
+    "aspect-ratio", "margin", "margin-block", "margin-inline", "margin-top", "margin-right",
+    "margin-bottom", "margin-left", "padding", "padding-block", "padding-inline", "padding-top",
+    "padding-right", "padding-bottom", "padding-left", "overflow", "overflow-x", "overflow-y",
+    "font", "font-family", "font-size", "font-style", "font-weight", "line-height",
+    "letter-spacing", "text-align", "text-decoration", "text-transform", "text-overflow",
+    "white-space", "word-break", "vertical-align", "list-style",
+    "color", "background", "background-color", "background-image", "background-position",
+    "background-size", "background-repeat", "border", "border-width", "border-style",
+    "border-color", "border-top", "border-right", "border-bottom", "border-left",
+    "border-radius", "outline", "outline-offset", "box-shadow", "opacity", "visibility",
+    "filter", "backdrop-filter",
+    "transform", "transform-origin", "transition", "animation", "cursor", "pointer-events",
+    "user-select", "content",
+];
+
+pub fn print(nodes: &[Node], style: &Style) -> String {
+    let mut out = String::new();
+    write_nodes(nodes, 0, style, &mut out);
+    out
+}
+
+fn write_nodes(nodes: &[Node], level: usize, style: &Style, out: &mut String) {
+    let nodes = reorder(nodes, style.order);
+    let indent = style.indent.repeat(level);
+    let forced = forced_blanks(&nodes, style);
+
+    for (i, node) in nodes.iter().enumerate() {
+        if let Kind::Comment { text, trailing: true } = &node.kind {
+            if i > 0 {
+                out.pop();
+                out.push(' ');
+                out.push_str(text);
+                out.push('\n');
+                continue;
+            }
+        }
+        if let Kind::Block { prelude, .. } = &node.kind {
+            let follows_block = i > 0 && matches!(nodes[i - 1].kind, Kind::Block { .. });
+            if follows_block && prelude.starts_with("@else") {
+                out.pop();
+                out.push(' ');
+                write_block(node, &indent, "", level, style, out);
+                continue;
+            }
+        }
+
+        if i > 0 {
+            let blanks = node.blank_lines.min(style.max_blank_lines);
+            let blanks = if forced[i] { blanks.max(1) } else { blanks };
+            for _ in 0..blanks {
+                out.push('\n');
+            }
+        }
+        match &node.kind {
+            Kind::Decl { prop, value } => {
+                out.push_str(&indent);
+                out.push_str(prop);
+                out.push(':');
+                let value = if prop.starts_with("--") {
+                    value.trim().to_string()
+                } else {
+                    format_value(value, level, style)
+                };
+                if !value.is_empty() {
+                    out.push(' ');
+                    out.push_str(&value);
+                }
+                out.push_str(";\n");
+            }
+            Kind::Statement(text) => {
+                out.push_str(&indent);
+                out.push_str(&format_value(text, level, style));
+                out.push_str(";\n");
+            }
+            Kind::Comment { text, .. } => {
+                out.push_str(&indent);
+                out.push_str(text);
+                out.push('\n');
+            }
+            Kind::Block { .. } => write_block(node, &indent, &indent, level, style, out),
+        }
+    }
+}
+
+fn write_block(node: &Node, indent: &str, first_indent: &str, level: usize, style: &Style, out: &mut String) {
+    let Kind::Block { prelude, children } = &node.kind else {
+        return;
+    };
+    out.push_str(first_indent);
+    // Line comments inside the prelude trail the selector they follow; any
+    // left over move after the `{`.
+    let mut open_comments = Vec::new();
+    if prelude.starts_with('@') || is_nested_property(prelude) {
+        let (code, comments) = take_line_comments(prelude);
+        out.push_str(&normalize(&code, style.quote));
+        open_comments = comments;
+    } else {
+        let selectors = split_selectors(prelude);
+        for (k, (sel, comments)) in selectors.iter().enumerate() {
+            out.push_str(&format_selector(sel, style.quote));
+            if k + 1 == selectors.len() {
+                open_comments = comments.clone();
+                break;
+            }
+            out.push(',');
+            for c in comments {
+                out.push(' ');
+                out.push_str(c);
+            }
+            out.push('\n');
+            out.push_str(indent);
+        }
+    }
+    let mut rest = &children[..];
+    if let Some(Node { kind: Kind::Comment { text, trailing: true }, .. }) = children.first() {
+        open_comments.push(text.clone());
+        rest = &children[1..];
+    }
+    if rest.is_empty() && open_comments.is_empty() {
+        out.push_str(" {}\n");
+        return;
+    }
+    out.push_str(" {");
+    for c in &open_comments {
+        out.push(' ');
+        out.push_str(c);
+    }
+    out.push('\n');
+    write_nodes(rest, level + 1, style, out);
+    out.push_str(indent);
+    out.push_str("}\n");
+}
+
+/// Splits a selector list on top-level commas, pairing each selector with
+/// the `//` comments on its line. A comment after a comma belongs to the
+/// selector before it.
+fn split_selectors(prelude: &str) -> Vec<(String, Vec<String>)> {
+    let mut out: Vec<(String, Vec<String>)> = vec![Default::default()];
+    let mut depth = 0i32;
+    let mut i = 0;
+    while i < prelude.len() {
+        let rest = &prelude[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        let len = match c {
+            '"' | '\'' => string_len(rest),
+            '/' if rest.starts_with("//") => {
+                let len = rest.find('\n').unwrap_or(rest.len());
+                let at = match out.last() {
+                    Some((sel, _)) if sel.trim().is_empty() && out.len() > 1 => out.len() - 2,
+                    _ => out.len() - 1,
+                };
+                out[at].1.push(rest[..len].trim_end().to_string());
+                out.last_mut().unwrap().0.push(' ');
+                i += len;
+                continue;
+            }
+            'u' | 'U' if is_url_call(prelude, i) => url_call_end(prelude.as_bytes(), i + 4) - i,
+            '(' | '[' => {
+                depth += 1;
+                1
+            }
+            ')' | ']' => {
+                depth -= 1;
+                1
+            }
+            ',' if depth == 0 => {
+                out.push(Default::default());
+                i += 1;
+                continue;
+            }
+            _ => c.len_utf8(),
+        };
+        out.last_mut().unwrap().0.push_str(&rest[..len]);
+        i += len;
+    }
+    out.retain(|(sel, comments)| !sel.trim().is_empty() || !comments.is_empty());
+    out
+}
+
+/// `s` with its `//` comments blanked out, and the comments.
+fn take_line_comments(s: &str) -> (String, Vec<String>) {
+    let mut code = String::with_capacity(s.len());
+    let mut comments = Vec::new();
+    let mut i = 0;
+    while i < s.len() {
+        let rest = &s[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        let len = match c {
+            '"' | '\'' => string_len(rest),
+            'u' | 'U' if is_url_call(s, i) => url_call_end(s.as_bytes(), i + 4) - i,
+            '/' if rest.starts_with("//") => {
+                let len = rest.find('\n').unwrap_or(rest.len());
+                comments.push(rest[..len].trim_end().to_string());
+                code.push(' ');
+                i += len;
+                continue;
+            }
+            _ => c.len_utf8(),
+        };
+        code.push_str(&rest[..len]);
+        i += len;
+    }
+    (code, comments)
+}
+
+/// Length of the quoted string `s` starts with, quotes included.
+fn string_len(s: &str) -> usize {
+    let q = s.as_bytes()[0];
+    let mut i = 1;
+    while i < s.len() && s.as_bytes()[i] != q {
+        if s.as_bytes()[i] == b'\\' {
+            i += 1;
+        }
+        i += 1;
+    }
+    (i + 1).min(s.len())
+}
+
+/// Which nodes get a blank line regardless of the source: blocks, together
+/// with any comments directly above them.
+fn forced_blanks(nodes: &[Node], style: &Style) -> Vec<bool> {
+    let mut forced = vec![false; nodes.len()];
+    if !style.blank_before_blocks {
+        return forced;
+    }
+    for i in 1..nodes.len() {
+        let Kind::Block { prelude, .. } = &nodes[i].kind else {
+            continue;
+        };
+        if prelude.starts_with("@else") {
+            continue;
+        }
+        let mut j = i;
+        while j > 0
+            && nodes[j].blank_lines == 0
+            && matches!(nodes[j - 1].kind, Kind::Comment { trailing: false, .. })
+        {
+            j -= 1;
+        }
+        if j > 0 {
+            forced[j] = true;
+        }
+    }
+    forced
+}
+
+/// Sorts each run of plain declarations not broken up by a blank line,
+/// comment, nested rule or other statement. Trailing comments travel with
+/// their declaration.
+fn reorder(nodes: &[Node], order: Order) -> Vec<Node> {
+    if order == Order::Preserve {
+        return nodes.to_vec();
+    }
+    let mut units: Vec<Vec<Node>> = Vec::new();
+    for n in nodes {
+        match (&n.kind, units.last_mut()) {
+            (Kind::Comment { trailing: true, .. }, Some(last)) => last.push(n.clone()),
+            _ => units.push(vec![n.clone()]),
+        }
+    }
+
+    let sortable = |u: &Vec<Node>| match &u[0].kind {
+        Kind::Decl { prop, .. } => !prop.starts_with('$') && !prop.starts_with("--"),
+        _ => false,
+    };
+    let mut out = Vec::with_capacity(nodes.len());
+    let mut i = 0;
+    while i < units.len() {
+        let mut j = i + 1;
+        if sortable(&units[i]) {
+            while j < units.len() && sortable(&units[j]) && units[j][0].blank_lines == 0 {
+                j += 1;
+            }
+        }
+        let run = &mut units[i..j];
+        let lead_blanks = run[0][0].blank_lines;
+        run.sort_by_key(|u| match &u[0].kind {
+            Kind::Decl { prop, .. } => sort_key(prop, order),
+            _ => (0, String::new()),
+        });
+        run[0][0].blank_lines = lead_blanks;
+        for u in run.iter_mut().skip(1) {
+            u[0].blank_lines = 0;
+        }
+        out.extend(run.iter().flatten().cloned());
+        i = j;
+    }
+    out
+}
+
+fn sort_key(prop: &str, order: Order) -> (usize, String) {
+    let prop = prop.to_ascii_lowercase();
+    let bare = strip_vendor(&prop).to_string();
+    match order {
+        Order::Grouped => {
+            let idx = GROUPED.iter().position(|p| *p == bare).or_else(|| {
+                GROUPED
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| bare.starts_with(&format!("{}-", p)))
+                    .max_by_key(|(_, p)| p.len())
+                    .map(|(i, _)| i)
+            });
+            match idx {
+                Some(i) => (i, String::new()),
+                None => (GROUPED.len(), String::new()),
+            }
+        }
+        _ => (0, bare),
+    }
+}
+
+fn strip_vendor(prop: &str) -> &str {
+    for prefix in ["-webkit-", "-moz-", "-ms-", "-o-"] {
+        if let Some(rest) = prop.strip_prefix(prefix) {
+            return rest;
+        }
+    }
+    prop
+}
+
+/// Values that the author spread over several lines, typically maps, get
+/// one entry per line; everything else is normalized onto one line.
+fn format_value(value: &str, level: usize, style: &Style) -> String {
+    let value = value.trim();
+    if !value.contains('\n') {
+        return normalize(value, style.quote);
+    }
+    let inner = style.indent.repeat(level + 1);
+    let outer = style.indent.repeat(level);
+
+    if value.contains("//") || !value.starts_with('(') {
+        // Can't safely split entries around line comments; re-indent lines.
+        let mut out = String::new();
+        for (i, line) in value.lines().map(str::trim).filter(|l| !l.is_empty()).enumerate() {
+            if i > 0 {
+                out.push('\n');
+                out.push_str(if line.starts_with(')') { &outer } else { &inner });
+            }
+            out.push_str(&normalize(line, style.quote));
+        }
+        return out;
+    }
+
+    let close = matching_paren(value).unwrap_or(value.len() - 1);
+    let entries = split_top_level(&value[1..close], ',');
+    let tail = value[close + 1..].trim();
+    let mut out = String::from("(\n");
+    for (i, e) in entries.iter().enumerate() {
+        out.push_str(&inner);
+        out.push_str(&format_value(e, level + 1, style));
+        if i + 1 < entries.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    out.push_str(&outer);
+    out.push(')');
+    if !tail.is_empty() {
+        out.push(' ');
+        out.push_str(&normalize(tail, style.quote));
+    }
+    out
+}
+
+fn matching_paren(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn format_selector(sel: &str, quote: Quote) -> String {
+    let sel = normalize(sel, quote);
+    // Space the combinators, outside parentheses and attribute brackets.
+    let mut out = String::with_capacity(sel.len());
+    let mut depth = 0;
+    let mut in_str = None;
+    for c in sel.chars() {
+        if let Some(q) = in_str {
+            if c == q {
+                in_str = None;
+            }
+            out.push(c);
+            continue;
+        }
+        match c {
+            '"' | '\'' => in_str = Some(c),
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '>' | '+' | '~' if depth == 0 => {
+                while out.ends_with(' ') {
+                    out.pop();
+                }
+                if !out.is_empty() {
+                    out.push(' ');
+                }
+                out.push(c);
+                out.push(' ');
+                continue;
+            }
+            ' ' if out.ends_with(' ') => continue,
+            _ => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Collapses whitespace and spaces commas outside strings and comments, and
+/// applies the quote style.
+fn normalize(s: &str, quote: Quote) -> String {
+    let chars: Vec<char> = s.trim().chars().collect();
+    let mut out = String::with_capacity(s.len());
+    let mut i = 0;
+    let mut space = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' || c == '\'' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            let end = (i + 1).min(chars.len());
+            if space {
+                out.push(' ');
+                space = false;
+            }
+            out.push_str(&requote(&chars[start..end].iter().collect::<String>(), quote));
+            i = end;
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            let start = i;
+            i += 2;
+            while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                i += 1;
+            }
+            let end = (i + 2).min(chars.len());
+            if space {
+                out.push(' ');
+                space = false;
+            }
+            out.extend(&chars[start..end]);
+            i = end;
+            continue;
+        }
+        match c {
+            _ if c.is_whitespace() => space = !out.is_empty() && !out.ends_with('('),
+            ',' => {
+                out.push(',');
+                space = true;
+            }
+            ')' => {
+                space = false;
+                out.push(')');
+            }
+            _ => {
+                if space {
+                    out.push(' ');
+                }
+                space = false;
+                out.push(c);
+                if c == '!' {
+                    // `! important` -> `!important`
+                    while chars.get(i + 1).is_some_and(|n| n.is_whitespace()) {
+                        i += 1;
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Switches a string literal to the preferred quote when its content
+/// doesn't contain either quote character.
+fn requote(lit: &str, quote: Quote) -> String {
+    let want = match quote {
+        Quote::Preserve => return lit.to_string(),
+        Quote::Double => '"',
+        Quote::Single => '\'',
+    };
+    if lit.len() < 2 || lit.starts_with(want) {
+        return lit.to_string();
+    }
+    let body = &lit[1..lit.len() - 1];
+    if body.contains(['"', '\'', '\\']) {
+        return lit.to_string();
+    }
+    format!("{}{}{}", want, body, want)
+}
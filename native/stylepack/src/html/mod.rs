@@ -0,0 +1,251 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+mod select;
+
+pub use select::{parse_selector, Selector};
+
+#[derive(Clone, Debug)]
+pub struct Element {
+    pub tag: String,
+    pub attrs: Vec<(String, String)>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.attr("id")
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.attr("class")
+            .map(|c| c.split_whitespace().any(|x| x == class))
+            .unwrap_or(false)
+    }
+}
+
+/// A parsed HTML document. Elements are stored in document order, so an
+/// element's index doubles as its position on the page.
+#[derive(Clone, Debug, Default)]
+pub struct Document {
+    pub elements: Vec<Element>,
+}
+
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+const RAW_TEXT: &[&str] = &["script", "style", "textarea", "title"];
+
+impl Document {
+    pub fn parse(src: &str) -> Document {
+        let mut doc = Document::default();
+        let mut stack: Vec<usize> = Vec::new();
+        let bytes = src.as_bytes();
+        let mut i = 0usize;
+
+        while i < bytes.len() {
+            if bytes[i] != b'<' {
+                i += 1;
+                continue;
+            }
+            if src[i..].starts_with("<!--") {
+                i = src[i..].find("-->").map(|e| i + e + 3).unwrap_or(bytes.len());
+                continue;
+            }
+            if src[i..].starts_with("<!") || src[i..].starts_with("<?") {
+                i = src[i..].find('>').map(|e| i + e + 1).unwrap_or(bytes.len());
+                continue;
+            }
+            if src[i..].starts_with("</") {
+                let end = src[i..].find('>').map(|e| i + e).unwrap_or(bytes.len());
+                let name = src[i + 2..end].trim().to_ascii_lowercase();
+                if let Some(pos) = stack.iter().rposition(|&e| doc.elements[e].tag == name) {
+                    stack.truncate(pos);
+                }
+                i = end + 1;
+                continue;
+            }
+            if !bytes.get(i + 1).is_some_and(|b| b.is_ascii_alphabetic()) {
+                i += 1;
+                continue;
+            }
+
+            let (tag, attrs, self_closing, next) = read_tag(src, i + 1);
+            i = next;
+            let idx = doc.elements.len();
+            let parent = stack.last().copied();
+            doc.elements.push(Element {
+                tag: tag.clone(),
+                attrs,
+                parent,
+                children: Vec::new(),
+            });
+            if let Some(p) = parent {
+                doc.elements[p].children.push(idx);
+            }
+
+            if RAW_TEXT.contains(&tag.as_str()) {
+                let close = format!("</{}", tag);
+                let lower = src[i..].to_ascii_lowercase();
+                i = lower.find(&close).map(|e| i + e).unwrap_or(bytes.len());
+                continue;
+            }
+            if !self_closing && !VOID.contains(&tag.as_str()) {
+                stack.push(idx);
+            }
+        }
+        doc
+    }
+
+    /// Index of the first element matched by `sel`, if any.
+    pub fn first_match(&self, sel: &Selector) -> Option<usize> {
+        (0..self.elements.len()).find(|&i| sel.matches(self, i))
+    }
+}
+
+fn read_tag(src: &str, start: usize) -> (String, Vec<(String, String)>, bool, usize) {
+    let bytes = src.as_bytes();
+    let mut i = start;
+    while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' && bytes[i] != b'/'
+    {
+        i += 1;
+    }
+    let tag = src[start..i].to_ascii_lowercase();
+    let mut attrs = Vec::new();
+    let mut self_closing = false;
+
+    loop {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i >= bytes.len() {
+            break;
+        }
+        match bytes[i] {
+            b'>' => {
+                i += 1;
+                break;
+            }
+            b'/' => {
+                self_closing = true;
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let name_start = i;
+        while i < bytes.len()
+            && !bytes[i].is_ascii_whitespace()
+            && !matches!(bytes[i], b'=' | b'>' | b'/')
+        {
+            i += 1;
+        }
+        let name = src[name_start..i].to_ascii_lowercase();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let mut value = String::new();
+        if i < bytes.len() && bytes[i] == b'=' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i < bytes.len() && (bytes[i] == b'"' || bytes[i] == b'\'') {
+                let q = bytes[i];
+                let vstart = i + 1;
+                i = vstart;
+                while i < bytes.len() && bytes[i] != q {
+                    i += 1;
+                }
+                value = src[vstart..i.min(bytes.len())].to_string();
+                i += 1;
+            } else {
+                let vstart = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
+                value = src[vstart..i].to_string();
+            }
+        }
+        if !name.is_empty() {
+            attrs.push((name, value));
+        }
+    }
+    (tag, attrs, self_closing, i.min(bytes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!doctype html>
+<html><body>
+  <!-- <p id="commented"> -->
+  <header id="top" class="site dark"><nav><a id="home" href="/" lang="en-US">Home</a></nav></header>
+  <script>if (a < b) { document.write("<p id='script'>") }</script>
+  <main><p id="intro">Hi<br>there</p><p id="second" data-x="A b"></p><img id="pic" src="x.png"><div id="empty"></div></main>
+</body></html>"#;
+
+    fn first(sel: &str) -> Option<String> {
+        let doc = Document::parse(PAGE);
+        let sel = parse_selector(sel).unwrap();
+        doc.first_match(&sel).map(|i| doc.elements[i].id().unwrap_or(&doc.elements[i].tag).to_string())
+    }
+
+    #[test]
+    fn parses_tree_skipping_comments_and_raw_text() {
+        let doc = Document::parse(PAGE);
+        let ids: Vec<&str> = doc.elements.iter().filter_map(Element::id).collect();
+        assert_eq!(ids, ["top", "home", "intro", "second", "pic", "empty"]);
+        let main = doc.elements.iter().position(|e| e.tag == "main").unwrap();
+        let children: Vec<&str> = doc.elements[main].children.iter().filter_map(|&c| doc.elements[c].id()).collect();
+        assert_eq!(children, ["intro", "second", "pic", "empty"]);
+    }
+
+    #[test]
+    fn combinators() {
+        assert_eq!(first("header a").as_deref(), Some("home"));
+        assert_eq!(first("header > a"), None);
+        assert_eq!(first("p + p").as_deref(), Some("second"));
+        assert_eq!(first("p ~ img").as_deref(), Some("pic"));
+        assert_eq!(first("main > br"), None);
+        assert_eq!(first("p > br"), Some("br".to_string()));
+    }
+
+    #[test]
+    fn attributes_and_pseudos() {
+        assert_eq!(first(".site.dark#top").as_deref(), Some("top"));
+        assert_eq!(first("[lang|=en]").as_deref(), Some("home"));
+        assert_eq!(first("[data-x~=b]").as_deref(), Some("second"));
+        assert_eq!(first("[data-x=\"a b\" i]").as_deref(), Some("second"));
+        assert_eq!(first("[src$=\".png\"]").as_deref(), Some("pic"));
+        assert_eq!(first("p:not(#intro)").as_deref(), Some("second"));
+        assert_eq!(first("main:has(img)"), Some("main".to_string()));
+        assert_eq!(first("div:empty").as_deref(), Some("empty"));
+        assert_eq!(first("main > :last-child").as_deref(), Some("empty"));
+        assert_eq!(first("a:hover").as_deref(), Some("home"));
+        assert_eq!(first(":root"), Some("html".to_string()));
+    }
+}
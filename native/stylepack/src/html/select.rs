@@ -0,0 +1,408 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::Document;
+use crate::css::split_top_level;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+    Adjacent,
+    Sibling,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum AttrOp {
+    Exists,
+    Equals,
+    Includes,
+    DashMatch,
+    Prefix,
+    Suffix,
+    Substring,
+}
+
+#[derive(Clone, Debug)]
+struct AttrSel {
+    name: String,
+    op: AttrOp,
+    value: String,
+    ci: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Pseudo {
+    Not(Vec<Selector>),
+    Is(Vec<Selector>),
+    Has(Vec<Selector>),
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    /// State and structural pseudo-classes we can't evaluate statically
+    /// (`:hover`, `:nth-child(...)`, pseudo-elements) match optimistically.
+    Any,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Compound {
+    tag: Option<String>,
+    ids: Vec<String>,
+    classes: Vec<String>,
+    attrs: Vec<AttrSel>,
+    pseudos: Vec<Pseudo>,
+}
+
+/// A complex selector, stored right to left: `parts[0]` is the subject and
+/// each following entry is reached from the previous one via its combinator.
+#[derive(Clone, Debug)]
+pub struct Selector {
+    parts: Vec<(Compound, Combinator)>,
+}
+
+pub fn parse_selector(s: &str) -> Option<Selector> {
+    let mut compounds: Vec<Compound> = Vec::new();
+    let mut combinators: Vec<Combinator> = Vec::new();
+    let chars: Vec<char> = s.trim().chars().collect();
+    let mut i = 0usize;
+    let mut pending: Option<Combinator> = None;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            if !compounds.is_empty() && pending.is_none() {
+                pending = Some(Combinator::Descendant);
+            }
+            i += 1;
+            continue;
+        }
+        if matches!(c, '>' | '+' | '~') {
+            pending = Some(match c {
+                '>' => Combinator::Child,
+                '+' => Combinator::Adjacent,
+                _ => Combinator::Sibling,
+            });
+            i += 1;
+            continue;
+        }
+        let (compound, next) = parse_compound(&chars, i)?;
+        if next == i {
+            return None;
+        }
+        i = next;
+        if !compounds.is_empty() {
+            combinators.push(pending.take().unwrap_or(Combinator::Descendant));
+        }
+        pending = None;
+        compounds.push(compound);
+    }
+
+    if compounds.is_empty() {
+        return None;
+    }
+    let mut parts = Vec::with_capacity(compounds.len());
+    let mut combs = combinators.into_iter().rev();
+    for compound in compounds.into_iter().rev() {
+        parts.push((compound, combs.next().unwrap_or(Combinator::Descendant)));
+    }
+    Some(Selector { parts })
+}
+
+fn parse_compound(chars: &[char], mut i: usize) -> Option<(Compound, usize)> {
+    let mut c = Compound::default();
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            '*' => i += 1,
+            '#' | '.' => {
+                let (name, next) = read_name(chars, i + 1);
+                if name.is_empty() {
+                    return None;
+                }
+                if ch == '#' {
+                    c.ids.push(name);
+                } else {
+                    c.classes.push(name);
+                }
+                i = next;
+            }
+            '[' => {
+                let end = find_close(chars, i, '[', ']')?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                c.attrs.push(parse_attr(&inner)?);
+                i = end + 1;
+            }
+            ':' => {
+                let element = chars.get(i + 1) == Some(&':');
+                let start = if element { i + 2 } else { i + 1 };
+                let (name, mut next) = read_name(chars, start);
+                let mut arg = None;
+                if chars.get(next) == Some(&'(') {
+                    let end = find_close(chars, next, '(', ')')?;
+                    arg = Some(chars[next + 1..end].iter().collect::<String>());
+                    next = end + 1;
+                }
+                let name = name.to_ascii_lowercase();
+                let pseudo = if element {
+                    Pseudo::Any
+                } else {
+                    match (name.as_str(), arg) {
+                        ("not", Some(a)) => Pseudo::Not(parse_list(&a)?),
+                        ("is" | "where" | "matches" | "-webkit-any" | "-moz-any", Some(a)) => {
+                            Pseudo::Is(parse_list(&a)?)
+                        }
+                        ("has", Some(a)) => Pseudo::Has(parse_list(&a)?),
+                        ("root", _) => Pseudo::Root,
+                        ("empty", _) => Pseudo::Empty,
+                        ("first-child" | "first-of-type", _) => Pseudo::FirstChild,
+                        ("last-child" | "last-of-type", _) => Pseudo::LastChild,
+                        ("only-child" | "only-of-type", _) => Pseudo::OnlyChild,
+                        _ => Pseudo::Any,
+                    }
+                };
+                c.pseudos.push(pseudo);
+                i = next;
+            }
+            _ if is_name_char(ch) => {
+                let (name, next) = read_name(chars, i);
+                c.tag = Some(name.to_ascii_lowercase());
+                i = next;
+            }
+            _ => break,
+        }
+    }
+    Some((c, i))
+}
+
+fn parse_list(s: &str) -> Option<Vec<Selector>> {
+    split_top_level(s, ',')
+        .iter()
+        .map(|x| parse_selector(x.trim_start_matches(['>', '+', '~']).trim()))
+        .collect()
+}
+
+fn parse_attr(inner: &str) -> Option<AttrSel> {
+    let inner = inner.trim();
+    let ops = [
+        ("~=", AttrOp::Includes),
+        ("|=", AttrOp::DashMatch),
+        ("^=", AttrOp::Prefix),
+        ("$=", AttrOp::Suffix),
+        ("*=", AttrOp::Substring),
+        ("=", AttrOp::Equals),
+    ];
+    for (tok, op) in ops {
+        if let Some(idx) = inner.find(tok) {
+            let name = inner[..idx].trim().to_ascii_lowercase();
+            let mut rest = inner[idx + tok.len()..].trim();
+            let mut ci = false;
+            if rest.ends_with(" i") || rest.ends_with(" I") {
+                ci = true;
+                rest = rest[..rest.len() - 2].trim();
+            } else if rest.ends_with(" s") || rest.ends_with(" S") {
+                rest = rest[..rest.len() - 2].trim();
+            }
+            let value = rest.trim_matches(|c| c == '"' || c == '\'').to_string();
+            return Some(AttrSel { name, op, value, ci });
+        }
+    }
+    Some(AttrSel {
+        name: inner.to_ascii_lowercase(),
+        op: AttrOp::Exists,
+        value: String::new(),
+        ci: false,
+    })
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == '\\' || !c.is_ascii()
+}
+
+fn read_name(chars: &[char], mut i: usize) -> (String, usize) {
+    let mut out = String::new();
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && i + 1 < chars.len() {
+            out.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if !is_name_char(c) {
+            break;
+        }
+        out.push(c);
+        i += 1;
+    }
+    (out, i)
+}
+
+fn find_close(chars: &[char], open_at: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut in_str: Option<char> = None;
+    for (j, &c) in chars.iter().enumerate().skip(open_at) {
+        if let Some(q) = in_str {
+            if c == q {
+                in_str = None;
+            }
+            continue;
+        }
+        if c == '"' || c == '\'' {
+            in_str = Some(c);
+        } else if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(j);
+            }
+        }
+    }
+    None
+}
+
+impl Selector {
+    fn is_dynamic(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|(c, _)| c.pseudos.iter().any(|p| matches!(p, Pseudo::Any)))
+    }
+
+    pub fn matches(&self, doc: &Document, idx: usize) -> bool {
+        self.match_from(doc, idx, 0)
+    }
+
+    fn match_from(&self, doc: &Document, idx: usize, part: usize) -> bool {
+        let (compound, comb) = &self.parts[part];
+        if !compound.matches(doc, idx) {
+            return false;
+        }
+        if part + 1 == self.parts.len() {
+            return true;
+        }
+        let el = &doc.elements[idx];
+        match comb {
+            Combinator::Child => el
+                .parent
+                .is_some_and(|p| self.match_from(doc, p, part + 1)),
+            Combinator::Descendant => {
+                let mut cur = el.parent;
+                while let Some(p) = cur {
+                    if self.match_from(doc, p, part + 1) {
+                        return true;
+                    }
+                    cur = doc.elements[p].parent;
+                }
+                false
+            }
+            Combinator::Adjacent => {
+                previous_siblings(doc, idx)
+                    .next()
+                    .is_some_and(|s| self.match_from(doc, s, part + 1))
+            }
+            Combinator::Sibling => {
+                previous_siblings(doc, idx).any(|s| self.match_from(doc, s, part + 1))
+            }
+        }
+    }
+}
+
+fn previous_siblings(doc: &Document, idx: usize) -> impl Iterator<Item = usize> + '_ {
+    let siblings: &[usize] = match doc.elements[idx].parent {
+        Some(p) => &doc.elements[p].children,
+        None => &[],
+    };
+    let pos = siblings.iter().position(|&s| s == idx).unwrap_or(0);
+    siblings[..pos].iter().rev().copied()
+}
+
+impl Compound {
+    fn matches(&self, doc: &Document, idx: usize) -> bool {
+        let el = &doc.elements[idx];
+        if let Some(t) = &self.tag {
+            if *t != el.tag {
+                return false;
+            }
+        }
+        if !self.ids.iter().all(|id| el.id() == Some(id.as_str())) {
+            return false;
+        }
+        if !self.classes.iter().all(|c| el.has_class(c)) {
+            return false;
+        }
+        if !self.attrs.iter().all(|a| a.matches(el.attr(&a.name))) {
+            return false;
+        }
+        self.pseudos.iter().all(|p| p.matches(doc, idx))
+    }
+}
+
+impl AttrSel {
+    fn matches(&self, actual: Option<&str>) -> bool {
+        let Some(actual) = actual else {
+            return false;
+        };
+        let (a, v) = if self.ci {
+            (actual.to_lowercase(), self.value.to_lowercase())
+        } else {
+            (actual.to_string(), self.value.clone())
+        };
+        match self.op {
+            AttrOp::Exists => true,
+            AttrOp::Equals => a == v,
+            AttrOp::Includes => a.split_whitespace().any(|x| x == v),
+            AttrOp::DashMatch => a == v || a.starts_with(&format!("{}-", v)),
+            AttrOp::Prefix => !v.is_empty() && a.starts_with(&v),
+            AttrOp::Suffix => !v.is_empty() && a.ends_with(&v),
+            AttrOp::Substring => !v.is_empty() && a.contains(&v),
+        }
+    }
+}
+
+impl Pseudo {
+    fn matches(&self, doc: &Document, idx: usize) -> bool {
+        let el = &doc.elements[idx];
+        let siblings = || -> &[usize] {
+            match el.parent {
+                Some(p) => &doc.elements[p].children,
+                None => &[],
+            }
+        };
+        match self {
+            Pseudo::Not(list) => !list
+                .iter()
+                .any(|s| !s.is_dynamic() && s.matches(doc, idx)),
+            Pseudo::Is(list) => list.iter().any(|s| s.matches(doc, idx)),
+            Pseudo::Has(list) => {
+                let mut stack = el.children.clone();
+                while let Some(c) = stack.pop() {
+                    if list.iter().any(|s| s.matches(doc, c)) {
+                        return true;
+                    }
+                    stack.extend(doc.elements[c].children.iter().copied());
+                }
+                false
+            }
+            Pseudo::Root => el.parent.is_none() && el.tag == "html",
+            Pseudo::Empty => el.children.is_empty(),
+            Pseudo::FirstChild => siblings().first().is_none_or(|&s| s == idx),
+            Pseudo::LastChild => siblings().last().is_none_or(|&s| s == idx),
+            Pseudo::OnlyChild => siblings().len() <= 1,
+            Pseudo::Any => true,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The SCSS compiler and CSS tooling behind the `stylepack` binary.
+//!
+//! [`scss`], [`css`] and [`transform`] are the supported library surface;
+//! the remaining modules back the CLI and may change between releases.
+
+use anyhow::Result;
+use std::path::Path;
+
+pub mod css;
+pub mod scss;
+pub mod transform;
+
+#[doc(hidden)]
+pub mod aliases;
+#[doc(hidden)]
+pub mod assets;
+#[doc(hidden)]
+pub mod critical;
+#[doc(hidden)]
+pub mod deps;
+#[doc(hidden)]
+pub mod fmt;
+#[doc(hidden)]
+pub mod html;
+#[doc(hidden)]
+pub mod lint;
+#[doc(hidden)]
+pub mod lower;
+#[doc(hidden)]
+pub mod modules;
+#[doc(hidden)]
+pub mod prefix;
+#[doc(hidden)]
+pub mod purge;
+#[doc(hidden)]
+pub mod rtl;
+#[doc(hidden)]
+pub mod sfc;
+#[doc(hidden)]
+pub mod tokens;
+#[doc(hidden)]
+pub mod variants;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
+
+/// Compiles an SCSS file to CSS, running `transforms` at each of their
+/// hooks. No cache, and none of the CLI's asset or output handling.
+pub fn compile(entry: &Path, opts: &scss::CompileOptions, transforms: &mut [Box<dyn transform::Transform>]) -> Result<String> {
+    let compiled = scss::compile_file_with(entry, opts, None, transforms)?;
+    transform::finish(&compiled.css, transforms)
+}
@@ -0,0 +1,411 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::fmt::cst::{self, Kind, Node};
+use crate::scss;
+
+mod rules;
+
+const DEFAULT_CONFIG: &str = "stylepack-lint.toml";
+
+/// Every rule and its default severity.
+const RULES: &[(&str, Severity)] = &[
+    ("undefined-variable", Severity::Error),
+    ("unused-variable", Severity::Warning),
+    ("undefined-mixin", Severity::Error),
+    ("unused-mixin", Severity::Warning),
+    ("duplicate-property", Severity::Warning),
+    ("empty-rule", Severity::Warning),
+    ("over-qualified-selector", Severity::Warning),
+    ("selector-max-specificity", Severity::Warning),
+    ("important", Severity::Warning),
+    ("unknown-property", Severity::Error),
+    ("max-nesting-depth", Severity::Warning),
+];
+
+#[derive(clap::Args, Debug)]
+pub struct LintArgs {
+    /// Files, or directories searched for `.scss` files.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Rule configuration; defaults to `stylepack-lint.toml` if present.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    #[arg(long)]
+    include: Vec<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = Format::Human)]
+    format: Format,
+
+    /// Apply the fixes for duplicate properties and empty rules in place.
+    #[arg(long)]
+    fix: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Rule settings from TOML:
+///
+/// ```toml
+/// [rules]
+/// important = "off"
+/// unknown-property = "warning"
+/// max-nesting-depth = { severity = "error", max = 4 }
+/// selector-max-specificity = { max = "0,3,0" }
+/// ```
+#[derive(Default)]
+pub struct Config {
+    severities: HashMap<String, Option<Severity>>,
+    options: HashMap<String, toml::Table>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("read: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("parse: {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let table: toml::Table = toml::from_str(text)?;
+        let mut config = Config::default();
+        let Some(rules) = table.get("rules") else {
+            return Ok(config);
+        };
+        let Some(rules) = rules.as_table() else {
+            bail!("[rules] must be a table");
+        };
+        for (name, value) in rules {
+            if !RULES.iter().any(|(r, _)| r == name) {
+                bail!("unknown lint rule: {}", name);
+            }
+            let (severity, options) = match value {
+                toml::Value::String(s) => (Some(s.as_str()), toml::Table::new()),
+                toml::Value::Table(t) => (t.get("severity").and_then(|v| v.as_str()), t.clone()),
+                _ => bail!("rule {} must be a severity string or a table", name),
+            };
+            if let Some(s) = severity {
+                config.severities.insert(name.clone(), parse_severity(s)?);
+            }
+            config.options.insert(name.clone(), options);
+        }
+        Ok(config)
+    }
+
+    fn severity(&self, rule: &str) -> Option<Severity> {
+        match self.severities.get(rule) {
+            Some(s) => *s,
+            None => RULES.iter().find(|(r, _)| *r == rule).map(|(_, s)| *s),
+        }
+    }
+
+    fn option_usize(&self, rule: &str, key: &str) -> Option<usize> {
+        let v = self.options.get(rule)?.get(key)?.as_integer()?;
+        usize::try_from(v).ok()
+    }
+
+    fn option_str(&self, rule: &str, key: &str) -> Option<&str> {
+        self.options.get(rule)?.get(key)?.as_str()
+    }
+}
+
+fn parse_severity(s: &str) -> Result<Option<Severity>> {
+    Ok(match s {
+        "off" => None,
+        "warn" | "warning" => Some(Severity::Warning),
+        "error" => Some(Severity::Error),
+        _ => bail!("unknown severity: {} (expected off, warning or error)", s),
+    })
+}
+
+#[derive(Serialize)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip)]
+    offset: usize,
+    /// Byte range whose removal fixes the problem.
+    #[serde(skip)]
+    fix: Option<(usize, usize)>,
+}
+
+impl Diagnostic {
+    fn new(
+        rule: &'static str,
+        severity: Severity,
+        offset: usize,
+        message: String,
+        fix: Option<(usize, usize)>,
+    ) -> Self {
+        Self {
+            file: String::new(),
+            line: 0,
+            column: 0,
+            rule,
+            severity,
+            message,
+            offset,
+            fix,
+        }
+    }
+}
+
+pub fn run(args: &LintArgs) -> Result<()> {
+    let config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None if Path::new(DEFAULT_CONFIG).exists() => Config::load(Path::new(DEFAULT_CONFIG))?,
+        None => Config::default(),
+    };
+
+    let files = crate::fmt::collect(&args.paths)?;
+    let mut parsed: Vec<(PathBuf, String, Vec<Node>)> = Vec::new();
+    for path in &files {
+        let src = std::fs::read_to_string(path).with_context(|| format!("read: {}", path.display()))?;
+        let nodes = cst::parse(&src).with_context(|| format!("parse: {}", path.display()))?;
+        parsed.push((path.clone(), src, nodes));
+    }
+
+    let mut symbols = rules::Symbols::default();
+    for (_, _, nodes) in &parsed {
+        symbols.add_file(nodes);
+    }
+    add_imported(&parsed, &args.include, &mut symbols)?;
+
+    let mut diagnostics = Vec::new();
+    let mut fixed = 0;
+    for (path, src, nodes) in &parsed {
+        let mut found = Vec::new();
+        rules::check(nodes, &symbols, &config, &mut found);
+
+        if args.fix {
+            let fixes: Vec<(usize, usize)> = found.iter().filter_map(|d| d.fix).collect();
+            if !fixes.is_empty() {
+                std::fs::write(path, apply_fixes(src, fixes.clone()))
+                    .with_context(|| format!("write: {}", path.display()))?;
+                fixed += fixes.len();
+                found.retain(|d| d.fix.is_none());
+            }
+        }
+
+        for mut d in found {
+            let (line, column) = cst::line_col(src, d.offset);
+            d.file = path.display().to_string();
+            d.line = line;
+            d.column = column;
+            diagnostics.push(d);
+        }
+    }
+    diagnostics.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    match args.format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&diagnostics)?),
+        Format::Human => {
+            for d in &diagnostics {
+                let severity = match d.severity {
+                    Severity::Warning => "warning",
+                    Severity::Error => "error",
+                };
+                println!("{}:{}:{}: {} [{}] {}", d.file, d.line, d.column, severity, d.rule, d.message);
+            }
+            if fixed > 0 {
+                eprintln!("stylepack lint: fixed {} problem(s)", fixed);
+            }
+            eprintln!(
+                "stylepack lint: {} file(s), {} problem(s) ({} error(s), {} warning(s))",
+                files.len(),
+                diagnostics.len(),
+                errors,
+                diagnostics.len() - errors
+            );
+        }
+    }
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Adds the definitions of partials reached through `@import`/`@use` that
+/// aren't linted themselves, e.g. a shared library on an include path.
+fn add_imported(parsed: &[(PathBuf, String, Vec<Node>)], include: &[PathBuf], symbols: &mut rules::Symbols) -> Result<()> {
+    let mut seen: HashSet<PathBuf> = parsed.iter().filter_map(|(p, _, _)| p.canonicalize().ok()).collect();
+    let mut queue: Vec<(PathBuf, Vec<String>)> =
+        parsed.iter().map(|(p, _, nodes)| (p.clone(), imports(nodes))).collect();
+    while let Some((from, paths)) = queue.pop() {
+        let base = from.parent().unwrap_or(Path::new("."));
+        for target in paths {
+            let Some(found) = scss::find_import(&target, base, include) else {
+                continue;
+            };
+            let Ok(real) = found.canonicalize() else {
+                continue;
+            };
+            if !seen.insert(real.clone()) {
+                continue;
+            }
+            let src = std::fs::read_to_string(&real).with_context(|| format!("read: {}", real.display()))?;
+            let nodes = cst::parse(&src).with_context(|| format!("parse: {}", real.display()))?;
+            symbols.add_file(&nodes);
+            queue.push((real, imports(&nodes)));
+        }
+    }
+    Ok(())
+}
+
+fn imports(nodes: &[Node]) -> Vec<String> {
+    let mut out = Vec::new();
+    for n in nodes {
+        let Kind::Statement(text) = &n.kind else {
+            continue;
+        };
+        let rest = ["@import", "@use", "@forward"].iter().find_map(|kw| text.strip_prefix(kw));
+        let Some(rest) = rest else {
+            continue;
+        };
+        for part in crate::css::split_top_level(rest, ',') {
+            let part = part.trim();
+            let quote = part.chars().next().filter(|c| *c == '"' || *c == '\'');
+            if let Some(q) = quote {
+                if let Some(end) = part[1..].find(q) {
+                    let path = &part[1..1 + end];
+                    if !path.starts_with("sass:") {
+                        out.push(path.to_string());
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Deletes the given byte ranges, and the lines they leave blank.
+fn apply_fixes(src: &str, mut fixes: Vec<(usize, usize)>) -> String {
+    fixes.sort();
+    fixes.dedup();
+    let mut out = String::with_capacity(src.len());
+    let mut pos = 0;
+    for (mut start, mut end) in fixes {
+        if start < pos {
+            continue;
+        }
+        let line_start = src[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = src[end..].find('\n').map(|i| end + i + 1).unwrap_or(src.len());
+        if src[line_start..start].trim().is_empty() && src[end..line_end].trim().is_empty() {
+            start = line_start;
+            end = line_end;
+        }
+        out.push_str(&src[pos..start]);
+        pos = end;
+    }
+    out.push_str(&src[pos..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(src: &str, config: &str) -> Vec<&'static str> {
+        let nodes = cst::parse(src).unwrap();
+        let mut symbols = rules::Symbols::default();
+        symbols.add_file(&nodes);
+        let mut found = Vec::new();
+        rules::check(&nodes, &symbols, &Config::parse(config).unwrap(), &mut found);
+        found.iter().map(|d| d.rule).collect()
+    }
+
+    #[test]
+    fn variables_and_mixins() {
+        let src = "$used: 1px;\n$unused: 2px;\n@mixin m { a: b; }\na { margin: $used $missing; @include gone; }\n";
+        let mut rules = lint(src, "");
+        rules.retain(|r| *r != "unknown-property");
+        rules.sort();
+        assert_eq!(rules, ["undefined-mixin", "undefined-variable", "unused-mixin", "unused-variable"]);
+    }
+
+    #[test]
+    fn duplicate_properties_but_not_fallbacks() {
+        assert_eq!(lint("a { color: red; width: 1px; color: blue; }", ""), ["duplicate-property"]);
+        assert_eq!(lint("a { color: red; color: blue; }", ""), ["duplicate-property"]);
+        assert_eq!(lint("a { margin: 1px; margin: 2px; }", ""), ["duplicate-property"]);
+        for ok in [
+            "a { display: -webkit-box; display: flex; }",
+            "a { height: 100vh; height: 100dvh; }",
+            "a { color: #000; color: rgb(0 0 0 / 50%); }",
+            "a { width: 90%; width: calc(100% - 2rem); }",
+        ] {
+            assert!(lint(ok, "").is_empty(), "{}", ok);
+        }
+    }
+
+    #[test]
+    fn selector_rules() {
+        assert_eq!(lint("div.card { color: red; }", ""), ["over-qualified-selector"]);
+        assert_eq!(lint("a {}", ""), ["empty-rule"]);
+        assert_eq!(lint("a { b { c { d { color: red; } } } }", ""), ["max-nesting-depth"]);
+        assert!(lint("a { b { c { d { color: red; } } } }", "[rules]\nmax-nesting-depth = { max = 4 }").is_empty());
+        assert_eq!(lint(".a .b .c .d .e { color: red; }", ""), ["selector-max-specificity"]);
+    }
+
+    #[test]
+    fn property_checks_and_severity_config() {
+        assert_eq!(lint("a { colour: red; color: red !important; }", ""), ["unknown-property", "important"]);
+        assert!(lint("a { font: { family: x; } --any-thing: 1; -webkit-box-flex: 1; }", "").is_empty());
+        assert_eq!(lint("a { colour: red !important; }", "[rules]\nimportant = \"off\""), ["unknown-property"]);
+    }
+
+    #[test]
+    fn config_errors() {
+        assert!(Config::parse("[rules]\nno-such-rule = \"error\"").is_err());
+        assert!(Config::parse("[rules]\nimportant = \"loud\"").is_err());
+        let config = Config::parse("[rules]\nunknown-property = \"warn\"").unwrap();
+        assert_eq!(config.severity("unknown-property"), Some(Severity::Warning));
+        assert_eq!(config.severity("undefined-mixin"), Some(Severity::Error));
+    }
+
+    #[test]
+    fn fixes_remove_blank_lines() {
+        let src = "a {\n  color: red;\n  width: 1px;\n  color: blue;\n}\n\nb {}\n";
+        let nodes = cst::parse(src).unwrap();
+        let mut found = Vec::new();
+        rules::check(&nodes, &rules::Symbols::default(), &Config::default(), &mut found);
+        let fixes = found.iter().filter_map(|d| d.fix).collect();
+        assert_eq!(apply_fixes(src, fixes), "a {\n  width: 1px;\n  color: blue;\n}\n\n");
+    }
+}
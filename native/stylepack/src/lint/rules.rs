@@ -0,0 +1,477 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use super::{Config, Diagnostic};
+use crate::fmt::cst::{is_nested_property, Kind, Node};
+
+const PROPERTIES: &str = include_str!("properties.txt");
+
+/// Variables and mixins defined and used across every linted file and the
+/// partials they import. Sass's `@import` shares one global scope, so a
+/// partial can use variables it never imports itself; lookups are therefore
+/// project-wide rather than per file.
+#[derive(Default)]
+pub struct Symbols {
+    vars: HashSet<String>,
+    var_refs: HashSet<String>,
+    mixins: HashSet<String>,
+    mixin_refs: HashSet<String>,
+}
+
+impl Symbols {
+    pub fn add_file(&mut self, nodes: &[Node]) {
+        visit(nodes, &mut |n, _| {
+            for (def, _) in var_defs(n) {
+                self.vars.insert(def);
+            }
+            self.var_refs.extend(var_refs(n));
+            if let Some(m) = mixin_def(n) {
+                self.mixins.insert(m);
+            }
+            if let Some(m) = mixin_ref(n) {
+                self.mixin_refs.insert(m);
+            }
+        });
+    }
+}
+
+/// Runs every enabled rule over one file.
+pub fn check(nodes: &[Node], symbols: &Symbols, config: &Config, out: &mut Vec<Diagnostic>) {
+    let mut push = |rule: &'static str, node: &Node, message: String, fix: Option<(usize, usize)>| {
+        if let Some(severity) = config.severity(rule) {
+            out.push(Diagnostic::new(rule, severity, node.start, message, fix));
+        }
+    };
+
+    let max_depth = config.option_usize("max-nesting-depth", "max").unwrap_or(3);
+    let max_spec = config
+        .option_str("selector-max-specificity", "max")
+        .and_then(parse_specificity)
+        .unwrap_or([0, 4, 0]);
+
+    walk(nodes, &Ctx::default(), &mut |n, ctx| {
+        match &n.kind {
+            Kind::Decl { prop, .. } if prop.starts_with('$') => {}
+            Kind::Decl { prop, value } => {
+                if value.contains("!important") {
+                    push("important", n, format!("`!important` on `{}`", prop), None);
+                }
+                let full = match &ctx.property_prefix {
+                    Some(p) => format!("{}-{}", p, prop),
+                    None => prop.to_ascii_lowercase(),
+                };
+                if !is_known_property(&full) {
+                    push("unknown-property", n, format!("unknown property `{}`", full), None);
+                }
+            }
+            Kind::Block { prelude, children } => {
+                let non_comment = children.iter().any(|c| !matches!(c.kind, Kind::Comment { .. }));
+                if !non_comment {
+                    let chained = prelude.starts_with("@if") || prelude.starts_with("@else");
+                    let fix = (children.is_empty() && !chained).then_some((n.start, n.end));
+                    push("empty-rule", n, format!("empty block `{}`", prelude), fix);
+                }
+                if is_rule(prelude) {
+                    if ctx.depth + 1 > max_depth {
+                        push(
+                            "max-nesting-depth",
+                            n,
+                            format!("`{}` is nested {} levels deep (max {})", prelude, ctx.depth + 1, max_depth),
+                            None,
+                        );
+                    }
+                    for sel in crate::css::split_top_level(prelude, ',') {
+                        if let Some(m) = over_qualified(&sel) {
+                            push("over-qualified-selector", n, format!("`{}` is qualified by element `{}`", sel, m), None);
+                        }
+                    }
+                    let spec = add_spec(ctx.specificity, selector_specificity(prelude));
+                    if spec > max_spec {
+                        push(
+                            "selector-max-specificity",
+                            n,
+                            format!(
+                                "`{}` has specificity {} (max {})",
+                                prelude,
+                                fmt_spec(spec),
+                                fmt_spec(max_spec)
+                            ),
+                            None,
+                        );
+                    }
+                }
+                check_duplicates(children, &mut push);
+            }
+            _ => {}
+        }
+
+        for name in var_refs(n) {
+            if !symbols.vars.contains(&name) {
+                push("undefined-variable", n, format!("undefined variable `${}`", name), None);
+            }
+        }
+        if let Some(m) = mixin_ref(n) {
+            if !symbols.mixins.contains(&m) {
+                push("undefined-mixin", n, format!("undefined mixin `{}`", m), None);
+            }
+        }
+        for (name, explicit) in var_defs(n) {
+            if explicit && !symbols.var_refs.contains(&name) {
+                push("unused-variable", n, format!("`${}` is never used", name), None);
+            }
+        }
+        if let Some(m) = mixin_def(n) {
+            if !symbols.mixin_refs.contains(&m) {
+                push("unused-mixin", n, format!("mixin `{}` is never included", m), None);
+            }
+        }
+    });
+}
+
+/// Reports repeated properties in one block. A consecutive repeat is left
+/// alone when it reads as a fallback, see [`is_fallback`].
+fn check_duplicates(
+    children: &[Node],
+    push: &mut impl FnMut(&'static str, &Node, String, Option<(usize, usize)>),
+) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut prev_decl: Option<usize> = None;
+    for (i, c) in children.iter().enumerate() {
+        let Kind::Decl { prop, value } = &c.kind else {
+            if !matches!(c.kind, Kind::Comment { .. }) {
+                prev_decl = None;
+            }
+            continue;
+        };
+        if prop.starts_with('$') {
+            continue;
+        }
+        let key = prop.to_ascii_lowercase();
+        if let Some(&j) = seen.get(&key) {
+            let Kind::Decl { value: earlier, .. } = &children[j].kind else {
+                unreachable!()
+            };
+            let fallback = prev_decl == Some(j) && is_fallback(earlier, value);
+            if !fallback {
+                let earlier = &children[j];
+                push(
+                    "duplicate-property",
+                    c,
+                    format!("`{}` is already set earlier in this block", prop),
+                    Some((earlier.start, earlier.end)),
+                );
+            }
+        }
+        seen.insert(key, i);
+        prev_decl = Some(i);
+    }
+}
+
+/// Whether `later` overriding `earlier` is a fallback for browsers that
+/// don't understand it: either side vendor-prefixed (`-webkit-box` then
+/// `flex`), or a different unit or function (`100vh` then `100dvh`, `#000`
+/// then `rgb(0 0 0 / 50%)`). Two plain values, like `red` then `blue`, are a
+/// mistake.
+fn is_fallback(earlier: &str, later: &str) -> bool {
+    static PREFIX: OnceLock<Regex> = OnceLock::new();
+    static SHAPE: OnceLock<Regex> = OnceLock::new();
+    let prefix = PREFIX.get_or_init(|| Regex::new(r"(?i)(^|[^\w-])-(webkit|moz|ms|o)-").unwrap());
+    let shape = SHAPE.get_or_init(|| Regex::new(r"(?:^|[^\w#.-])[-+]?[\d.]+(%|[a-zA-Z]+)|([\w-]+)\(").unwrap());
+    let units_and_functions = |v: &str| {
+        let mut found: Vec<String> = shape
+            .captures_iter(v)
+            .filter_map(|c| c.get(1).or(c.get(2)).map(|m| m.as_str().to_ascii_lowercase()))
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    };
+    earlier != later
+        && (prefix.is_match(earlier)
+            || prefix.is_match(later)
+            || units_and_functions(earlier) != units_and_functions(later))
+}
+
+#[derive(Clone, Default)]
+struct Ctx {
+    /// Rule blocks enclosing the node.
+    depth: usize,
+    specificity: [u32; 3],
+    /// Set inside nested properties: `font` in `font: { family: x }`.
+    property_prefix: Option<String>,
+}
+
+fn walk(nodes: &[Node], ctx: &Ctx, f: &mut impl FnMut(&Node, &Ctx)) {
+    for n in nodes {
+        f(n, ctx);
+        if let Kind::Block { prelude, children } = &n.kind {
+            let mut inner = ctx.clone();
+            if is_nested_property(prelude) {
+                let name = prelude.split(':').next().unwrap_or_default().trim().to_ascii_lowercase();
+                inner.property_prefix = Some(match &ctx.property_prefix {
+                    Some(p) => format!("{}-{}", p, name),
+                    None => name,
+                });
+            } else if is_rule(prelude) {
+                inner.depth += 1;
+                inner.specificity = add_spec(ctx.specificity, selector_specificity(prelude));
+            }
+            walk(children, &inner, f);
+        }
+    }
+}
+
+fn visit(nodes: &[Node], f: &mut impl FnMut(&Node, &Ctx)) {
+    walk(nodes, &Ctx::default(), f)
+}
+
+fn is_rule(prelude: &str) -> bool {
+    !prelude.starts_with('@') && !is_nested_property(prelude)
+}
+
+fn is_known_property(prop: &str) -> bool {
+    static KNOWN: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    let known = KNOWN.get_or_init(|| {
+        PROPERTIES
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .collect()
+    });
+    // Custom properties, vendor extensions and interpolated names can't be
+    // checked against the list.
+    prop.starts_with("--") || prop.starts_with('-') || prop.contains("#{") || known.contains(prop)
+}
+
+/// Variables a node defines, and whether the definition is explicit
+/// (`$x: 1`) as opposed to a parameter or loop binding, which aren't
+/// reported when unused.
+fn var_defs(n: &Node) -> Vec<(String, bool)> {
+    match &n.kind {
+        Kind::Decl { prop, .. } if prop.starts_with('$') => vec![(prop[1..].replace('_', "-"), true)],
+        Kind::Block { prelude, .. }
+            if ["@mixin", "@function", "@each", "@for"].iter().any(|kw| prelude.starts_with(kw)) =>
+        {
+            let head = match prelude.split_once(" in ").or_else(|| prelude.split_once(" from ")) {
+                Some((head, _)) => head,
+                None => prelude,
+            };
+            let defaults = default_refs(head);
+            scan_vars(head)
+                .into_iter()
+                .filter(|v| !defaults.contains(v))
+                .map(|v| (v, false))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Variables a node reads.
+fn var_refs(n: &Node) -> Vec<String> {
+    match &n.kind {
+        Kind::Decl { prop, value } => {
+            let mut refs = scan_reads(value);
+            if !prop.starts_with('$') {
+                refs.extend(scan_reads(prop));
+            }
+            refs
+        }
+        Kind::Statement(text) => scan_reads(text),
+        Kind::Block { prelude, .. } => {
+            let params: Vec<String> = var_defs(n).into_iter().map(|(v, _)| v).collect();
+            let mut refs = scan_reads(prelude);
+            refs.retain(|v| !params.contains(v));
+            refs
+        }
+        Kind::Comment { .. } => Vec::new(),
+    }
+}
+
+/// Variables used as default values in a parameter list.
+fn default_refs(prelude: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for part in prelude.split(',') {
+        if let Some((_, default)) = part.split_once(':') {
+            out.extend(scan_vars(default));
+        }
+    }
+    out
+}
+
+fn mixin_def(n: &Node) -> Option<String> {
+    match &n.kind {
+        Kind::Block { prelude, .. } => at_name(prelude, "@mixin"),
+        _ => None,
+    }
+}
+
+fn mixin_ref(n: &Node) -> Option<String> {
+    let text = match &n.kind {
+        Kind::Statement(t) => t,
+        Kind::Block { prelude, .. } => prelude,
+        _ => return None,
+    };
+    at_name(text, "@include").filter(|name| !name.contains('.'))
+}
+
+fn at_name(text: &str, kw: &str) -> Option<String> {
+    let rest = text.strip_prefix(kw)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let name: String = rest
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    (!name.is_empty()).then(|| name.replace('_', "-"))
+}
+
+/// `$name` occurrences outside of module namespaces (`math.$pi`).
+fn scan_vars(text: &str) -> Vec<String> {
+    var_matches(text).into_iter().map(|(name, _)| name).collect()
+}
+
+/// Like [`scan_vars`], minus keyword argument and parameter names such as
+/// `$size` in `@include button($size: 2)`.
+fn scan_reads(text: &str) -> Vec<String> {
+    var_matches(text)
+        .into_iter()
+        .filter(|(_, end)| !text[*end..].trim_start().starts_with(':'))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+fn var_matches(text: &str) -> Vec<(String, usize)> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"(^|[^\w.$])\$([a-zA-Z_][\w-]*)").unwrap());
+    re.captures_iter(text)
+        .map(|c| (c[2].replace('_', "-"), c.get(0).unwrap().end()))
+        .collect()
+}
+
+/// The element name qualifying a class or id, as in `div.alert`.
+fn over_qualified(sel: &str) -> Option<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"(?:^|[\s>+~(])([a-zA-Z][a-zA-Z0-9]*)[.#][a-zA-Z_-]").unwrap());
+    re.captures(sel).map(|c| c[1].to_string())
+}
+
+/// Highest `[ids, classes, types]` among a comma-separated selector list.
+fn selector_specificity(prelude: &str) -> [u32; 3] {
+    crate::css::split_top_level(prelude, ',')
+        .iter()
+        .map(|s| specificity(s))
+        .max()
+        .unwrap_or_default()
+}
+
+fn specificity(sel: &str) -> [u32; 3] {
+    let chars: Vec<char> = sel.chars().collect();
+    let mut spec = [0u32; 3];
+    let mut i = 0;
+    let ident_len = |from: usize| {
+        chars[from..]
+            .iter()
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+            .count()
+    };
+    while i < chars.len() {
+        match chars[i] {
+            '#' if chars.get(i + 1) == Some(&'{') => {
+                // Interpolation: unknown, count nothing.
+                while i < chars.len() && chars[i] != '}' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            '#' => {
+                spec[0] += 1;
+                i += 1 + ident_len(i + 1);
+            }
+            '.' | '%' => {
+                spec[1] += 1;
+                i += 1 + ident_len(i + 1);
+            }
+            '[' => {
+                spec[1] += 1;
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            ':' if chars.get(i + 1) == Some(&':') => {
+                spec[2] += 1;
+                i += 2 + ident_len(i + 2);
+            }
+            ':' => {
+                let len = ident_len(i + 1);
+                let name: String = chars[i + 1..i + 1 + len].iter().collect();
+                i += 1 + len;
+                if chars.get(i) == Some(&'(') {
+                    let start = i + 1;
+                    let mut depth = 0;
+                    while i < chars.len() {
+                        match chars[i] {
+                            '(' => depth += 1,
+                            ')' => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                        i += 1;
+                    }
+                    let inner: String = chars[start..i.min(chars.len())].iter().collect();
+                    i += 1;
+                    match name.as_str() {
+                        "where" => {}
+                        "is" | "not" | "has" | "matches" => spec = add_spec(spec, selector_specificity(&inner)),
+                        _ => spec[1] += 1,
+                    }
+                } else {
+                    spec[1] += 1;
+                }
+            }
+            c if c.is_alphabetic() => {
+                spec[2] += 1;
+                i += ident_len(i);
+            }
+            _ => i += 1,
+        }
+    }
+    spec
+}
+
+fn add_spec(a: [u32; 3], b: [u32; 3]) -> [u32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn parse_specificity(s: &str) -> Option<[u32; 3]> {
+    let parts: Vec<u32> = s.split(',').map(|p| p.trim().parse().ok()).collect::<Option<_>>()?;
+    parts.try_into().ok()
+}
+
+fn fmt_spec(s: [u32; 3]) -> String {
+    format!("{},{},{}", s[0], s[1], s[2])
+}
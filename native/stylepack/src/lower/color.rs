@@ -0,0 +1,420 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Static evaluation of `color-mix()`, `oklch()` and `oklab()` down to sRGB.
+
+use crate::css::split_top_level;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Rgba {
+    r: f64,
+    g: f64,
+    b: f64,
+    a: f64,
+}
+
+/// Replaces every color function the targets lack with an equivalent hex or
+/// `rgba()` color. Anything that can't be evaluated statically (`var()`,
+/// `currentcolor`, unknown names) is left untouched.
+pub fn lower_colors(value: &str, color_mix: bool, oklch: bool) -> String {
+    let mut names = Vec::new();
+    if color_mix {
+        names.push("color-mix(");
+    }
+    if oklch {
+        names.extend(["oklch(", "oklab("]);
+    }
+    let mut out = value.to_string();
+    for name in names {
+        out = replace_calls(&out, name);
+    }
+    out
+}
+
+fn replace_calls(value: &str, name: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(idx) = rest.find(name) {
+        let boundary = !rest[..idx]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '-');
+        let Some(end) = matching_paren(rest, idx + name.len() - 1) else {
+            break;
+        };
+        let call = &rest[idx..=end];
+        out.push_str(&rest[..idx]);
+        match parse_color(call).filter(|_| boundary) {
+            Some(c) => out.push_str(&format_color(c)),
+            None => out.push_str(call),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn matching_paren(s: &str, open: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, c) in s[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn format_color(c: Rgba) -> String {
+    let ch = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    if c.a >= 0.9995 {
+        format!("#{:02x}{:02x}{:02x}", ch(c.r), ch(c.g), ch(c.b))
+    } else {
+        let a = format!("{:.3}", c.a.clamp(0.0, 1.0));
+        let a = a.trim_end_matches('0').trim_end_matches('.');
+        format!("rgba({}, {}, {}, {})", ch(c.r), ch(c.g), ch(c.b), if a.is_empty() { "0" } else { a })
+    }
+}
+
+fn parse_color(s: &str) -> Option<Rgba> {
+    let s = s.trim();
+    let lower = s.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+    if let Some(open) = lower.find('(') {
+        if !lower.ends_with(')') {
+            return None;
+        }
+        let name = &lower[..open];
+        let args = &s[open + 1..s.len() - 1];
+        return match name {
+            "rgb" | "rgba" => parse_rgb(args),
+            "hsl" | "hsla" => parse_hsl(args),
+            "oklch" => {
+                let ([l, c, h], a) = components(args)?;
+                let l = percent_or(&l, 1.0)?;
+                let c = percent_or(&c, 0.4)?;
+                let h = angle(&h)?;
+                Some(from_oklab(l, c * h.to_radians().cos(), c * h.to_radians().sin(), a))
+            }
+            "oklab" => {
+                let ([l, a_, b], a) = components(args)?;
+                Some(from_oklab(
+                    percent_or(&l, 1.0)?,
+                    percent_or(&a_, 0.4)?,
+                    percent_or(&b, 0.4)?,
+                    a,
+                ))
+            }
+            "color-mix" => color_mix(args),
+            _ => None,
+        };
+    }
+    named(&lower)
+}
+
+/// Splits `a b c / alpha` or legacy `a, b, c, alpha` arguments.
+fn components(args: &str) -> Option<([String; 3], f64)> {
+    let (main, alpha) = match args.split_once('/') {
+        Some((m, a)) => (m.to_string(), Some(a.trim().to_string())),
+        None => (args.to_string(), None),
+    };
+    let mut parts: Vec<String> = if main.contains(',') {
+        main.split(',').map(|p| p.trim().to_string()).collect()
+    } else {
+        main.split_whitespace().map(|p| p.to_string()).collect()
+    };
+    let alpha = match (alpha, parts.len()) {
+        (Some(a), 3) => a,
+        (None, 4) => parts.pop().unwrap(),
+        (None, 3) => "1".to_string(),
+        _ => return None,
+    };
+    let alpha = percent_or(&alpha, 1.0)?;
+    Some(([parts[0].clone(), parts[1].clone(), parts[2].clone()], alpha))
+}
+
+fn percent_or(s: &str, scale: f64) -> Option<f64> {
+    let s = s.trim();
+    if s == "none" {
+        return Some(0.0);
+    }
+    match s.strip_suffix('%') {
+        Some(p) => p.parse::<f64>().ok().map(|v| v / 100.0 * scale),
+        None => s.parse::<f64>().ok(),
+    }
+}
+
+fn angle(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if let Some(v) = s.strip_suffix("deg") {
+        return v.parse().ok();
+    }
+    if let Some(v) = s.strip_suffix("turn") {
+        return v.parse::<f64>().ok().map(|t| t * 360.0);
+    }
+    if let Some(v) = s.strip_suffix("rad") {
+        return v.parse::<f64>().ok().map(|r| r.to_degrees());
+    }
+    percent_or(s, 1.0)
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    let digit = |i: usize, len: usize| -> Option<f64> {
+        let v = u8::from_str_radix(&hex[i * len..i * len + len], 16).ok()?;
+        Some(if len == 1 { (v * 17) as f64 } else { v as f64 } / 255.0)
+    };
+    match hex.len() {
+        3 | 4 => Some(Rgba {
+            r: digit(0, 1)?,
+            g: digit(1, 1)?,
+            b: digit(2, 1)?,
+            a: if hex.len() == 4 { digit(3, 1)? } else { 1.0 },
+        }),
+        6 | 8 => Some(Rgba {
+            r: digit(0, 2)?,
+            g: digit(1, 2)?,
+            b: digit(2, 2)?,
+            a: if hex.len() == 8 { digit(3, 2)? } else { 1.0 },
+        }),
+        _ => None,
+    }
+}
+
+fn parse_rgb(args: &str) -> Option<Rgba> {
+    let ([r, g, b], a) = components(args)?;
+    let ch = |s: &str| -> Option<f64> {
+        match s.strip_suffix('%') {
+            Some(p) => p.parse::<f64>().ok().map(|v| v / 100.0),
+            None => s.parse::<f64>().ok().map(|v| v / 255.0),
+        }
+    };
+    Some(Rgba {
+        r: ch(&r)?,
+        g: ch(&g)?,
+        b: ch(&b)?,
+        a,
+    })
+}
+
+fn parse_hsl(args: &str) -> Option<Rgba> {
+    let ([h, s, l], a) = components(args)?;
+    let h = angle(&h)?.rem_euclid(360.0);
+    let s = percent_or(&s, 1.0)?;
+    let l = percent_or(&l, 1.0)?;
+    let f = |n: f64| {
+        let k = (n + h / 30.0) % 12.0;
+        let amp = s * l.min(1.0 - l);
+        l - amp * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    Some(Rgba {
+        r: f(0.0),
+        g: f(8.0),
+        b: f(4.0),
+        a,
+    })
+}
+
+fn named(name: &str) -> Option<Rgba> {
+    let hex = match name {
+        "black" => "000000",
+        "white" => "ffffff",
+        "red" => "ff0000",
+        "green" => "008000",
+        "lime" => "00ff00",
+        "blue" => "0000ff",
+        "yellow" => "ffff00",
+        "cyan" | "aqua" => "00ffff",
+        "magenta" | "fuchsia" => "ff00ff",
+        "gray" | "grey" => "808080",
+        "silver" => "c0c0c0",
+        "maroon" => "800000",
+        "olive" => "808000",
+        "purple" => "800080",
+        "teal" => "008080",
+        "navy" => "000080",
+        "orange" => "ffa500",
+        "pink" => "ffc0cb",
+        "rebeccapurple" => "663399",
+        "transparent" => "00000000",
+        _ => return None,
+    };
+    parse_hex(hex)
+}
+
+fn to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_oklab(c: Rgba) -> [f64; 3] {
+    let (r, g, b) = (to_linear(c.r), to_linear(c.g), to_linear(c.b));
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+fn from_oklab(l: f64, a: f64, b: f64, alpha: f64) -> Rgba {
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    Rgba {
+        r: from_linear(4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_),
+        g: from_linear(-1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_),
+        b: from_linear(-0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_),
+        a: alpha,
+    }
+}
+
+fn color_mix(args: &str) -> Option<Rgba> {
+    let parts = split_top_level(args, ',');
+    let [space, first, second] = parts.as_slice() else {
+        return None;
+    };
+    let space = space.trim().strip_prefix("in ")?.trim().to_ascii_lowercase();
+    let (c1, p1) = mix_operand(first)?;
+    let (c2, p2) = mix_operand(second)?;
+
+    let (p1, p2) = match (p1, p2) {
+        (None, None) => (0.5, 0.5),
+        (Some(a), None) => (a, 1.0 - a),
+        (None, Some(b)) => (1.0 - b, b),
+        (Some(a), Some(b)) => (a, b),
+    };
+    let sum = p1 + p2;
+    if sum <= 0.0 {
+        return None;
+    }
+    let (w1, w2) = (p1 / sum, p2 / sum);
+    let alpha_scale = sum.min(1.0);
+
+    let alpha = c1.a * w1 + c2.a * w2;
+    let premul = |v1: f64, v2: f64| {
+        if alpha == 0.0 {
+            0.0
+        } else {
+            (v1 * c1.a * w1 + v2 * c2.a * w2) / alpha
+        }
+    };
+
+    let mixed = match space.split_whitespace().next()? {
+        "srgb" => Rgba {
+            r: premul(c1.r, c2.r),
+            g: premul(c1.g, c2.g),
+            b: premul(c1.b, c2.b),
+            a: alpha,
+        },
+        "srgb-linear" => Rgba {
+            r: from_linear(premul(to_linear(c1.r), to_linear(c2.r))),
+            g: from_linear(premul(to_linear(c1.g), to_linear(c2.g))),
+            b: from_linear(premul(to_linear(c1.b), to_linear(c2.b))),
+            a: alpha,
+        },
+        "oklab" => {
+            let (x, y) = (to_oklab(c1), to_oklab(c2));
+            from_oklab(premul(x[0], y[0]), premul(x[1], y[1]), premul(x[2], y[2]), alpha)
+        }
+        "oklch" => {
+            let (x, y) = (to_oklab(c1), to_oklab(c2));
+            let polar = |v: [f64; 3]| ((v[1] * v[1] + v[2] * v[2]).sqrt(), v[2].atan2(v[1]).to_degrees());
+            let ((cx, mut hx), (cy, mut hy)) = (polar(x), polar(y));
+            // An achromatic color has no meaningful hue; it takes the other's.
+            if cx < 1e-4 {
+                hx = hy;
+            }
+            if cy < 1e-4 {
+                hy = hx;
+            }
+            let mut dh = hy - hx;
+            if dh > 180.0 {
+                dh -= 360.0;
+            } else if dh < -180.0 {
+                dh += 360.0;
+            }
+            let h = (hx + dh * w2).to_radians();
+            let c = premul(cx, cy);
+            from_oklab(premul(x[0], y[0]), c * h.cos(), c * h.sin(), alpha)
+        }
+        _ => return None,
+    };
+    Some(Rgba {
+        a: mixed.a * alpha_scale,
+        ..mixed
+    })
+}
+
+fn mix_operand(s: &str) -> Option<(Rgba, Option<f64>)> {
+    let s = s.trim();
+    if let Some(idx) = s.rfind(char::is_whitespace) {
+        let (head, tail) = (s[..idx].trim(), s[idx..].trim());
+        if let Some(p) = tail.strip_suffix('%').and_then(|p| p.parse::<f64>().ok()) {
+            return Some((parse_color(head)?, Some(p / 100.0)));
+        }
+        if let Some(p) = head.strip_suffix('%').and_then(|p| p.parse::<f64>().ok()) {
+            if !head.contains(char::is_whitespace) {
+                return Some((parse_color(tail)?, Some(p / 100.0)));
+            }
+        }
+    }
+    Some((parse_color(s)?, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixes_colors() {
+        assert_eq!(lower_colors("color-mix(in srgb, red, blue)", true, false), "#800080");
+        assert_eq!(lower_colors("1px solid color-mix(in srgb, white 25%, black)", true, false), "1px solid #404040");
+    }
+
+    #[test]
+    fn converts_oklch_and_oklab() {
+        assert_eq!(lower_colors("oklch(100% 0 0)", false, true), "#ffffff");
+        assert_eq!(lower_colors("oklab(0 0 0)", false, true), "#000000");
+    }
+
+    #[test]
+    fn leaves_what_it_cannot_evaluate() {
+        let v = "color-mix(in srgb, var(--a), blue)";
+        assert_eq!(lower_colors(v, true, true), v);
+        assert_eq!(lower_colors("oklch(50% 0.1 30)", true, false), "oklch(50% 0.1 30)");
+    }
+}
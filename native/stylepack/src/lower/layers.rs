@@ -0,0 +1,203 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+
+use crate::css::{split_top_level, AtRule, Item, Rule, Stylesheet};
+
+/// A layer's position: the declaration index of each name along its path,
+/// then `usize::MAX` for the layer's own rules, which beat its sublayers.
+/// Unlayered rules are `[usize::MAX]` and sort last.
+type Key = Vec<usize>;
+
+/// Removes `@layer`, reordering rules so plain source order gives the
+/// precedence the layers did: layers in declaration order, a layer's
+/// sublayers before its own rules, unlayered rules last. `!important`
+/// declarations move to copies after everything, in reverse, since layers
+/// invert their order. Specificity can't be undone by reordering, so a
+/// more specific selector in an earlier layer still wins.
+pub fn lower_layers(sheet: &mut Stylesheet) {
+    if !has_layers(&sheet.items) {
+        return;
+    }
+    let mut order = Order::default();
+    let mut buckets: Vec<(Key, Vec<Item>)> = Vec::new();
+    let mut head = Vec::new();
+    for item in std::mem::take(&mut sheet.items) {
+        match &item {
+            Item::At(a) if a.body.is_none() && matches!(a.name.as_str(), "charset" | "import" | "namespace") => {
+                head.push(item)
+            }
+            _ => collect(vec![item], &[], &mut order, &mut buckets),
+        }
+    }
+    let mut normal: Vec<(Key, Vec<Item>)> = Vec::new();
+    let mut important: Vec<(Key, Vec<Item>)> = Vec::new();
+    for (key, items) in buckets {
+        let (n, i) = split_important(items);
+        push(&mut normal, key.clone(), n);
+        push(&mut important, key, i);
+    }
+    normal.sort_by(|a, b| a.0.cmp(&b.0));
+    important.sort_by(|a, b| b.0.cmp(&a.0));
+
+    sheet.items = head;
+    sheet.items.extend(normal.into_iter().flat_map(|(_, items)| items));
+    sheet.items.extend(important.into_iter().flat_map(|(_, items)| items));
+}
+
+fn has_layers(items: &[Item]) -> bool {
+    items.iter().any(|item| match item {
+        Item::At(a) => a.name == "layer" || a.body.as_deref().is_some_and(has_layers),
+        Item::Rule(r) => has_layers(&r.body),
+        _ => false,
+    })
+}
+
+/// Layer paths in first-declaration order. Anonymous layers get a name no
+/// stylesheet can write.
+#[derive(Default)]
+struct Order {
+    paths: Vec<Vec<String>>,
+    anon: usize,
+}
+
+impl Order {
+    fn declare(&mut self, parent: &[String], name: &str) -> Vec<String> {
+        let mut path = parent.to_vec();
+        if name.is_empty() {
+            self.anon += 1;
+            path.push(format!(" anon{}", self.anon));
+            self.paths.push(path.clone());
+            return path;
+        }
+        for part in name.split('.') {
+            path.push(part.trim().to_string());
+            if !self.paths.contains(&path) {
+                self.paths.push(path.clone());
+            }
+        }
+        path
+    }
+
+    fn key(&self, path: &[String]) -> Key {
+        let mut key: Key = (1..=path.len())
+            .map(|n| self.paths.iter().position(|p| p == &path[..n]).unwrap())
+            .collect();
+        key.push(usize::MAX);
+        key
+    }
+}
+
+/// Sorts `items` into per-layer buckets. Other at-rules are kept around
+/// whatever part of their body lands in each bucket.
+fn collect(items: Vec<Item>, path: &[String], order: &mut Order, buckets: &mut Vec<(Key, Vec<Item>)>) {
+    for item in items {
+        match item {
+            Item::At(a) if a.name == "layer" => match a.body {
+                None => {
+                    for name in split_top_level(&a.prelude, ',') {
+                        order.declare(path, &name);
+                    }
+                }
+                Some(body) => {
+                    let inner = order.declare(path, a.prelude.trim());
+                    collect(body, &inner, order, buckets);
+                }
+            },
+            Item::At(AtRule {
+                name,
+                prelude,
+                body: Some(body),
+            }) => {
+                let mut inner = Vec::new();
+                collect(body, path, order, &mut inner);
+                for (key, body) in inner {
+                    let at = AtRule {
+                        name: name.clone(),
+                        prelude: prelude.clone(),
+                        body: Some(body),
+                    };
+                    push(buckets, key, vec![Item::At(at)]);
+                }
+            }
+            other => push(buckets, order.key(path), vec![other]),
+        }
+    }
+}
+
+fn push(buckets: &mut Vec<(Key, Vec<Item>)>, key: Key, items: Vec<Item>) {
+    if items.is_empty() {
+        return;
+    }
+    match buckets.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, existing)) => existing.extend(items),
+        None => buckets.push((key, items)),
+    }
+}
+
+/// Splits `!important` declarations out into copies of their rules.
+fn split_important(items: Vec<Item>) -> (Vec<Item>, Vec<Item>) {
+    let mut normal = Vec::new();
+    let mut important = Vec::new();
+    for item in items {
+        match item {
+            Item::Rule(r) => {
+                let (n, i) = split_important(r.body);
+                if !i.is_empty() {
+                    important.push(Item::Rule(Rule {
+                        selectors: r.selectors.clone(),
+                        body: i,
+                    }));
+                }
+                if !n.is_empty() {
+                    normal.push(Item::Rule(Rule {
+                        selectors: r.selectors,
+                        body: n,
+                    }));
+                }
+            }
+            Item::At(AtRule {
+                name,
+                prelude,
+                body: Some(body),
+            }) if name != "keyframes" => {
+                let (n, i) = split_important(body);
+                if !i.is_empty() {
+                    important.push(Item::At(AtRule {
+                        name: name.clone(),
+                        prelude: prelude.clone(),
+                        body: Some(i),
+                    }));
+                }
+                if !n.is_empty() {
+                    normal.push(Item::At(AtRule {
+                        name,
+                        prelude,
+                        body: Some(n),
+                    }));
+                }
+            }
+            Item::Decl(d) if is_important(&d.value) => important.push(Item::Decl(d)),
+            other => normal.push(other),
+        }
+    }
+    (normal, important)
+}
+
+fn is_important(value: &str) -> bool {
+    let v = value.trim_end();
+    v.len() >= 10 && v[v.len() - 10..].eq_ignore_ascii_case("!important")
+}
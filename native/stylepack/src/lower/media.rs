@@ -0,0 +1,232 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+/// Replaces `(--name)` references with their `@custom-media` queries.
+/// Definitions may refer to each other; expansion stops after a few rounds
+/// so a cycle can't hang the build.
+pub fn expand_custom_media(query: &str, defs: &HashMap<String, String>) -> String {
+    let mut out = query.to_string();
+    for _ in 0..8 {
+        let mut changed = false;
+        for (name, value) in defs {
+            let needle = format!("({})", name);
+            if out.contains(&needle) {
+                out = out.replace(&needle, value);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    out
+}
+
+/// Rewrites range syntax such as `(width >= 600px)` or
+/// `(400px < width <= 700px)` into `min-`/`max-` features.
+pub fn lower_ranges(query: &str) -> String {
+    let mut out = String::new();
+    let mut rest = query;
+    while let Some(close) = rest.find(')') {
+        let Some(open) = rest[..close].rfind('(') else {
+            out.push_str(&rest[..=close]);
+            rest = &rest[close + 1..];
+            continue;
+        };
+        out.push_str(&rest[..open]);
+        let inner = &rest[open + 1..close];
+        match lower_range(inner) {
+            Some(lowered) => out.push_str(&lowered),
+            None => {
+                out.push('(');
+                out.push_str(inner);
+                out.push(')');
+            }
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Op {
+    fn flip(self) -> Op {
+        match self {
+            Op::Lt => Op::Gt,
+            Op::Le => Op::Ge,
+            Op::Gt => Op::Lt,
+            Op::Ge => Op::Le,
+            Op::Eq => Op::Eq,
+        }
+    }
+}
+
+fn lower_range(inner: &str) -> Option<String> {
+    let tokens = tokenize(inner)?;
+    match tokens.as_slice() {
+        [a, op, b] => {
+            let op = parse_op(op)?;
+            if is_feature_name(a) {
+                Some(bound(a, op, b))
+            } else if is_feature_name(b) {
+                Some(bound(b, op.flip(), a))
+            } else {
+                None
+            }
+        }
+        [lo, op1, name, op2, hi] if is_feature_name(name) => {
+            let (op1, op2) = (parse_op(op1)?, parse_op(op2)?);
+            Some(format!(
+                "{} and {}",
+                bound(name, op1.flip(), lo),
+                bound(name, op2, hi)
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn tokenize(inner: &str) -> Option<Vec<String>> {
+    if !inner.contains(['<', '>', '=']) {
+        return None;
+    }
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        if matches!(c, '<' | '>' | '=') {
+            if !cur.trim().is_empty() {
+                out.push(cur.trim().to_string());
+            }
+            cur.clear();
+            let mut op = c.to_string();
+            if c != '=' && chars.peek() == Some(&'=') {
+                op.push('=');
+                chars.next();
+            }
+            out.push(op);
+            continue;
+        }
+        cur.push(c);
+    }
+    if !cur.trim().is_empty() {
+        out.push(cur.trim().to_string());
+    }
+    Some(out)
+}
+
+fn parse_op(s: &str) -> Option<Op> {
+    Some(match s {
+        "<" => Op::Lt,
+        "<=" => Op::Le,
+        ">" => Op::Gt,
+        ">=" => Op::Ge,
+        "=" => Op::Eq,
+        _ => return None,
+    })
+}
+
+fn is_feature_name(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn bound(name: &str, op: Op, value: &str) -> String {
+    match op {
+        Op::Eq => format!("({}: {})", name, value),
+        Op::Ge => format!("(min-{}: {})", name, value),
+        Op::Le => format!("(max-{}: {})", name, value),
+        Op::Gt => format!("(min-{}: {})", name, nudge(value, true)),
+        Op::Lt => format!("(max-{}: {})", name, nudge(value, false)),
+    }
+}
+
+/// Strict comparisons have no `min-`/`max-` equivalent, so the bound moves
+/// by the smallest step that still matters for the unit.
+fn nudge(value: &str, up: bool) -> String {
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(split);
+    let Ok(n) = num.parse::<f64>() else {
+        return value.to_string();
+    };
+    let step = match unit {
+        "px" => 0.02,
+        "" => return value.to_string(),
+        _ => 0.001,
+    };
+    let n = if up { n + step } else { n - step };
+    format!("{}{}", format_number(n), unit)
+}
+
+fn format_number(n: f64) -> String {
+    let s = format!("{:.3}", n);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s.is_empty() || s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowers_one_sided_ranges() {
+        assert_eq!(lower_ranges("(width >= 600px)"), "(min-width: 600px)");
+        assert_eq!(lower_ranges("screen and (600px > width)"), "screen and (max-width: 599.98px)");
+        assert_eq!(lower_ranges("(width = 10em)"), "(width: 10em)");
+    }
+
+    #[test]
+    fn lowers_two_sided_ranges() {
+        assert_eq!(
+            lower_ranges("(400px < width <= 700px)"),
+            "(min-width: 400.02px) and (max-width: 700px)"
+        );
+    }
+
+    #[test]
+    fn leaves_plain_features_alone() {
+        assert_eq!(lower_ranges("(min-width: 600px) and (hover)"), "(min-width: 600px) and (hover)");
+    }
+
+    #[test]
+    fn expands_nested_custom_media_without_hanging_on_cycles() {
+        let defs: HashMap<String, String> = [
+            ("--narrow".to_string(), "(--small)".to_string()),
+            ("--small".to_string(), "(max-width: 30em)".to_string()),
+            ("--loop".to_string(), "(--loop)".to_string()),
+        ]
+        .into();
+        assert_eq!(expand_custom_media("screen and (--narrow)", &defs), "screen and (max-width: 30em)");
+        assert_eq!(expand_custom_media("(--loop)", &defs), "(--loop)");
+    }
+}
@@ -0,0 +1,185 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::css::{self, Item};
+use crate::prefix::Targets;
+use crate::scss::{self, Node};
+
+mod color;
+mod layers;
+mod media;
+
+pub use layers::lower_layers;
+
+/// Which modern syntax to rewrite. Without targets everything is lowered
+/// except `@layer`, whose lowering only approximates it.
+#[derive(Clone, Copy, Debug)]
+pub struct Lowering {
+    pub nesting: bool,
+    pub media_range: bool,
+    pub custom_media: bool,
+    pub color_mix: bool,
+    pub oklch: bool,
+    pub layers: bool,
+}
+
+impl Lowering {
+    pub fn for_targets(targets: Option<&Targets>) -> Lowering {
+        let needs = |f: &str| targets.is_none_or(|t| t.needs_feature(f));
+        Lowering {
+            nesting: needs("nesting"),
+            media_range: needs("media-range"),
+            custom_media: needs("custom-media"),
+            color_mix: needs("color-mix"),
+            oklch: needs("oklch"),
+            layers: targets.is_some_and(|t| t.needs_feature("cascade-layers")),
+        }
+    }
+}
+
+/// Compiles a standards CSS file: lowers the syntax `targets` can't handle
+/// and, when nesting has to go, flattens through the SCSS renderer.
+pub fn compile_plain(src: &str, targets: Option<&Targets>) -> Result<String> {
+    let mut sheet = css::parse_stylesheet(src)?;
+    let low = Lowering::for_targets(targets);
+
+    if low.custom_media {
+        let mut defs = HashMap::new();
+        sheet.items.retain(|item| match item {
+            Item::At(a) if a.name == "custom-media" && a.body.is_none() => {
+                if let Some((name, query)) = a.prelude.split_once(char::is_whitespace) {
+                    defs.insert(name.trim().to_string(), query.trim().to_string());
+                }
+                false
+            }
+            _ => true,
+        });
+        if !defs.is_empty() {
+            rewrite_media(&mut sheet.items, &|q| media::expand_custom_media(q, &defs));
+        }
+    }
+    if low.media_range {
+        rewrite_media(&mut sheet.items, &media::lower_ranges);
+    }
+    if low.color_mix || low.oklch {
+        rewrite_values(&mut sheet.items, &|v| color::lower_colors(v, low.color_mix, low.oklch));
+    }
+
+    if low.layers {
+        lower_layers(&mut sheet);
+    }
+
+    if low.nesting {
+        scss::render_nodes(to_nodes(&sheet.items))
+    } else {
+        Ok(css::to_css(&sheet))
+    }
+}
+
+fn rewrite_media(items: &mut [Item], f: &dyn Fn(&str) -> String) {
+    for item in items {
+        match item {
+            Item::At(a) => {
+                if a.name == "media" || a.name == "custom-media" || a.name == "import" {
+                    a.prelude = f(&a.prelude);
+                }
+                if let Some(body) = &mut a.body {
+                    rewrite_media(body, f);
+                }
+            }
+            Item::Rule(r) => rewrite_media(&mut r.body, f),
+            _ => {}
+        }
+    }
+}
+
+fn rewrite_values(items: &mut [Item], f: &dyn Fn(&str) -> String) {
+    for item in items {
+        match item {
+            Item::Decl(d) => d.value = f(&d.value),
+            Item::Rule(r) => rewrite_values(&mut r.body, f),
+            Item::At(a) => {
+                if let Some(body) = &mut a.body {
+                    rewrite_values(body, f);
+                }
+            }
+            Item::Comment(_) => {}
+        }
+    }
+}
+
+fn is_conditional_group(name: &str) -> bool {
+    matches!(name, "media" | "supports" | "container" | "layer" | "scope" | "document")
+}
+
+fn to_nodes(items: &[Item]) -> Vec<Node> {
+    let mut out = Vec::new();
+    for item in items {
+        match item {
+            Item::Rule(r) => out.push(Node::Rule {
+                selectors: r.selectors.clone(),
+                body: to_nodes(&r.body),
+            }),
+            Item::Decl(d) => out.push(Node::Decl {
+                prop: d.prop.clone(),
+                value: d.value.clone(),
+                pos: Default::default(),
+            }),
+            Item::At(a) if a.body.is_some() && is_conditional_group(&a.name) => {
+                out.push(Node::AtBlock {
+                    name: a.name.clone(),
+                    prelude: a.prelude.clone(),
+                    body: to_nodes(a.body.as_ref().unwrap()),
+                })
+            }
+            Item::At(_) => out.push(Node::RawAt {
+                text: css::item_to_css(item).trim_end().to_string(),
+            }),
+            Item::Comment(_) => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flattens_nesting_without_targets() {
+        let out = compile_plain(".a { color: red; &:hover { color: blue; } .b { x: y; } }", None).unwrap();
+        assert!(out.contains(".a:hover {"), "{}", out);
+        assert!(out.contains(".a .b {"), "{}", out);
+    }
+
+    #[test]
+    fn keeps_modern_syntax_targets_support() {
+        let targets = Targets::parse("chrome >= 130").unwrap();
+        let src = ".a { color: red; &:hover { color: blue; } }";
+        assert!(compile_plain(src, Some(&targets)).unwrap().contains("&:hover"));
+    }
+
+    #[test]
+    fn expands_custom_media_then_ranges() {
+        let src = "@custom-media --wide (width >= 800px);\n@media (--wide) { .a { b: c; } }";
+        let out = compile_plain(src, None).unwrap();
+        assert!(out.contains("@media (min-width: 800px)"), "{}", out);
+        assert!(!out.contains("custom-media"), "{}", out);
+    }
+}
@@ -15,41 +15,426 @@
 # along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use anyhow::{bail, Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::{Path, PathBuf};
 
-mod scss;
+use stylepack::{
+    aliases, assets, critical, css, deps, fmt, lint, lower, modules, prefix, purge, rtl, scss, sfc, tokens,
+    transform, variants,
+};
 
 #[derive(Parser, Debug)]
-#[command(name = "stylepack")]
+#[command(name = "stylepack", subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long)]
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    #[arg(short, long)]
-    output: PathBuf,
+    #[arg(short, long, required = true)]
+    input: Option<PathBuf>,
+
+    #[arg(short, long, required = true)]
+    output: Option<PathBuf>,
 
     #[arg(long)]
     include: Vec<PathBuf>,
 
+    /// Import path alias, e.g. `@styles=src/styles`; repeatable, and wins
+    /// over the config file.
+    #[arg(long, value_parser = aliases::parse_flag)]
+    alias: Vec<(String, PathBuf)>,
+
+    /// Config to read `[aliases]` from; defaults to `jenpack.config.toml` if
+    /// present.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
     #[arg(long)]
     minify: bool,
+
+    /// Treat the input as standards CSS rather than SCSS; implied for `.css`.
+    #[arg(long)]
+    plain: bool,
+
+    /// Scope class names CSS Modules style; implied for `*.module.scss`.
+    #[arg(long)]
+    modules: bool,
+
+    #[arg(long, default_value = modules::DEFAULT_PATTERN)]
+    module_pattern: String,
+
+    /// Browserslist-style query, e.g. "defaults, safari >= 13".
+    #[arg(long)]
+    targets: Option<String>,
+
+    /// Remove `@layer`, reordering rules to keep their precedence, for
+    /// browsers without cascade layers; implied when --targets need it.
+    #[arg(long)]
+    lower_layers: bool,
+
+    /// Inline local assets up to this many bytes as data: URIs.
+    #[arg(long)]
+    inline_limit: Option<u64>,
+
+    /// Copy referenced assets next to the output CSS.
+    #[arg(long)]
+    copy_assets: bool,
+
+    /// Copy referenced assets under content-hashed names; implies --copy-assets.
+    #[arg(long)]
+    hash_assets: bool,
+
+    /// Directory under the output's directory that assets are copied into.
+    #[arg(long, default_value = "assets")]
+    assets_dir: String,
+
+    #[arg(long, default_value_t = 12)]
+    hash_length: usize,
+
+    /// Merge original -> emitted asset paths into this JSON manifest; implies
+    /// --copy-assets.
+    #[arg(long)]
+    asset_manifest: Option<PathBuf>,
+
+    /// Write the files the output depends on as JSON.
+    #[arg(long)]
+    deps_json: Option<PathBuf>,
+
+    /// Write a Make/ninja depfile for the output.
+    #[arg(long)]
+    depfile: Option<PathBuf>,
+
+    /// Override a `!default` variable, e.g. `'$accent=#e11d48'`; repeatable.
+    #[arg(long, value_parser = variants::parse_define)]
+    define: Vec<(String, String)>,
+
+    /// Compile the input once per `[[variant]]` in this TOML file, writing
+    /// `<output stem>.<variant>.css` for each.
+    #[arg(long)]
+    variants: Option<PathBuf>,
+
+    /// With --variants, write every variant into the --output file instead.
+    #[arg(long, requires = "variants")]
+    variants_combined: bool,
+
+    /// Also write a mirrored right-to-left stylesheet, `<output stem>.rtl.css`.
+    #[arg(long)]
+    rtl: bool,
+
+    /// Append right-to-left overrides under `[dir=rtl]` to the output instead.
+    #[arg(long, conflicts_with = "rtl")]
+    rtl_merge: bool,
+
+    /// Run a built-in transform, as `name` or `name=option`: px-to-rem[=16],
+    /// layer=<name>, no-literal-colors. Repeatable; applied in order.
+    #[arg(long)]
+    transform: Vec<String>,
+
+    /// Inline local `@import "x.css"` instead of leaving it to the browser.
+    #[arg(long)]
+    inline_css_imports: bool,
+
+    /// Don't print @warn or @debug output.
+    #[arg(long)]
+    quiet: bool,
+
+    /// Don't print @warn output from stylesheets loaded through --include.
+    #[arg(long)]
+    quiet_deps: bool,
+
+    /// Fail the build if the stylesheets raise any warnings.
+    #[arg(long)]
+    fatal_warnings: bool,
+
+    #[arg(long, default_value = ".jen/stylepack")]
+    cache_dir: PathBuf,
+
+    #[arg(long)]
+    no_cache: bool,
+
+    #[arg(long)]
+    cache_stats: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Split compiled CSS into above-the-fold rules and the rest.
+    Critical(critical::CriticalArgs),
+    /// Pretty-print SCSS sources in place, or check that they are.
+    Fmt(fmt::FmtArgs),
+    /// Check SCSS sources against configurable rules.
+    Lint(lint::LintArgs),
+    /// Remove rules whose selectors never appear in the site's sources.
+    Purge(purge::PurgeArgs),
+    /// Compile and scope the `<style>` blocks of .vue and .svelte components
+    /// into one stylesheet.
+    Sfc(sfc::SfcArgs),
+    /// Export top-level SCSS variables as JSON, TypeScript and custom properties.
+    Tokens(tokens::TokensArgs),
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    if !args.input.exists() {
-        bail!("input does not exist: {}", args.input.display());
+    match &args.command {
+        Some(Command::Critical(a)) => critical::run(a),
+        Some(Command::Fmt(a)) => fmt::run(a),
+        Some(Command::Lint(a)) => lint::run(a),
+        Some(Command::Purge(a)) => purge::run(a),
+        Some(Command::Sfc(a)) => sfc::run(a),
+        Some(Command::Tokens(a)) => tokens::run(a),
+        None => compile(&args),
+    }
+}
+
+fn compile(args: &Args) -> Result<()> {
+    let (Some(input), Some(output)) = (&args.input, &args.output) else {
+        bail!("--input and --output are required");
+    };
+
+    if !input.exists() {
+        bail!("input does not exist: {}", input.display());
     }
 
+    let targets = args.targets.as_deref().map(prefix::Targets::parse).transpose()?;
+
     let mut include = args.include.clone();
-    if let Some(parent) = args.input.parent() {
+    if let Some(parent) = input.parent() {
         include.push(parent.to_path_buf());
     }
 
-    let css = scss::compile_file(&args.input, &include)
-        .with_context(|| format!("compile failed: {}", args.input.display()))?;
+    let mut import_aliases = match &args.config {
+        Some(path) => aliases::load(path)?,
+        None if Path::new(aliases::DEFAULT_CONFIG).exists() => aliases::load(Path::new(aliases::DEFAULT_CONFIG))?,
+        None => Vec::new(),
+    };
+    for (alias, target) in &args.alias {
+        aliases::set(&mut import_aliases, alias.clone(), target.clone());
+    }
+
+    let out_dir = output_dir(output)?;
+
+    let opts = scss::CompileOptions {
+        include_paths: include,
+        aliases: import_aliases,
+        url_base: Some(out_dir.clone()),
+        inline_css_imports: args.inline_css_imports,
+        defines: args.define.clone(),
+    };
+
+    let mut cache = if args.no_cache {
+        None
+    } else {
+        let fingerprint = format!("{:?} {:?}", opts, args.transform);
+        Some(scss::Cache::open(&args.cache_dir, &fingerprint)?)
+    };
+
+    let mut transforms = args
+        .transform
+        .iter()
+        .map(|spec| transform::builtin(spec))
+        .collect::<Result<Vec<_>>>()?;
+
+    let plain = args.plain || input.extension().is_some_and(|e| e == "css");
+    if let Some(path) = &args.variants {
+        if plain {
+            bail!("--variants needs an SCSS input");
+        }
+        return compile_variants(args, path, input, output, &opts, cache, &mut transforms);
+    }
+
+    let compiled = if plain {
+        let src = std::fs::read_to_string(input)
+            .with_context(|| format!("read: {}", input.display()))?;
+        let real = std::fs::canonicalize(input)?;
+        let input_dir = real.parent().unwrap().to_path_buf();
+        lower::compile_plain(&src, targets.as_ref()).map(|css| scss::Compiled {
+            css: assets::rebase_urls(&css, &input_dir, &out_dir),
+            loaded: vec![real],
+            probed: Vec::new(),
+            messages: Vec::new(),
+        })
+    } else {
+        scss::compile_file_with(input, &opts, cache.as_mut(), &mut transforms)
+    }
+    .with_context(|| format!("compile failed: {}", input.display()))?;
+    print_messages(args, input, &compiled.messages)?;
+    let css = compiled.css;
+
+    write_deps(args, output, &out_dir, compiled.loaded, &css, compiled.probed)?;
+    if args.cache_stats {
+        print_cache_stats(cache.as_ref());
+    }
+    finish(args, input, output, targets.as_ref(), css, &mut transforms)
+}
+
+fn compile_variants(
+    args: &Args,
+    path: &Path,
+    input: &Path,
+    output: &Path,
+    opts: &scss::CompileOptions,
+    mut cache: Option<scss::Cache>,
+    transforms: &mut [Box<dyn transform::Transform>],
+) -> Result<()> {
+    let out_dir = opts.url_base.as_deref().unwrap();
+    let targets = args.targets.as_deref().map(prefix::Targets::parse).transpose()?;
+    let variants = variants::load(path)?;
+    let sets: Vec<_> = variants.iter().map(variants::Variant::defines).collect();
+    let compiled = scss::compile_variants(input, opts, &sets, cache.as_mut(), transforms)
+        .with_context(|| format!("compile failed: {}", input.display()))?;
+
+    let messages: Vec<scss::Message> = compiled.iter().flat_map(|c| c.messages.iter().cloned()).collect();
+    print_messages(args, input, &messages)?;
+
+    let mut outputs = Vec::new();
+    for (variant, c) in variants.iter().zip(&compiled) {
+        let css = match &variant.selector {
+            Some(sel) => {
+                let mut sheet = css::parse_stylesheet(&c.css)?;
+                variants::scope(&mut sheet, sel);
+                css::to_css(&sheet)
+            }
+            None => c.css.clone(),
+        };
+        outputs.push((variants::output_path(output, &variant.name), css));
+    }
+    if args.variants_combined {
+        outputs = vec![(output.to_path_buf(), outputs.into_iter().map(|(_, css)| css).collect())];
+    }
+
+    // Every variant reads the same files, so one deps record covers them;
+    // it names the first output, as ninja expects.
+    let (first, first_css) = &outputs[0];
+    let first_compiled = &compiled[0];
+    write_deps(args, first, out_dir, first_compiled.loaded.clone(), first_css, first_compiled.probed.clone())?;
+    if args.cache_stats {
+        print_cache_stats(cache.as_ref());
+    }
+    for (path, css) in outputs {
+        finish(args, input, &path, targets.as_ref(), css, transforms)?;
+        eprintln!("stylepack: wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn print_messages(args: &Args, input: &Path, messages: &[scss::Message]) -> Result<()> {
+    let log_opts = scss::LogOptions {
+        quiet: args.quiet,
+        quiet_deps: args.quiet_deps,
+    };
+    let warnings = scss::print_messages(messages, &log_opts);
+    if args.fatal_warnings && warnings > 0 {
+        bail!("{} warning(s) with --fatal-warnings: {}", warnings, input.display());
+    }
+    Ok(())
+}
+
+fn write_deps(
+    args: &Args,
+    output: &Path,
+    out_dir: &Path,
+    loaded: Vec<PathBuf>,
+    css: &str,
+    probed: Vec<PathBuf>,
+) -> Result<()> {
+    if args.deps_json.is_none() && args.depfile.is_none() {
+        return Ok(());
+    }
+    let deps = deps::Deps::new(output, loaded, assets::local_refs(css, out_dir), probed);
+    if let Some(path) = &args.deps_json {
+        deps.write_json(path)?;
+    }
+    if let Some(path) = &args.depfile {
+        deps.write_depfile(path)?;
+    }
+    Ok(())
+}
+
+/// Post-processing shared by every output: CSS modules, layer lowering,
+/// prefixing and RTL, then `write_output`.
+fn finish(
+    args: &Args,
+    input: &Path,
+    output: &Path,
+    targets: Option<&prefix::Targets>,
+    css: String,
+    transforms: &mut [Box<dyn transform::Transform>],
+) -> Result<()> {
+    let out_dir = output_dir(output)?;
+    let out_dir = out_dir.as_path();
+    let use_modules = args.modules || modules::is_module_file(input);
+    let rtl = args.rtl || args.rtl_merge;
+    let lower_layers = args.lower_layers || targets.is_some_and(|t| t.needs_feature("cascade-layers"));
+    let css = if use_modules || lower_layers || targets.is_some() || rtl || !transforms.is_empty() {
+        let mut sheet = css::parse_stylesheet(&css)?;
+        transform::after_flatten(&mut sheet, transforms)?;
+        if use_modules {
+            let real = std::fs::canonicalize(input).with_context(|| format!("read: {}", input.display()))?;
+            let root = project_root(args.config.as_deref(), &real)?;
+            let rel = real.strip_prefix(&root).unwrap_or(&real);
+            let exports = modules::scope(&mut sheet, rel, &args.module_pattern)?;
+            write_module_exports(input, output, &exports)?;
+        }
+        if lower_layers {
+            lower::lower_layers(&mut sheet);
+        }
+        if let Some(t) = targets {
+            prefix::prefix(&mut sheet, t);
+        }
+        if args.rtl {
+            let mut flipped = rtl::flip(&sheet)?;
+            transform::before_serialize(&mut flipped, transforms)?;
+            write_output(args, out_dir, &variants::output_path(output, "rtl"), css::to_css(&flipped))?;
+        }
+        if args.rtl_merge {
+            let overrides = rtl::overrides(&sheet)?;
+            rtl::strip_directives(&mut sheet);
+            sheet.items.extend(overrides.items);
+        } else if rtl {
+            rtl::strip_directives(&mut sheet);
+        }
+        transform::before_serialize(&mut sheet, transforms)?;
+        css::to_css(&sheet)
+    } else {
+        css
+    };
+    write_output(args, out_dir, output, css)
+}
+
+/// The output's directory, created and canonicalized.
+fn output_dir(output: &Path) -> Result<PathBuf> {
+    let out_dir = match output.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    std::fs::create_dir_all(&out_dir).with_context(|| format!("mkdir: {}", out_dir.display()))?;
+    std::fs::canonicalize(&out_dir).with_context(|| format!("canonicalize: {}", out_dir.display()))
+}
+
+/// Asset inlining and copying, minification and the write.
+fn write_output(args: &Args, out_dir: &Path, output: &Path, css: String) -> Result<()> {
+    let css = if args.inline_limit.is_some() || css.contains("inline-asset(") {
+        let (inlined_css, inlined) = assets::inline(&css, out_dir, args.inline_limit)?;
+        print_inline_report(&inlined, inlined_css.len() as i64 - css.len() as i64);
+        inlined_css
+    } else {
+        css
+    };
+
+    let css = if args.copy_assets || args.hash_assets || args.asset_manifest.is_some() {
+        let asset_opts = assets::AssetOptions {
+            dir: args.assets_dir.clone(),
+            hash: args.hash_assets,
+            hash_length: args.hash_length,
+        };
+        let (css, emitted) = assets::emit(&css, out_dir, &asset_opts)?;
+        if let Some(path) = &args.asset_manifest {
+            assets::write_manifest(path, &emitted)?;
+        }
+        css
+    } else {
+        css
+    };
 
     let out_css = if args.minify {
         scss::minify_css(&css)
@@ -57,13 +442,70 @@ fn main() -> Result<()> {
         css
     };
 
-    if let Some(parent) = args.output.parent() {
+    std::fs::write(output, out_css)
+        .with_context(|| format!("write: {}", output.display()))?;
+
+    Ok(())
+}
+
+/// The directory module names are hashed relative to, so they don't depend
+/// on where stylepack runs: the config file's, or else the nearest one above
+/// `input` holding a `jenpack.config.toml` or `package.json`, or else the
+/// working directory.
+fn project_root(config: Option<&Path>, input: &Path) -> Result<PathBuf> {
+    if let Some(config) = config {
+        let config = std::fs::canonicalize(config).with_context(|| format!("read: {}", config.display()))?;
+        return Ok(config.parent().unwrap_or(Path::new("/")).to_path_buf());
+    }
+    let marked = input
+        .ancestors()
+        .skip(1)
+        .find(|d| d.join(aliases::DEFAULT_CONFIG).is_file() || d.join("package.json").is_file());
+    match marked {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => Ok(std::env::current_dir()?),
+    }
+}
+
+fn write_module_exports(input: &Path, output: &Path, exports: &modules::Exports) -> Result<()> {
+    let json_path = PathBuf::from(format!("{}.json", output.display()));
+    let dts_path = PathBuf::from(format!("{}.d.ts", input.display()));
+    if let Some(parent) = json_path.parent() {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("mkdir: {}", parent.display()))?;
     }
+    std::fs::write(&json_path, modules::to_json(&modules::with_camel_aliases(exports)))
+        .with_context(|| format!("write: {}", json_path.display()))?;
+    std::fs::write(&dts_path, modules::to_dts(exports))
+        .with_context(|| format!("write: {}", dts_path.display()))?;
+    Ok(())
+}
 
-    std::fs::write(&args.output, out_css)
-        .with_context(|| format!("write: {}", args.output.display()))?;
+fn print_inline_report(inlined: &[assets::Inlined], growth: i64) {
+    if inlined.is_empty() {
+        return;
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    for i in inlined {
+        let path = i.source.strip_prefix(&cwd).unwrap_or(&i.source);
+        eprintln!("stylepack: inlined {} ({} B)", path.display(), i.size);
+    }
+    eprintln!(
+        "stylepack: inlined {} asset(s), CSS {:+} bytes",
+        inlined.len(),
+        growth
+    );
+}
 
-    Ok(())
+fn print_cache_stats(cache: Option<&scss::Cache>) {
+    let Some(cache) = cache else {
+        eprintln!("stylepack cache: disabled");
+        return;
+    };
+    let s = &cache.stats;
+    let outcome = if s.entry_hits > 0 { "hit" } else { "miss" };
+    eprintln!(
+        "stylepack cache: entry {}, {} file(s) from cache, {} parsed",
+        outcome, s.parse_hits, s.parse_misses
+    );
 }
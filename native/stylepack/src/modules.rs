@@ -0,0 +1,474 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Result};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+
+use crate::css::{Item, Stylesheet};
+
+pub const DEFAULT_PATTERN: &str = "[name]_[local]__[hash:5]";
+
+pub fn is_module_file(p: &Path) -> bool {
+    let name = p.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    name.ends_with(".module.scss") || name.ends_with(".module.css")
+}
+
+/// Exported class, id and keyframe names: local name to the space-separated
+/// scoped names it expands to (more than one when `composes` is used).
+#[derive(Debug, Default)]
+pub struct Exports {
+    pub names: BTreeMap<String, Vec<String>>,
+}
+
+struct Scoper<'a> {
+    file: &'a Path,
+    pattern: &'a str,
+    exports: Exports,
+}
+
+impl Scoper<'_> {
+    fn scoped(&mut self, local: &str) -> String {
+        let name = scoped_name(self.file, local, self.pattern);
+        let entry = self.exports.names.entry(local.to_string()).or_default();
+        if entry.is_empty() {
+            entry.push(name.clone());
+        }
+        name
+    }
+}
+
+/// Rewrites local names in `sheet` to file-scoped ones and returns the
+/// mapping. `file` should be the source path relative to the project root so
+/// hashes are stable across machines.
+pub fn scope(sheet: &mut Stylesheet, file: &Path, pattern: &str) -> Result<Exports> {
+    let mut s = Scoper {
+        file,
+        pattern,
+        exports: Exports::default(),
+    };
+
+    let mut keyframes = HashMap::new();
+    collect_keyframes(&mut sheet.items, &mut s, &mut keyframes);
+    rewrite_items(&mut sheet.items, &mut s, &keyframes)?;
+    Ok(s.exports)
+}
+
+fn is_keyframes(name: &str) -> bool {
+    name == "keyframes" || name.ends_with("-keyframes")
+}
+
+fn collect_keyframes(items: &mut [Item], s: &mut Scoper, out: &mut HashMap<String, String>) {
+    for item in items {
+        if let Item::At(a) = item {
+            if is_keyframes(&a.name) {
+                let prelude = a.prelude.trim().to_string();
+                let unwrap = |p: &str| p.strip_suffix(')').map(|x| x.trim().to_string());
+                if let Some(global) = prelude.strip_prefix(":global(").and_then(unwrap) {
+                    a.prelude = global;
+                    continue;
+                }
+                let local = prelude
+                    .strip_prefix(":local(")
+                    .and_then(unwrap)
+                    .unwrap_or(prelude);
+                let scoped = s.scoped(&local);
+                out.insert(local, scoped.clone());
+                a.prelude = scoped;
+            } else if let Some(body) = &mut a.body {
+                collect_keyframes(body, s, out);
+            }
+        }
+    }
+}
+
+/// Rewrites names in `items`. A rule that only held `composes` is dropped,
+/// since its class is exported through the names it composes.
+fn rewrite_items(items: &mut Vec<Item>, s: &mut Scoper, keyframes: &HashMap<String, String>) -> Result<()> {
+    let mut emptied = Vec::new();
+    for (k, item) in items.iter_mut().enumerate() {
+        match item {
+            Item::Rule(r) => {
+                let mut locals = Vec::new();
+                for sel in &mut r.selectors {
+                    *sel = rewrite_selector(sel, s, &mut locals);
+                }
+                let mut composed = Vec::new();
+                r.body.retain(|i| match i {
+                    Item::Decl(d) if d.prop == "composes" || d.prop == "compose-with" => {
+                        composed.push(d.value.clone());
+                        false
+                    }
+                    _ => true,
+                });
+                if !composed.is_empty() && r.body.is_empty() {
+                    emptied.push(k);
+                }
+                for value in composed {
+                    apply_composes(&value, &locals, s)?;
+                }
+                rewrite_items(&mut r.body, s, keyframes)?;
+            }
+            Item::At(a) if !is_keyframes(&a.name) => {
+                if let Some(body) = &mut a.body {
+                    rewrite_items(body, s, keyframes)?;
+                }
+            }
+            Item::Decl(d) => rename_animations(d, keyframes),
+            _ => {}
+        }
+    }
+    for k in emptied.into_iter().rev() {
+        items.remove(k);
+    }
+    Ok(())
+}
+
+fn rename_animations(d: &mut crate::css::Decl, keyframes: &HashMap<String, String>) {
+    if !(d.prop.ends_with("animation") || d.prop.ends_with("animation-name")) {
+        return;
+    }
+    let renamed: Vec<String> = d
+        .value
+        .split(',')
+        .map(|part| {
+            part.split(' ')
+                .map(|w| keyframes.get(w).cloned().unwrap_or_else(|| w.to_string()))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+    d.value = renamed.join(",");
+}
+
+fn apply_composes(value: &str, locals: &[String], s: &mut Scoper) -> Result<()> {
+    let (names, from) = match value.split_once(" from ") {
+        Some((n, f)) => (n, Some(f.trim().trim_matches(['"', '\'']))),
+        None => (value, None),
+    };
+    if locals.is_empty() {
+        bail!("composes is only allowed in rules with a local class selector");
+    }
+    let mut extra = Vec::new();
+    for name in names.split_whitespace() {
+        extra.push(match from {
+            None => s.scoped(name),
+            Some("global") => name.to_string(),
+            Some(other) => {
+                let base = s.file.parent().unwrap_or(Path::new(""));
+                scoped_name(&normalize(&base.join(other)), name, s.pattern)
+            }
+        });
+    }
+    for local in locals {
+        let entry = s.exports.names.entry(local.clone()).or_default();
+        for e in &extra {
+            if !entry.contains(e) {
+                entry.push(e.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites `.class` and `#id` to scoped names, honouring `:global(...)`,
+/// `:local(...)` and the bare `:global`/`:local` mode switches.
+fn rewrite_selector(sel: &str, s: &mut Scoper, locals: &mut Vec<String>) -> String {
+    let chars: Vec<char> = sel.chars().collect();
+    let mut out = String::new();
+    let mut global = false;
+    let mut i = 0usize;
+
+    while i < chars.len() {
+        let rest: String = chars[i..].iter().take(8).collect();
+        if rest.starts_with(":global(") || rest.starts_with(":local(") {
+            let open = i + if rest.starts_with(":global(") { 7 } else { 6 };
+            let close = matching(&chars, open);
+            let inner: String = chars[open + 1..close].iter().collect();
+            if rest.starts_with(":global(") {
+                out.push_str(&inner);
+            } else {
+                out.push_str(&rewrite_selector(&inner, s, locals));
+            }
+            i = close + 1;
+            continue;
+        }
+        if rest.starts_with(":global") || rest.starts_with(":local") {
+            global = rest.starts_with(":global");
+            i += if global { 7 } else { 6 };
+            while i < chars.len() && chars[i] == ' ' {
+                i += 1;
+            }
+            continue;
+        }
+        let c = chars[i];
+        match c {
+            '[' => {
+                let close = matching(&chars, i);
+                out.extend(&chars[i..=close.min(chars.len() - 1)]);
+                i = close + 1;
+            }
+            '(' => {
+                let close = matching(&chars, i);
+                let inner: String = chars[i + 1..close].iter().collect();
+                out.push('(');
+                if global {
+                    out.push_str(&inner);
+                } else {
+                    out.push_str(&rewrite_selector(&inner, s, locals));
+                }
+                out.push(')');
+                i = close + 1;
+            }
+            '.' | '#' if chars.get(i + 1).is_some_and(|n| is_name_start(*n)) => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && is_name_char(chars[end]) {
+                    end += 1;
+                }
+                let name: String = chars[start..end].iter().collect();
+                out.push(c);
+                if global {
+                    out.push_str(&name);
+                } else {
+                    out.push_str(&s.scoped(&name));
+                    if c == '.' && !locals.contains(&name) {
+                        locals.push(name);
+                    }
+                }
+                i = end;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out.trim().to_string()
+}
+
+fn matching(chars: &[char], open: usize) -> usize {
+    let (o, c) = (chars[open], if chars[open] == '[' { ']' } else { ')' });
+    let mut depth = 0;
+    for (j, &ch) in chars.iter().enumerate().skip(open) {
+        if ch == o {
+            depth += 1;
+        } else if ch == c {
+            depth -= 1;
+            if depth == 0 {
+                return j;
+            }
+        }
+    }
+    chars.len() - 1
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '-' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || !c.is_ascii()
+}
+
+fn normalize(p: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for c in p.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Expands `[name]`, `[local]` and `[hash]`/`[hash:N]` in `pattern`.
+pub fn scoped_name(file: &Path, local: &str, pattern: &str) -> String {
+    let stem = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = stem
+        .trim_end_matches(".scss")
+        .trim_end_matches(".css")
+        .trim_end_matches(".module");
+    let stem: String = stem
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+
+    let mut h = Sha256::new();
+    h.update(normalize(file).to_string_lossy().replace('\\', "/").as_bytes());
+    h.update([0]);
+    h.update(local.as_bytes());
+    let hash = format!("{:x}", h.finalize());
+
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(open) = rest.find('[') {
+        out.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find(']').map(|c| open + c) else {
+            break;
+        };
+        let token = &rest[open + 1..close];
+        match token.split_once(':') {
+            _ if token == "name" => out.push_str(&stem),
+            _ if token == "local" => out.push_str(local),
+            _ if token == "hash" => out.push_str(&hash[..8]),
+            Some(("hash", n)) => {
+                let n = n.parse::<usize>().unwrap_or(8).clamp(1, hash.len());
+                out.push_str(&hash[..n]);
+            }
+            _ => out.push_str(&rest[open..=close]),
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(rest);
+    if out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+pub fn to_json(exports: &Exports) -> String {
+    let map: BTreeMap<&String, String> = exports
+        .names
+        .iter()
+        .map(|(k, v)| (k, v.join(" ")))
+        .collect();
+    let mut out = serde_json::to_string_pretty(&map).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+/// A declaration file for `import styles from "./x.module.scss"`. Dashed
+/// names also get a camelCase alias so `styles.cardTitle` type-checks.
+pub fn to_dts(exports: &Exports) -> String {
+    let mut keys: Vec<String> = Vec::new();
+    for k in exports.names.keys() {
+        keys.push(k.clone());
+        let camel = camel_case(k);
+        if camel != *k && !exports.names.contains_key(&camel) {
+            keys.push(camel);
+        }
+    }
+    keys.sort();
+    let mut out = String::from("declare const styles: {\n");
+    for k in keys {
+        if k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+            && !k.starts_with(|c: char| c.is_ascii_digit())
+        {
+            out.push_str(&format!("  readonly {}: string;\n", k));
+        } else {
+            out.push_str(&format!("  readonly {:?}: string;\n", k));
+        }
+    }
+    out.push_str("};\nexport default styles;\n");
+    out
+}
+
+pub fn camel_case(s: &str) -> String {
+    let mut out = String::new();
+    let mut upper = false;
+    for c in s.chars() {
+        if c == '-' {
+            upper = !out.is_empty();
+            continue;
+        }
+        if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Adds camelCase aliases to the JSON export map, matching `to_dts`.
+pub fn with_camel_aliases(exports: &Exports) -> Exports {
+    let mut names = exports.names.clone();
+    for (k, v) in &exports.names {
+        let camel = camel_case(k);
+        names.entry(camel).or_insert_with(|| v.clone());
+    }
+    Exports { names }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css;
+
+    fn scope_css(src: &str) -> (String, Exports) {
+        let mut sheet = css::parse_stylesheet(src).unwrap();
+        let exports = scope(&mut sheet, Path::new("src/card.module.scss"), "[name]_[local]").unwrap();
+        (css::to_css(&sheet), exports)
+    }
+
+    #[test]
+    fn scopes_classes_ids_and_keyframes() {
+        let src = ".title, #main .body:hover { animation: fade 1s; } @keyframes fade { to { opacity: 0; } }";
+        let (out, exports) = scope_css(src);
+        assert!(out.contains(".card_title, #card_main .card_body:hover"), "{}", out);
+        assert!(out.contains("@keyframes card_fade"), "{}", out);
+        assert!(out.contains("animation: card_fade 1s"), "{}", out);
+        assert_eq!(exports.names["title"], ["card_title"]);
+    }
+
+    #[test]
+    fn global_names_are_left_alone() {
+        let (out, exports) = scope_css(":global(.theme) .title { x: y; }");
+        assert!(out.contains(".theme .card_title"), "{}", out);
+        assert!(!exports.names.contains_key("theme"));
+    }
+
+    #[test]
+    fn composes_adds_names_to_the_export() {
+        let (out, exports) = scope_css(".base { x: y; } .button { composes: base; z: w; }");
+        assert!(!out.contains("composes"), "{}", out);
+        assert_eq!(exports.names["button"], ["card_button", "card_base"]);
+    }
+
+    #[test]
+    fn rule_with_only_composes_is_dropped() {
+        let (out, exports) = scope_css(".base { x: y; } .button { composes: base; }");
+        assert!(!out.contains("card_button"), "{}", out);
+        assert_eq!(exports.names["button"], ["card_button", "card_base"]);
+    }
+
+    #[test]
+    fn pattern_tokens() {
+        let file = Path::new("a/b.module.css");
+        let hashed = scoped_name(file, "x", "[hash:5]");
+        assert_eq!(hashed.trim_start_matches('_').len(), 5);
+        assert_eq!(hashed, scoped_name(Path::new("a/./b.module.css"), "x", "[hash:5]"));
+        assert_ne!(hashed, scoped_name(file, "y", "[hash:5]"));
+        assert_eq!(scoped_name(file, "x", "[name]-[local]-[unknown]"), "b-x-[unknown]");
+    }
+
+    #[test]
+    fn dts_adds_camel_case_aliases() {
+        let exports = Exports { names: [("card-title".to_string(), vec!["s".to_string()])].into() };
+        let dts = to_dts(&exports);
+        assert!(dts.contains("readonly \"card-title\": string;"), "{}", dts);
+        assert!(dts.contains("readonly cardTitle: string;"), "{}", dts);
+    }
+}
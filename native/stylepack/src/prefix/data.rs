@@ -0,0 +1,166 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::targets::Version;
+
+const RAW: &str = include_str!("data.txt");
+
+#[derive(Clone, Debug)]
+pub struct Cond {
+    pub browser: String,
+    pub below: Option<Version>,
+}
+
+#[derive(Debug)]
+pub struct PropEntry {
+    pub names: Vec<String>,
+    pub prefix: String,
+    pub conds: Vec<Cond>,
+}
+
+#[derive(Debug)]
+pub struct ValueEntry {
+    /// Empty means any property.
+    pub props: Vec<String>,
+    pub keywords: Vec<String>,
+    pub prefix: String,
+    pub conds: Vec<Cond>,
+}
+
+#[derive(Debug)]
+pub struct PropValueEntry {
+    pub prop: String,
+    pub value: String,
+    pub prefix: String,
+    pub conds: Vec<Cond>,
+}
+
+#[derive(Debug)]
+pub struct SelectorEntry {
+    pub pseudo: String,
+    pub replacement: String,
+    pub conds: Vec<Cond>,
+}
+
+#[derive(Debug)]
+pub struct AtRuleEntry {
+    pub name: String,
+    pub prefix: String,
+    pub conds: Vec<Cond>,
+}
+
+#[derive(Debug, Default)]
+pub struct Data {
+    pub versions: HashMap<String, Vec<Version>>,
+    pub defaults: Vec<(String, Version)>,
+    pub esr: Vec<(String, Version)>,
+    pub dead: Vec<Cond>,
+    pub properties: Vec<PropEntry>,
+    pub values: Vec<ValueEntry>,
+    pub prop_values: Vec<PropValueEntry>,
+    pub selectors: Vec<SelectorEntry>,
+    pub at_rules: Vec<AtRuleEntry>,
+    pub features: HashMap<String, Vec<Cond>>,
+}
+
+pub fn data() -> &'static Data {
+    static DATA: OnceLock<Data> = OnceLock::new();
+    DATA.get_or_init(|| parse(RAW))
+}
+
+fn parse(raw: &str) -> Data {
+    let mut d = Data::default();
+    for line in raw.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let f: Vec<&str> = line.split_whitespace().collect();
+        match f[0] {
+            "versions" => {
+                let list = d.versions.entry(f[1].to_string()).or_default();
+                for v in &f[2..] {
+                    match v.split_once('-') {
+                        Some((a, b)) => {
+                            let (a, b): (u32, u32) = (a.parse().unwrap(), b.parse().unwrap());
+                            list.extend((a..=b).map(|m| Version(m, 0)));
+                        }
+                        None => list.push(Version::parse(v).unwrap()),
+                    }
+                }
+            }
+            "defaults" => d.defaults.push((f[1].to_string(), Version::parse(f[2]).unwrap())),
+            "esr" => d.esr.push((f[1].to_string(), Version::parse(f[2]).unwrap())),
+            "dead" => d.dead.push(cond(f[1])),
+            "property" => d.properties.push(PropEntry {
+                names: list(f[1]),
+                prefix: f[2].to_string(),
+                conds: conds(&f[3..]),
+            }),
+            "value" => d.values.push(ValueEntry {
+                props: if f[1] == "*" { Vec::new() } else { list(f[1]) },
+                keywords: list(f[2]),
+                prefix: f[3].to_string(),
+                conds: conds(&f[4..]),
+            }),
+            "propvalue" => d.prop_values.push(PropValueEntry {
+                prop: f[1].to_string(),
+                value: f[2].to_string(),
+                prefix: f[3].to_string(),
+                conds: conds(&f[4..]),
+            }),
+            "selector" => d.selectors.push(SelectorEntry {
+                pseudo: f[1].to_string(),
+                replacement: f[2].to_string(),
+                conds: conds(&f[3..]),
+            }),
+            "at-rule" => d.at_rules.push(AtRuleEntry {
+                name: f[1].to_string(),
+                prefix: f[2].to_string(),
+                conds: conds(&f[3..]),
+            }),
+            "feature" => {
+                d.features.insert(f[1].to_string(), conds(&f[2..]));
+            }
+            other => panic!("prefix data: unknown record `{}`", other),
+        }
+    }
+    d
+}
+
+fn list(s: &str) -> Vec<String> {
+    s.split(',').map(|x| x.to_string()).collect()
+}
+
+fn conds(fields: &[&str]) -> Vec<Cond> {
+    fields.iter().map(|f| cond(f)).collect()
+}
+
+fn cond(f: &str) -> Cond {
+    match f.split_once('<') {
+        Some((b, v)) => Cond {
+            browser: b.to_string(),
+            below: Some(Version::parse(v).unwrap()),
+        },
+        None => Cond {
+            browser: f.to_string(),
+            below: None,
+        },
+    }
+}
@@ -0,0 +1,332 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use crate::css::{AtRule, Decl, Item, Rule, Stylesheet};
+
+mod data;
+mod targets;
+
+pub use targets::Targets;
+
+use data::data;
+
+const VENDORS: &[&str] = &["-webkit-", "-moz-", "-ms-", "-o-"];
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Adds the vendor prefixes `targets` still need and drops the ones none of
+/// them do. Prefixed forms the dataset doesn't know about are left alone.
+pub fn prefix(sheet: &mut Stylesheet, targets: &Targets) -> Report {
+    let mut report = Report::default();
+    let items = std::mem::take(&mut sheet.items);
+    sheet.items = prefix_items(items, targets, &mut report);
+    report
+}
+
+fn prefix_items(items: Vec<Item>, t: &Targets, report: &mut Report) -> Vec<Item> {
+    let present: HashSet<(String, String)> = items
+        .iter()
+        .filter_map(|i| match i {
+            Item::Decl(d) => Some((d.prop.clone(), d.value.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            Item::Decl(d) => {
+                if is_outdated_decl(&d, t) {
+                    report.removed += 1;
+                    continue;
+                }
+                for extra in prefixed_decls(&d, t) {
+                    if !present.contains(&(extra.prop.clone(), extra.value.clone())) {
+                        report.added += 1;
+                        out.push(Item::Decl(extra));
+                    }
+                }
+                out.push(Item::Decl(d));
+            }
+            Item::Rule(r) => {
+                let selectors: Vec<String> = r
+                    .selectors
+                    .iter()
+                    .filter(|s| !is_outdated_selector(s, t))
+                    .cloned()
+                    .collect();
+                report.removed += r.selectors.len() - selectors.len();
+                if selectors.is_empty() {
+                    continue;
+                }
+                let body = prefix_items(r.body, t, report);
+                for variant in prefixed_selectors(&selectors, t) {
+                    report.added += 1;
+                    out.push(Item::Rule(Rule {
+                        selectors: variant,
+                        body: body.clone(),
+                    }));
+                }
+                out.push(Item::Rule(Rule { selectors, body }));
+            }
+            Item::At(mut a) => {
+                if is_outdated_at_rule(&a, t) {
+                    report.removed += 1;
+                    continue;
+                }
+                if let Some(body) = a.body.take() {
+                    a.body = Some(prefix_items(body, t, report));
+                }
+                for p in prefixed_at_rules(&a, t) {
+                    report.added += 1;
+                    out.push(Item::At(p));
+                }
+                out.push(Item::At(a));
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn split_vendor(name: &str) -> Option<(&'static str, &str)> {
+    VENDORS
+        .iter()
+        .find(|v| name.starts_with(*v))
+        .map(|v| (*v, &name[v.len()..]))
+}
+
+fn is_outdated_decl(d: &Decl, t: &Targets) -> bool {
+    let db = data();
+    if let Some((vendor, base)) = split_vendor(&d.prop) {
+        let known: Vec<_> = db
+            .properties
+            .iter()
+            .filter(|e| e.prefix == vendor && e.names.iter().any(|n| n == base))
+            .collect();
+        if !known.is_empty() {
+            return !known.iter().any(|e| t.needs_any(&e.conds));
+        }
+        let pv: Vec<_> = db
+            .prop_values
+            .iter()
+            .filter(|e| e.prefix == vendor && e.prop == base && d.value.trim() == e.value)
+            .collect();
+        if !pv.is_empty() {
+            return !pv.iter().any(|e| t.needs_any(&e.conds));
+        }
+        return false;
+    }
+    for e in &db.values {
+        if !e.props.is_empty() && !e.props.contains(&d.prop) {
+            continue;
+        }
+        for kw in &e.keywords {
+            let prefixed = format!("{}{}", e.prefix, kw);
+            if contains_keyword(&d.value, &prefixed) {
+                return !t.needs_any(&e.conds);
+            }
+        }
+    }
+    false
+}
+
+fn prefixed_decls(d: &Decl, t: &Targets) -> Vec<Decl> {
+    if d.is_custom_property() || split_vendor(&d.prop).is_some() {
+        return Vec::new();
+    }
+    let db = data();
+    let mut out = Vec::new();
+    for e in &db.properties {
+        if e.names.contains(&d.prop) && t.needs_any(&e.conds) {
+            out.push(Decl {
+                prop: format!("{}{}", e.prefix, d.prop),
+                value: d.value.clone(),
+            });
+        }
+    }
+    for e in &db.prop_values {
+        if e.prop == d.prop && d.value.trim() == e.value && t.needs_any(&e.conds) {
+            out.push(Decl {
+                prop: format!("{}{}", e.prefix, d.prop),
+                value: d.value.clone(),
+            });
+        }
+    }
+    for e in &db.values {
+        if (!e.props.is_empty() && !e.props.contains(&d.prop)) || !t.needs_any(&e.conds) {
+            continue;
+        }
+        let mut value = d.value.clone();
+        for kw in &e.keywords {
+            if contains_keyword(&value, kw) {
+                value = replace_keyword(&value, kw, &format!("{}{}", e.prefix, kw));
+            }
+        }
+        if value != d.value {
+            out.push(Decl {
+                prop: d.prop.clone(),
+                value,
+            });
+        }
+    }
+    out
+}
+
+fn is_keyword_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Finds `kw` in `value` on identifier boundaries; function keywords end in
+/// `(` and only need a boundary on the left.
+fn keyword_positions(value: &str, kw: &str) -> Vec<usize> {
+    let mut out = Vec::new();
+    let mut from = 0;
+    while let Some(idx) = value[from..].find(kw) {
+        let at = from + idx;
+        let end = at + kw.len();
+        let left_ok = !value[..at].chars().next_back().is_some_and(is_keyword_char);
+        let right_ok = kw.ends_with('(') || !value[end..].chars().next().is_some_and(is_keyword_char);
+        if left_ok && right_ok {
+            out.push(at);
+        }
+        from = end;
+    }
+    out
+}
+
+fn contains_keyword(value: &str, kw: &str) -> bool {
+    !keyword_positions(value, kw).is_empty()
+}
+
+fn replace_keyword(value: &str, kw: &str, with: &str) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for at in keyword_positions(value, kw) {
+        out.push_str(&value[last..at]);
+        out.push_str(with);
+        last = at + kw.len();
+    }
+    out.push_str(&value[last..]);
+    out
+}
+
+fn contains_pseudo(selector: &str, pseudo: &str) -> bool {
+    let mut from = 0;
+    while let Some(idx) = selector[from..].find(pseudo) {
+        let at = from + idx;
+        let end = at + pseudo.len();
+        let prev_colon = pseudo.starts_with("::") || !selector[..at].ends_with(':');
+        if prev_colon && !selector[end..].chars().next().is_some_and(is_keyword_char) {
+            return true;
+        }
+        from = end;
+    }
+    false
+}
+
+fn is_outdated_selector(selector: &str, t: &Targets) -> bool {
+    data()
+        .selectors
+        .iter()
+        .any(|e| contains_pseudo(selector, &e.replacement) && !t.needs_any(&e.conds))
+}
+
+/// One extra selector list per needed vendor form. Each gets its own rule
+/// since a browser drops a whole list when any selector in it is unknown.
+fn prefixed_selectors(selectors: &[String], t: &Targets) -> Vec<Vec<String>> {
+    let mut out = Vec::new();
+    for e in &data().selectors {
+        if !t.needs_any(&e.conds) {
+            continue;
+        }
+        let list: Vec<String> = selectors
+            .iter()
+            .filter(|s| contains_pseudo(s, &e.pseudo))
+            .map(|s| s.replace(&e.pseudo, &e.replacement))
+            .collect();
+        if !list.is_empty() {
+            out.push(list);
+        }
+    }
+    out
+}
+
+fn is_outdated_at_rule(a: &AtRule, t: &Targets) -> bool {
+    let Some((vendor, base)) = split_vendor(&a.name) else {
+        return false;
+    };
+    let known: Vec<_> = data()
+        .at_rules
+        .iter()
+        .filter(|e| e.prefix == vendor && e.name == base)
+        .collect();
+    !known.is_empty() && !known.iter().any(|e| t.needs_any(&e.conds))
+}
+
+fn prefixed_at_rules(a: &AtRule, t: &Targets) -> Vec<AtRule> {
+    data()
+        .at_rules
+        .iter()
+        .filter(|e| e.name == a.name && t.needs_any(&e.conds))
+        .map(|e| AtRule {
+            name: format!("{}{}", e.prefix, a.name),
+            prelude: a.prelude.clone(),
+            body: a.body.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css;
+
+    fn run(src: &str, query: &str) -> (String, Report) {
+        let mut sheet = css::parse_stylesheet(src).unwrap();
+        let report = prefix(&mut sheet, &Targets::parse(query).unwrap());
+        (css::to_css(&sheet), report)
+    }
+
+    #[test]
+    fn adds_prefixes_the_targets_need() {
+        let (out, report) = run(".a { user-select: none; }", "safari >= 15, firefox >= 60");
+        assert!(out.contains("-webkit-user-select: none"), "{}", out);
+        assert!(out.contains("-moz-user-select: none"), "{}", out);
+        assert!(!out.contains("-ms-"), "{}", out);
+        assert_eq!(report.added, 2);
+    }
+
+    #[test]
+    fn keeps_existing_prefixed_declarations_once() {
+        let (out, report) = run(".a { -webkit-user-select: none; user-select: none; }", "safari >= 15");
+        assert_eq!(out.matches("-webkit-user-select").count(), 1, "{}", out);
+        assert_eq!(report.added, 0);
+    }
+
+    #[test]
+    fn drops_prefixes_no_target_needs() {
+        let (out, report) = run(".a { -moz-appearance: none; appearance: none; }", "firefox >= 100");
+        assert!(!out.contains("-moz-appearance"), "{}", out);
+        assert_eq!(report.removed, 1);
+    }
+}
@@ -0,0 +1,277 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+
+use super::data::{data, Cond};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version(pub u32, pub u32);
+
+impl Version {
+    pub fn parse(s: &str) -> Option<Version> {
+        let (major, minor) = s.split_once('.').unwrap_or((s, "0"));
+        Some(Version(major.parse().ok()?, minor.parse().ok()?))
+    }
+}
+
+/// Resolved browser targets: the oldest version of each browser we ship to.
+/// Prefix data is always expressed as "needed below version X", so the
+/// minimum is all the prefixer has to know.
+#[derive(Clone, Debug, Default)]
+pub struct Targets {
+    min: BTreeMap<String, Version>,
+}
+
+impl Targets {
+    /// Parses a browserslist-style query such as `defaults, safari >= 13`.
+    ///
+    /// Supported terms: `defaults`, `last N versions`, `last N <browser>
+    /// versions`, `<browser> >= | > | <= | < V`, `<browser> V`,
+    /// `Firefox ESR`, `not dead` and `not <browser>[ ...]`. Usage-share
+    /// queries need live data and are rejected.
+    pub fn parse(query: &str) -> Result<Targets> {
+        let mut t = Targets::default();
+        let terms = query
+            .split([',', '\n'])
+            .flat_map(|t| t.split(" or "))
+            .map(|t| t.trim().to_ascii_lowercase())
+            .filter(|t| !t.is_empty());
+
+        for term in terms {
+            if let Some(rest) = term.strip_prefix("not ") {
+                t.remove(rest.trim())?;
+                continue;
+            }
+            t.add(&term)?;
+        }
+        if t.min.is_empty() {
+            bail!("browser query matched nothing: {}", query);
+        }
+        Ok(t)
+    }
+
+    /// True when some targeted browser version falls under `cond`.
+    pub fn needs(&self, cond: &Cond) -> bool {
+        match self.min.get(&cond.browser) {
+            Some(v) => cond.below.is_none_or(|below| *v < below),
+            None => false,
+        }
+    }
+
+    pub fn needs_any(&self, conds: &[Cond]) -> bool {
+        conds.iter().any(|c| self.needs(c))
+    }
+
+    /// True when some target lacks the named syntax feature from the
+    /// dataset's `feature` records.
+    pub fn needs_feature(&self, name: &str) -> bool {
+        data()
+            .features
+            .get(name)
+            .is_some_and(|conds| self.needs_any(conds))
+    }
+
+    fn include(&mut self, browser: &str, v: Version) {
+        let e = self.min.entry(browser.to_string()).or_insert(v);
+        if v < *e {
+            *e = v;
+        }
+    }
+
+    fn add(&mut self, term: &str) -> Result<()> {
+        let d = data();
+        if term == "defaults" {
+            for (b, v) in &d.defaults {
+                self.include(b, *v);
+            }
+            return Ok(());
+        }
+        if term == "firefox esr" || term == "ff esr" {
+            for (b, v) in &d.esr {
+                self.include(b, *v);
+            }
+            return Ok(());
+        }
+        if term.starts_with('>') || term.starts_with('<') || term.contains('%') {
+            bail!("usage-based browser queries are not supported: {}", term);
+        }
+
+        let words: Vec<&str> = term.split_whitespace().collect();
+        if words.first() == Some(&"last") {
+            let n: usize = match words.get(1).and_then(|n| n.parse().ok()) {
+                Some(0) => bail!("browser query asks for no versions: {}", term),
+                Some(n) => n,
+                None => bail!("invalid browser query: {}", term),
+            };
+            match words.as_slice() {
+                [_, _, "versions" | "version"] => {
+                    for (b, list) in &d.versions {
+                        self.include(b, list[list.len().saturating_sub(n)]);
+                    }
+                }
+                [_, _, browser, "versions" | "version"] => {
+                    let b = canonical_browser(browser)?;
+                    let list = &d.versions[b];
+                    self.include(b, list[list.len().saturating_sub(n)]);
+                }
+                _ => bail!("invalid browser query: {}", term),
+            }
+            return Ok(());
+        }
+
+        let (browser, op, version) = split_version_term(&words, term)?;
+        let b = canonical_browser(browser)?;
+        let list = &d.versions[b];
+        let oldest = list[0];
+        let newest = *list.last().unwrap();
+        let v = match version {
+            "all" => oldest,
+            _ => match Version::parse(version) {
+                Some(v) => v,
+                None => bail!("invalid version in browser query: {}", term),
+            },
+        };
+        let min = match op {
+            ">=" | "=" => v,
+            ">" => list.iter().copied().find(|x| *x > v).unwrap_or(newest),
+            "<=" | "<" => oldest,
+            _ => bail!("invalid browser query: {}", term),
+        };
+        self.include(b, min);
+        Ok(())
+    }
+
+    fn remove(&mut self, term: &str) -> Result<()> {
+        if term == "dead" {
+            for c in &data().dead {
+                match (c.below, self.min.get(&c.browser).copied()) {
+                    (None, _) => {
+                        self.min.remove(&c.browser);
+                    }
+                    (Some(below), Some(v)) if v < below => {
+                        let next = data().versions[&c.browser]
+                            .iter()
+                            .copied()
+                            .find(|x| *x >= below);
+                        match next {
+                            Some(n) => {
+                                self.min.insert(c.browser.clone(), n);
+                            }
+                            None => {
+                                self.min.remove(&c.browser);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            return Ok(());
+        }
+        let words: Vec<&str> = term.split_whitespace().collect();
+        let Some(browser) = words.first() else {
+            bail!("invalid browser query: not {}", term);
+        };
+        let b = canonical_browser(browser)?;
+        if words.len() == 1 {
+            self.min.remove(b);
+            return Ok(());
+        }
+        let (_, op, version) = split_version_term(&words, term)?;
+        let Some(v) = Version::parse(version) else {
+            bail!("invalid version in browser query: not {}", term);
+        };
+        let Some(cur) = self.min.get(b).copied() else {
+            return Ok(());
+        };
+        let cutoff = match op {
+            "<" => Some(v),
+            "<=" | "=" if cur >= v => return Ok(()),
+            "<=" | "=" => data().versions[b].iter().copied().find(|x| *x > v),
+            _ => bail!("unsupported exclusion in browser query: not {}", term),
+        };
+        match cutoff.and_then(|c| data().versions[b].iter().copied().find(|x| *x >= c)) {
+            Some(n) if n > cur => {
+                self.min.insert(b.to_string(), n);
+            }
+            Some(_) => {}
+            None => {
+                self.min.remove(b);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn split_version_term<'a>(words: &[&'a str], term: &str) -> Result<(&'a str, &'a str, &'a str)> {
+    match words {
+        [b, op, v] if matches!(*op, ">=" | ">" | "<=" | "<") => Ok((b, op, v)),
+        [b, v] => Ok((b, "=", v)),
+        _ => bail!("invalid browser query: {}", term),
+    }
+}
+
+fn canonical_browser(name: &str) -> Result<&'static str> {
+    Ok(match name {
+        "chrome" => "chrome",
+        "edge" => "edge",
+        "firefox" | "ff" => "firefox",
+        "safari" => "safari",
+        "ios" | "ios_saf" | "iossafari" => "ios_saf",
+        "opera" => "opera",
+        "samsung" => "samsung",
+        "ie" | "explorer" => "ie",
+        "and_chr" | "chromeandroid" => "and_chr",
+        "and_ff" | "firefoxandroid" => "and_ff",
+        _ => bail!("unknown browser in query: {}", name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_versions() {
+        let t = Targets::parse("last 2 chrome versions").unwrap();
+        assert_eq!(t.min.len(), 1);
+        assert!(t.min.contains_key("chrome"));
+        assert!(Targets::parse("last 1000 versions").is_ok());
+    }
+
+    #[test]
+    fn last_zero_versions_is_an_error() {
+        for q in ["last 0 versions", "last 0 safari versions", "defaults, last 0 versions"] {
+            let err = Targets::parse(q).unwrap_err().to_string();
+            assert!(err.contains("no versions"), "{}: {}", q, err);
+        }
+    }
+
+    #[test]
+    fn version_ranges_and_not() {
+        let t = Targets::parse("safari >= 13, firefox 100").unwrap();
+        assert_eq!(t.min["safari"], Version(13, 0));
+        assert_eq!(t.min["firefox"], Version(100, 0));
+        let t = Targets::parse("safari >= 13, firefox 100, not firefox").unwrap();
+        assert!(!t.min.contains_key("firefox"));
+    }
+
+    #[test]
+    fn rejects_usage_queries() {
+        assert!(Targets::parse("> 1%").is_err());
+    }
+}
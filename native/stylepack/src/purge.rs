@@ -0,0 +1,500 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::css::{self, AtRule, Item, Rule, Stylesheet};
+use crate::scss;
+
+#[derive(clap::Args, Debug)]
+pub struct PurgeArgs {
+    #[arg(long)]
+    css: PathBuf,
+
+    #[arg(long, required = true)]
+    content: Vec<PathBuf>,
+
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Names to keep regardless of content; `/pattern/` is a regex.
+    #[arg(long)]
+    safelist: Vec<String>,
+
+    #[arg(long)]
+    minify: bool,
+}
+
+const CONTENT_EXTS: &[&str] = &["tsx", "jsx", "vue", "svelte", "html", "md"];
+const SKIP_DIRS: &[&str] = &["node_modules", ".git", "dist", "target", ".jen"];
+
+/// Elements produced when markdown is rendered, which never appear
+/// literally in `.md` sources.
+const MARKDOWN_ELEMENTS: &[&str] = &[
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "a", "ul", "ol", "li", "code", "pre", "blockquote",
+    "em", "strong", "img", "hr", "br", "table", "thead", "tbody", "tr", "th", "td", "del",
+];
+
+const ALWAYS_KEPT: &[&str] = &["html", "body", "*", ":root"];
+
+pub fn run(args: &PurgeArgs) -> Result<()> {
+    let src = std::fs::read_to_string(&args.css)
+        .with_context(|| format!("read: {}", args.css.display()))?;
+    let sheet = css::parse_stylesheet(&src)
+        .with_context(|| format!("parse: {}", args.css.display()))?;
+
+    let mut used = Used::default();
+    for root in &args.content {
+        used.scan_path(root)?;
+    }
+    let safelist = Safelist::parse(&args.safelist)?;
+
+    let (purged, report) = purge(&sheet, &used, &safelist);
+    let before = scss::minify_css(&src).len();
+    let out = css::to_css(&purged);
+    let out = if args.minify { scss::minify_css(&out) } else { out };
+    let after = scss::minify_css(&out).len();
+
+    if let Some(parent) = args.output.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("mkdir: {}", parent.display()))?;
+    }
+    std::fs::write(&args.output, out)
+        .with_context(|| format!("write: {}", args.output.display()))?;
+
+    let saved = before.saturating_sub(after);
+    eprintln!(
+        "stylepack purge: {} file(s) scanned; removed {} selector(s), {} rule(s), {} @keyframes, {} custom properties",
+        used.files, report.selectors, report.rules, report.keyframes, report.custom_properties
+    );
+    eprintln!(
+        "stylepack purge: {} -> {} bytes minified, saved {} ({:.1}%)",
+        before,
+        after,
+        saved,
+        if before == 0 { 0.0 } else { saved as f64 * 100.0 / before as f64 }
+    );
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct Used {
+    tokens: HashSet<String>,
+    files: usize,
+}
+
+impl Used {
+    pub fn scan_path(&mut self, root: &Path) -> Result<()> {
+        if !root.exists() {
+            bail!("content path does not exist: {}", root.display());
+        }
+        let walker = WalkDir::new(root).into_iter().filter_entry(|e| {
+            e.depth() == 0 || !SKIP_DIRS.contains(&e.file_name().to_string_lossy().as_ref())
+        });
+        for entry in walker {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let ext = entry
+                .path()
+                .extension()
+                .map(|e| e.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            if !CONTENT_EXTS.contains(&ext.as_str()) {
+                continue;
+            }
+            let text = std::fs::read_to_string(entry.path())
+                .with_context(|| format!("read: {}", entry.path().display()))?;
+            self.scan_text(&text);
+            if ext == "md" {
+                self.tokens
+                    .extend(MARKDOWN_ELEMENTS.iter().map(|s| s.to_string()));
+            }
+            self.files += 1;
+        }
+        Ok(())
+    }
+
+    /// Collects every run of identifier-ish characters. `:` and `/` are part
+    /// of the run so utility classes like `md:flex` and `w-1/2` survive whole.
+    pub fn scan_text(&mut self, text: &str) {
+        let is_tok = |c: char| c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '/' | '.');
+        for tok in text.split(|c: char| !is_tok(c)) {
+            let tok = tok.trim_end_matches([':', '.', '/']);
+            if tok.is_empty() {
+                continue;
+            }
+            self.tokens.insert(tok.to_string());
+            if tok.contains(['.', '/', ':']) {
+                for part in tok.split(['.', '/', ':']) {
+                    if !part.is_empty() {
+                        self.tokens.insert(part.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.tokens.contains(name)
+    }
+}
+
+pub struct Safelist {
+    literals: HashSet<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Safelist {
+    pub fn parse(entries: &[String]) -> Result<Self> {
+        let mut literals = HashSet::new();
+        let mut patterns = Vec::new();
+        for e in entries {
+            if e.len() >= 2 && e.starts_with('/') && e.ends_with('/') {
+                let re = Regex::new(&e[1..e.len() - 1])
+                    .with_context(|| format!("invalid safelist pattern: {}", e))?;
+                patterns.push(re);
+            } else {
+                literals.insert(e.trim_start_matches(['.', '#']).to_string());
+            }
+        }
+        Ok(Self { literals, patterns })
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.literals.contains(name) || self.patterns.iter().any(|p| p.is_match(name))
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Report {
+    pub selectors: usize,
+    pub rules: usize,
+    pub keyframes: usize,
+    pub custom_properties: usize,
+}
+
+pub fn purge(sheet: &Stylesheet, used: &Used, safelist: &Safelist) -> (Stylesheet, Report) {
+    let mut report = Report::default();
+    let mut items = purge_rules(&sheet.items, used, safelist, &mut report);
+
+    let mut animations = HashSet::new();
+    collect_animation_names(&items, &mut animations);
+    items = drop_keyframes(items, &animations, safelist, &mut report);
+
+    loop {
+        let mut refs = HashSet::new();
+        collect_var_refs(&items, &mut refs);
+        let before = report.custom_properties;
+        items = drop_custom_properties(items, &refs, used, safelist, &mut report);
+        if report.custom_properties == before {
+            break;
+        }
+    }
+
+    (Stylesheet { items }, report)
+}
+
+fn purge_rules(items: &[Item], used: &Used, safelist: &Safelist, report: &mut Report) -> Vec<Item> {
+    let mut out = Vec::new();
+    for item in items {
+        match item {
+            Item::Rule(r) => {
+                let kept: Vec<String> = r
+                    .selectors
+                    .iter()
+                    .filter(|s| selector_used(s, used, safelist))
+                    .cloned()
+                    .collect();
+                report.selectors += r.selectors.len() - kept.len();
+                if kept.is_empty() {
+                    report.rules += 1;
+                    continue;
+                }
+                out.push(Item::Rule(Rule {
+                    selectors: kept,
+                    body: purge_rules(&r.body, used, safelist, report),
+                }));
+            }
+            Item::At(a) if is_keyframes(a) || a.body.is_none() => out.push(item.clone()),
+            Item::At(a) if a.name == "font-face" || a.name == "page" || a.name == "property" => {
+                out.push(item.clone())
+            }
+            Item::At(a) => {
+                let body = purge_rules(a.body.as_deref().unwrap_or(&[]), used, safelist, report);
+                if body.iter().any(|i| !matches!(i, Item::Comment(_))) {
+                    out.push(Item::At(AtRule {
+                        name: a.name.clone(),
+                        prelude: a.prelude.clone(),
+                        body: Some(body),
+                    }));
+                }
+            }
+            _ => out.push(item.clone()),
+        }
+    }
+    out
+}
+
+fn is_keyframes(a: &AtRule) -> bool {
+    a.name == "keyframes" || (a.name.starts_with('-') && a.name.ends_with("-keyframes"))
+}
+
+/// A selector survives when every class, id and element it names outside
+/// functional pseudo-classes appears in the scanned content.
+pub fn selector_used(sel: &str, used: &Used, safelist: &Safelist) -> bool {
+    let names = selector_names(sel);
+    if names.is_empty() || ALWAYS_KEPT.contains(&sel.trim()) {
+        return true;
+    }
+    if names.iter().any(|(_, n)| safelist.contains(n)) {
+        return true;
+    }
+    names.iter().all(|(kind, n)| match kind {
+        NameKind::Tag => ALWAYS_KEPT.contains(&n.as_str()) || used.contains(n),
+        _ => used.contains(n),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NameKind {
+    Tag,
+    Class,
+    Id,
+}
+
+pub fn selector_names(sel: &str) -> Vec<(NameKind, String)> {
+    let chars: Vec<char> = sel.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0usize;
+    let mut compound_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '.' | '#' => {
+                let (name, next) = read_name(&chars, i + 1);
+                if !name.is_empty() {
+                    let kind = if c == '.' { NameKind::Class } else { NameKind::Id };
+                    out.push((kind, name));
+                }
+                i = next;
+                compound_start = false;
+            }
+            '[' => {
+                i = skip_balanced(&chars, i, '[', ']');
+                compound_start = false;
+            }
+            ':' => {
+                i += 1;
+                if chars.get(i) == Some(&':') {
+                    i += 1;
+                }
+                let (_, next) = read_name(&chars, i);
+                i = next;
+                if chars.get(i) == Some(&'(') {
+                    i = skip_balanced(&chars, i, '(', ')');
+                }
+                compound_start = false;
+            }
+            ' ' | '\t' | '\n' | '>' | '+' | '~' | ',' => {
+                i += 1;
+                compound_start = true;
+            }
+            _ if compound_start && (c.is_alphabetic() || c == '-' || c == '_') => {
+                let (name, next) = read_name(&chars, i);
+                out.push((NameKind::Tag, name.to_ascii_lowercase()));
+                i = next;
+                compound_start = false;
+            }
+            _ => {
+                i += 1;
+                compound_start = false;
+            }
+        }
+    }
+    out
+}
+
+fn read_name(chars: &[char], mut i: usize) -> (String, usize) {
+    let mut out = String::new();
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && i + 1 < chars.len() {
+            out.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if !(c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()) {
+            break;
+        }
+        out.push(c);
+        i += 1;
+    }
+    (out, i)
+}
+
+fn skip_balanced(chars: &[char], mut i: usize, open: char, close: char) -> usize {
+    let mut depth = 0i32;
+    while i < chars.len() {
+        if chars[i] == open {
+            depth += 1;
+        } else if chars[i] == close {
+            depth -= 1;
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+        i += 1;
+    }
+    i
+}
+
+fn collect_animation_names(items: &[Item], out: &mut HashSet<String>) {
+    for item in items {
+        match item {
+            Item::Decl(d) if d.prop.ends_with("animation") || d.prop.ends_with("animation-name") => {
+                for part in css::split_top_level(&d.value, ',') {
+                    out.extend(part.split_whitespace().map(|w| w.to_string()));
+                }
+            }
+            Item::Rule(r) => collect_animation_names(&r.body, out),
+            Item::At(a) if !is_keyframes(a) => {
+                collect_animation_names(a.body.as_deref().unwrap_or(&[]), out)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn drop_keyframes(
+    items: Vec<Item>,
+    used: &HashSet<String>,
+    safelist: &Safelist,
+    report: &mut Report,
+) -> Vec<Item> {
+    let mut out = Vec::new();
+    for item in items {
+        match item {
+            Item::At(a) if is_keyframes(&a) => {
+                let name = a.prelude.trim().trim_matches(['"', '\'']);
+                if used.contains(name) || safelist.contains(name) {
+                    out.push(Item::At(a));
+                } else {
+                    report.keyframes += 1;
+                }
+            }
+            Item::At(mut a) if a.body.is_some() => {
+                a.body = Some(drop_keyframes(a.body.take().unwrap(), used, safelist, report));
+                out.push(Item::At(a));
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn collect_var_refs(items: &[Item], out: &mut HashSet<String>) {
+    for item in items {
+        match item {
+            Item::Decl(d) => out.extend(css::var_refs(&d.value)),
+            Item::Rule(r) => collect_var_refs(&r.body, out),
+            Item::At(a) => {
+                out.extend(css::var_refs(&a.prelude));
+                collect_var_refs(a.body.as_deref().unwrap_or(&[]), out);
+            }
+            Item::Comment(_) => {}
+        }
+    }
+}
+
+/// Drops custom properties that nothing reads: neither a `var()` in `refs`
+/// nor the content, where inline styles and scripts may set or read them.
+fn drop_custom_properties(
+    items: Vec<Item>,
+    refs: &HashSet<String>,
+    used: &Used,
+    safelist: &Safelist,
+    report: &mut Report,
+) -> Vec<Item> {
+    let mut out = Vec::new();
+    for item in items {
+        match item {
+            Item::Decl(d) if d.is_custom_property() => {
+                if refs.contains(&d.prop) || used.contains(&d.prop) || safelist.contains(&d.prop) {
+                    out.push(Item::Decl(d));
+                } else {
+                    report.custom_properties += 1;
+                }
+            }
+            Item::Rule(mut r) => {
+                r.body = drop_custom_properties(r.body, refs, used, safelist, report);
+                if !r.body.is_empty() {
+                    out.push(Item::Rule(r));
+                }
+            }
+            Item::At(mut a) if a.body.is_some() && !is_keyframes(&a) => {
+                a.body = Some(drop_custom_properties(a.body.take().unwrap(), refs, used, safelist, report));
+                if a.body.as_ref().is_some_and(|b| !b.is_empty()) || a.name == "font-face" {
+                    out.push(Item::At(a));
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_purge(css: &str, content: &str) -> (String, Report) {
+        let mut used = Used::default();
+        used.scan_text(content);
+        let safelist = Safelist::parse(&[]).unwrap();
+        let (sheet, report) = purge(&css::parse_stylesheet(css).unwrap(), &used, &safelist);
+        (css::to_css(&sheet), report)
+    }
+
+    #[test]
+    fn drops_unused_selectors_and_rules() {
+        let (out, report) = run_purge(".used, .gone { color: red; } .gone { color: blue; }", r#"<p class="used">"#);
+        assert!(out.contains(".used {"), "{}", out);
+        assert!(!out.contains(".gone"), "{}", out);
+        assert_eq!((report.selectors, report.rules), (2, 1));
+    }
+
+    #[test]
+    fn keeps_custom_properties_read_from_content() {
+        let css = ":root { --accent: red; --unused: blue; }";
+        let (out, report) = run_purge(css, r#"<div style={{ color: "var(--accent)" }} />"#);
+        assert!(out.contains("--accent: red"), "{}", out);
+        assert!(!out.contains("--unused"), "{}", out);
+        assert_eq!(report.custom_properties, 1);
+    }
+
+    #[test]
+    fn keeps_custom_properties_read_through_other_properties() {
+        let css = ":root { --a: 1px; --b: var(--a); } .x { margin: var(--b); }";
+        let (out, _) = run_purge(css, r#"<p class="x">"#);
+        assert!(out.contains("--a: 1px") && out.contains("--b: var(--a)"), "{}", out);
+    }
+}
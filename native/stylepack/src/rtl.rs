@@ -0,0 +1,549 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+
+use crate::css::{self, AtRule, Decl, Item, Rule, Stylesheet};
+
+/// Mirrors a stylesheet for right-to-left scripts. Honors two directives:
+/// `/*rtl:ignore*/` before a rule or declaration (or at the end of a value)
+/// leaves it as is, and `/*rtl:raw: css */` inserts `css` into the RTL
+/// output only.
+pub fn flip(sheet: &Stylesheet) -> Result<Stylesheet> {
+    Ok(Stylesheet {
+        items: flip_items(&sheet.items)?,
+    })
+}
+
+/// Removes RTL directives, for the LTR output.
+pub fn strip_directives(sheet: &mut Stylesheet) {
+    strip_items(&mut sheet.items);
+}
+
+/// The declarations that differ once flipped, as overrides scoped under
+/// `[dir=rtl]`, to append to the LTR stylesheet. A property that moved sides
+/// is reset on its old side.
+pub fn overrides(sheet: &Stylesheet) -> Result<Stylesheet> {
+    Ok(Stylesheet {
+        items: override_items(&sheet.items)?,
+    })
+}
+
+fn directive(item: &Item) -> Option<&str> {
+    match item {
+        Item::Comment(c) => c.trim().strip_prefix("rtl:"),
+        _ => None,
+    }
+}
+
+fn flip_items(items: &[Item]) -> Result<Vec<Item>> {
+    let mut out = Vec::with_capacity(items.len());
+    let mut ignore_next = false;
+    for item in items {
+        if let Some(d) = directive(item) {
+            if d.trim() == "ignore" {
+                ignore_next = true;
+            } else if let Some(raw) = d.strip_prefix("raw:") {
+                out.extend(css::parse_stylesheet(raw)?.items);
+            }
+            continue;
+        }
+        if std::mem::take(&mut ignore_next) {
+            let mut kept = vec![item.clone()];
+            strip_items(&mut kept);
+            out.extend(kept);
+            continue;
+        }
+        out.push(match item {
+            Item::Decl(d) => Item::Decl(flip_decl(d)),
+            Item::Rule(r) => Item::Rule(Rule {
+                selectors: r.selectors.clone(),
+                body: flip_items(&r.body)?,
+            }),
+            Item::At(a) => Item::At(AtRule {
+                name: a.name.clone(),
+                prelude: a.prelude.clone(),
+                body: a.body.as_deref().map(flip_items).transpose()?,
+            }),
+            Item::Comment(c) => Item::Comment(c.clone()),
+        });
+    }
+    Ok(out)
+}
+
+fn strip_items(items: &mut Vec<Item>) {
+    items.retain(|i| directive(i).is_none());
+    for item in items {
+        match item {
+            Item::Decl(d) => d.value = strip_value_directive(&d.value).to_string(),
+            Item::Rule(r) => strip_items(&mut r.body),
+            Item::At(a) => {
+                if let Some(body) = &mut a.body {
+                    strip_items(body);
+                }
+            }
+            Item::Comment(_) => {}
+        }
+    }
+}
+
+fn strip_value_directive(value: &str) -> &str {
+    value.strip_suffix("/*rtl:ignore*/").map_or(value, str::trim_end)
+}
+
+fn flip_decl(d: &Decl) -> Decl {
+    if d.value.ends_with("/*rtl:ignore*/") || d.is_custom_property() {
+        return Decl {
+            prop: d.prop.clone(),
+            value: strip_value_directive(&d.value).to_string(),
+        };
+    }
+    let prop = d.prop.to_ascii_lowercase();
+    let value = match prop.as_str() {
+        "float" | "clear" | "text-align" | "text-align-last" | "caption-side" => swap_keywords(&d.value),
+        "direction" => match d.value.trim() {
+            "ltr" => "rtl".to_string(),
+            "rtl" => "ltr".to_string(),
+            v => v.to_string(),
+        },
+        "margin" | "padding" | "border-width" | "border-color" | "border-style" | "inset" | "scroll-margin"
+        | "scroll-padding" => flip_four(&d.value),
+        "border-radius" => flip_radius(&d.value),
+        "transform" => flip_transform(&d.value),
+        "background-position" | "background-position-x" | "object-position" => flip_position(&d.value),
+        "background" => swap_keywords(&d.value),
+        "box-shadow" | "text-shadow" => flip_shadow(&d.value),
+        "cursor" => flip_cursor(&d.value),
+        "transition" | "transition-property" | "will-change" => swap_sides_in_names(&d.value),
+        _ => d.value.clone(),
+    };
+    Decl {
+        prop: swap_sides_in_names(&d.prop),
+        value,
+    }
+}
+
+/// `margin-left` -> `margin-right`, `border-top-left-radius` ->
+/// `border-top-right-radius`, `left` -> `right`.
+fn swap_sides_in_names(s: &str) -> String {
+    map_words(s, |w| {
+        let parts: Vec<&str> = w.split('-').collect();
+        if !parts.iter().any(|p| *p == "left" || *p == "right") {
+            return None;
+        }
+        let swapped: Vec<&str> = parts
+            .iter()
+            .map(|p| match *p {
+                "left" => "right",
+                "right" => "left",
+                other => other,
+            })
+            .collect();
+        Some(swapped.join("-"))
+    })
+}
+
+fn swap_keywords(value: &str) -> String {
+    map_words(value, |w| match w {
+        "left" => Some("right".to_string()),
+        "right" => Some("left".to_string()),
+        _ => None,
+    })
+}
+
+/// Applies `f` to each identifier-like word outside strings and `url()`.
+fn map_words(s: &str, f: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' || c == '\'' {
+            let end = rest[1..].find(c).map_or(rest.len(), |e| e + 2);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if rest.starts_with("url(") {
+            let end = rest.find(')').map_or(rest.len(), |e| e + 1);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if c.is_alphabetic() || c == '-' || c == '_' {
+            let end = rest
+                .find(|ch: char| !(ch.is_alphanumeric() || ch == '-' || ch == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            match f(word) {
+                Some(w) => out.push_str(&w),
+                None => out.push_str(word),
+            }
+            rest = &rest[end..];
+            continue;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Space-separated components, keeping functions and `!important` intact.
+fn components(value: &str) -> (Vec<String>, &str) {
+    let (body, important) = match value.find("!important") {
+        Some(i) => (value[..i].trim_end(), &value[i..]),
+        None => (value.trim(), ""),
+    };
+    let parts = css::split_top_level(body, ' ')
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    (parts, important)
+}
+
+fn join(parts: &[String], important: &str) -> String {
+    let mut out = parts.join(" ");
+    if !important.is_empty() {
+        out.push(' ');
+        out.push_str(important);
+    }
+    out
+}
+
+/// `top right bottom left` -> `top left bottom right`.
+fn flip_four(value: &str) -> String {
+    let (mut p, important) = components(value);
+    if p.len() == 4 {
+        p.swap(1, 3);
+    }
+    join(&p, important)
+}
+
+/// Radii go top-left, top-right, bottom-right, bottom-left, on each side of
+/// an optional `/`.
+fn flip_radius(value: &str) -> String {
+    let (body, important) = match value.find("!important") {
+        Some(i) => (value[..i].trim_end(), &value[i..]),
+        None => (value.trim(), ""),
+    };
+    let sides: Vec<String> = body
+        .split('/')
+        .map(|side| {
+            let (p, _) = components(side);
+            let flipped = match p.as_slice() {
+                [a, b] => vec![b.clone(), a.clone()],
+                [a, b, c] => vec![b.clone(), a.clone(), b.clone(), c.clone()],
+                [a, b, c, d] => vec![b.clone(), a.clone(), d.clone(), c.clone()],
+                _ => p,
+            };
+            flipped.join(" ")
+        })
+        .collect();
+    join(&[sides.join(" / ")], important)
+}
+
+fn negate(v: &str) -> String {
+    let v = v.trim();
+    if let Some(pos) = v.strip_prefix('-') {
+        if !pos.starts_with('-') {
+            return pos.to_string();
+        }
+    }
+    if v.trim_start_matches(['0', '.']).chars().all(|c| c.is_alphabetic()) {
+        // `0`, `0px`, `0deg`.
+        return v.to_string();
+    }
+    if v.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return format!("-{}", v);
+    }
+    format!("calc(-1 * {})", v)
+}
+
+fn flip_transform(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(open) = rest.find('(') {
+        let name_start = rest[..open]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '-'))
+            .map_or(0, |i| i + 1);
+        let name = rest[name_start..open].to_ascii_lowercase();
+        let close = css_close(rest, open);
+        let args = &rest[open + 1..close];
+        out.push_str(&rest[..=open]);
+        let parts = css::split_top_level(args, ',');
+        let flipped: Vec<String> = match name.as_str() {
+            "translatex" | "rotate" | "rotatez" | "skewx" | "skewy" => parts.iter().map(|a| negate(a)).collect(),
+            "translate" | "translate3d" | "skew" => parts
+                .iter()
+                .enumerate()
+                .map(|(i, a)| if i == 0 || name == "skew" { negate(a) } else { a.trim().to_string() })
+                .collect(),
+            _ => parts.iter().map(|a| a.trim().to_string()).collect(),
+        };
+        out.push_str(&flipped.join(", "));
+        out.push(')');
+        rest = &rest[(close + 1).min(rest.len())..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn css_close(s: &str, open: usize) -> usize {
+    let mut depth = 0;
+    for (i, c) in s[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + i;
+                }
+            }
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// Swaps `left`/`right` and mirrors a leading horizontal percentage, per
+/// comma-separated layer.
+fn flip_position(value: &str) -> String {
+    css::split_top_level(value, ',')
+        .iter()
+        .map(|layer| {
+            let (mut p, important) = components(layer);
+            if let Some(first) = p.first_mut() {
+                if let Some(pct) = first.strip_suffix('%').and_then(|n| n.parse::<f64>().ok()) {
+                    *first = format!("{}%", 100.0 - pct);
+                }
+            }
+            swap_keywords(&join(&p, important))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Negates the horizontal offset of each shadow.
+fn flip_shadow(value: &str) -> String {
+    css::split_top_level(value, ',')
+        .iter()
+        .map(|shadow| {
+            let (mut p, important) = components(shadow);
+            let first_len = p
+                .iter()
+                .position(|c| c.starts_with(|ch: char| ch.is_ascii_digit() || ch == '-' || ch == '.') || c.starts_with("calc("));
+            if let Some(i) = first_len {
+                p[i] = negate(&p[i]);
+            }
+            join(&p, important)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn flip_cursor(value: &str) -> String {
+    map_words(value, |w| {
+        let flipped = match w {
+            "e-resize" => "w-resize",
+            "w-resize" => "e-resize",
+            "ne-resize" => "nw-resize",
+            "nw-resize" => "ne-resize",
+            "se-resize" => "sw-resize",
+            "sw-resize" => "se-resize",
+            "nesw-resize" => "nwse-resize",
+            "nwse-resize" => "nesw-resize",
+            _ => return None,
+        };
+        Some(flipped.to_string())
+    })
+}
+
+fn override_items(items: &[Item]) -> Result<Vec<Item>> {
+    let mut out = Vec::new();
+    let mut ignore_next = false;
+    for item in items {
+        if let Some(d) = directive(item) {
+            if d.trim() == "ignore" {
+                ignore_next = true;
+            } else if let Some(raw) = d.strip_prefix("raw:") {
+                let mut raw = css::parse_stylesheet(raw)?.items;
+                scope_rules(&mut raw);
+                out.extend(raw);
+            }
+            continue;
+        }
+        if std::mem::take(&mut ignore_next) {
+            continue;
+        }
+        match item {
+            Item::Rule(r) => {
+                let body = override_body(&r.body)?;
+                if !body.is_empty() {
+                    let mut rule = vec![Item::Rule(Rule {
+                        selectors: r.selectors.clone(),
+                        body,
+                    })];
+                    scope_rules(&mut rule);
+                    out.extend(rule);
+                }
+            }
+            Item::At(a) if !a.name.ends_with("keyframes") => {
+                if let Some(body) = &a.body {
+                    let body = override_items(body)?;
+                    if !body.is_empty() {
+                        out.push(Item::At(AtRule {
+                            name: a.name.clone(),
+                            prelude: a.prelude.clone(),
+                            body: Some(body),
+                        }));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+fn scope_rules(items: &mut [Item]) {
+    for item in items {
+        match item {
+            Item::Rule(r) => {
+                for sel in &mut r.selectors {
+                    *sel = crate::variants::scope_selector(sel, "[dir=rtl]");
+                }
+            }
+            Item::At(a) => {
+                if let Some(body) = &mut a.body {
+                    scope_rules(body);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Flipped declarations that differ from the originals, plus `rtl:raw`
+/// insertions. A property that moved sides is reset on its old side.
+fn override_body(body: &[Item]) -> Result<Vec<Item>> {
+    let mut out = Vec::new();
+    let mut moved: Vec<String> = Vec::new();
+    let mut set: Vec<String> = Vec::new();
+    let mut ignore_next = false;
+    for item in body {
+        if let Some(d) = directive(item) {
+            if d.trim() == "ignore" {
+                ignore_next = true;
+            } else if let Some(raw) = d.strip_prefix("raw:") {
+                out.extend(css::parse_stylesheet(raw)?.items);
+            }
+            continue;
+        }
+        let Item::Decl(d) = item else {
+            continue;
+        };
+        if std::mem::take(&mut ignore_next) {
+            continue;
+        }
+        let flipped = flip_decl(d);
+        let original = Decl {
+            prop: d.prop.clone(),
+            value: strip_value_directive(&d.value).to_string(),
+        };
+        if flipped == original {
+            continue;
+        }
+        if flipped.prop != original.prop {
+            moved.push(original.prop);
+        }
+        set.push(flipped.prop.clone());
+        out.push(Item::Decl(flipped));
+    }
+    for prop in moved {
+        if !set.contains(&prop) {
+            out.push(Item::Decl(Decl {
+                prop,
+                value: "unset".to_string(),
+            }));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flipped(prop: &str, value: &str) -> String {
+        let d = flip_decl(&Decl {
+            prop: prop.to_string(),
+            value: value.to_string(),
+        });
+        format!("{}: {}", d.prop, d.value)
+    }
+
+    fn run(src: &str, f: fn(&Stylesheet) -> Result<Stylesheet>) -> String {
+        css::to_css(&f(&css::parse_stylesheet(src).unwrap()).unwrap())
+    }
+
+    #[test]
+    fn flips_sides_and_shorthands() {
+        assert_eq!(flipped("margin-left", "4px"), "margin-right: 4px");
+        assert_eq!(flipped("border-top-left-radius", "2px"), "border-top-right-radius: 2px");
+        assert_eq!(flipped("padding", "1px 2px 3px 4px !important"), "padding: 1px 4px 3px 2px !important");
+        assert_eq!(flipped("padding", "1px 2px 3px"), "padding: 1px 2px 3px");
+        assert_eq!(flipped("border-radius", "1px 2px 3px 4px / 5px 6px"), "border-radius: 2px 1px 4px 3px / 6px 5px");
+        assert_eq!(flipped("float", "left"), "float: right");
+        assert_eq!(flipped("direction", "ltr"), "direction: rtl");
+        assert_eq!(flipped("cursor", "nw-resize"), "cursor: ne-resize");
+        assert_eq!(flipped("transition", "margin-left 1s"), "transition: margin-right 1s");
+    }
+
+    #[test]
+    fn flips_values_with_offsets() {
+        assert_eq!(
+            flipped("transform", "translate(10px, 5px) rotate(-45deg) translateX(var(--x))"),
+            "transform: translate(-10px, 5px) rotate(45deg) translateX(calc(-1 * var(--x)))"
+        );
+        assert_eq!(flipped("background-position", "25% 0, left top"), "background-position: 75% 0, right top");
+        assert_eq!(flipped("box-shadow", "inset 2px 0 red, -1px 1px blue"), "box-shadow: inset -2px 0 red, 1px 1px blue");
+        assert_eq!(flipped("background", "url(left.png) left"), "background: url(left.png) right");
+    }
+
+    #[test]
+    fn leaves_custom_properties_and_ignored_values() {
+        assert_eq!(flipped("--side", "left"), "--side: left");
+        assert_eq!(flipped("float", "left /*rtl:ignore*/"), "float: left");
+    }
+
+    #[test]
+    fn directives() {
+        let src = "/*rtl:ignore*/ a { float: left; } b { /*rtl:ignore*/ margin-left: 1px; padding-left: 2px; } /*rtl:raw: c { d: e; } */";
+        let out = run(src, flip);
+        assert!(out.contains("a {\n  float: left;"), "{}", out);
+        assert!(out.contains("margin-left: 1px;\n  padding-right: 2px;"), "{}", out);
+        assert!(out.contains("c {\n  d: e;"), "{}", out);
+        assert!(!out.contains("rtl:"), "{}", out);
+    }
+
+    #[test]
+    fn overrides_reset_the_old_side() {
+        let out = run("a { margin-left: 1px; color: red; } b { float: left; } @media print { c { left: 0; right: 2px; } }", overrides);
+        assert!(out.contains("[dir=rtl] a {\n  margin-right: 1px;\n  margin-left: unset;\n}"), "{}", out);
+        assert!(out.contains("[dir=rtl] b {\n  float: right;\n}"), "{}", out);
+        assert!(out.contains("[dir=rtl] c {\n    right: 0;\n    left: 2px;\n  }"), "{}", out);
+        assert!(!out.contains("color"), "{}", out);
+    }
+}
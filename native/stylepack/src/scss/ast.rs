@@ -0,0 +1,579 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use super::intern::{Interner, Sym};
+use super::log::{Frame, LogLevel, Pos};
+
+/// The owned form of a statement, for transforms, the parse cache and front
+/// ends other than the SCSS parser. Compiling works on an [`Arena`] instead.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Node {
+    VarDecl {
+        name: String,
+        value: String,
+        #[serde(default)]
+        pos: Pos,
+    },
+    Rule { selectors: Vec<String>, body: Vec<Node> },
+    Decl {
+        prop: String,
+        value: String,
+        #[serde(default)]
+        pos: Pos,
+    },
+    RawAt { text: String },
+    /// A `/* */` comment between statements, kept in the output.
+    Comment { text: String },
+    Import { text: String, pos: Pos },
+    /// `@debug`, `@warn` or `@error`.
+    Log { level: LogLevel, message: String, pos: Pos },
+    AtBlock { name: String, prelude: String, body: Vec<Node> },
+    /// `@mixin name(params)`. Names are kept with `_` read as `-`.
+    Mixin { name: String, params: String, body: Vec<Node> },
+    Function { name: String, params: String, body: Vec<Node> },
+    Include {
+        name: String,
+        args: String,
+        content: Option<Vec<Node>>,
+        pos: Pos,
+    },
+    Content { pos: Pos },
+    Return { value: String, pos: Pos },
+    /// `@extend selector`, with any `!optional` left in.
+    Extend { selector: String, pos: Pos },
+    /// `@if`; an `@else if` is an `If` alone in `otherwise`.
+    If {
+        cond: String,
+        body: Vec<Node>,
+        otherwise: Option<Vec<Node>>,
+        pos: Pos,
+    },
+    /// `@each $a, $b in list`, with `vars` as written.
+    Each {
+        vars: String,
+        list: String,
+        body: Vec<Node>,
+        pos: Pos,
+    },
+    For {
+        var: String,
+        from: String,
+        to: String,
+        through: bool,
+        body: Vec<Node>,
+        pos: Pos,
+    },
+    While { cond: String, body: Vec<Node>, pos: Pos },
+}
+
+impl Node {
+    /// The bodies nested in this node.
+    pub fn bodies_mut(&mut self) -> impl Iterator<Item = &mut Vec<Node>> {
+        let (a, b) = match self {
+            Node::Rule { body, .. }
+            | Node::AtBlock { body, .. }
+            | Node::Mixin { body, .. }
+            | Node::Function { body, .. }
+            | Node::Each { body, .. }
+            | Node::For { body, .. }
+            | Node::While { body, .. } => (Some(body), None),
+            Node::Include { content, .. } => (content.as_mut(), None),
+            Node::If { body, otherwise, .. } => (Some(body), otherwise.as_mut()),
+            _ => (None, None),
+        };
+        a.into_iter().chain(b)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeId(u32);
+
+/// A byte range of one of the arena's sources.
+#[derive(Clone, Copy, Debug)]
+pub struct Span {
+    file: u32,
+    start: u32,
+    end: u32,
+}
+
+/// Text still in its source file, or made by the parser or loader.
+#[derive(Clone, Copy, Debug)]
+pub enum Text {
+    Span(Span),
+    Sym(Sym),
+}
+
+/// A rule or at-rule body: a run of `Arena::children`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Block {
+    start: u32,
+    len: u32,
+}
+
+/// A selector list: a run of `Arena::selectors`.
+#[derive(Clone, Copy, Debug)]
+pub struct Selectors {
+    start: u32,
+    len: u32,
+}
+
+#[derive(Clone, Debug)]
+pub enum Stmt {
+    VarDecl { name: Sym, value: Text, pos: Pos },
+    Rule { selectors: Selectors, body: Block },
+    Decl { prop: Sym, value: Text, pos: Pos },
+    RawAt { text: Text },
+    Comment { text: Text },
+    Import { text: Text, pos: Pos },
+    Log { level: LogLevel, message: Text, pos: Pos },
+    AtBlock { name: Sym, prelude: Text, body: Block },
+    Mixin { name: Sym, params: Text, body: Block },
+    Function { name: Sym, params: Text, body: Block },
+    Include {
+        name: Sym,
+        args: Text,
+        content: Option<Block>,
+        pos: Pos,
+    },
+    Content { pos: Pos },
+    Return { value: Text, pos: Pos },
+    Extend { selector: Text, pos: Pos },
+    If {
+        cond: Text,
+        body: Block,
+        otherwise: Option<Block>,
+        pos: Pos,
+    },
+    Each {
+        vars: Text,
+        list: Text,
+        body: Block,
+        pos: Pos,
+    },
+    For {
+        var: Sym,
+        from: Text,
+        to: Text,
+        through: bool,
+        body: Block,
+        pos: Pos,
+    },
+    While { cond: Text, body: Block, pos: Pos },
+}
+
+impl Stmt {
+    /// The bodies nested in this statement.
+    pub fn blocks(&self) -> impl Iterator<Item = Block> {
+        let (a, b) = match *self {
+            Stmt::Rule { body, .. }
+            | Stmt::AtBlock { body, .. }
+            | Stmt::Mixin { body, .. }
+            | Stmt::Function { body, .. }
+            | Stmt::Each { body, .. }
+            | Stmt::For { body, .. }
+            | Stmt::While { body, .. } => (Some(body), None),
+            Stmt::Include { content, .. } => (content, None),
+            Stmt::If { body, otherwise, .. } => (Some(body), otherwise),
+            _ => (None, None),
+        };
+        a.into_iter().chain(b)
+    }
+}
+
+/// A loaded stylesheet, for stack traces: the statements from `first` up to
+/// the next file's came from it.
+#[derive(Clone, Debug)]
+pub struct SourceFile {
+    first: u32,
+    pub path: PathBuf,
+    /// The `@import`s that led to it, innermost first; empty for the entry.
+    pub imports: Vec<Frame>,
+    /// Loaded through an include path, for `--quiet-deps`.
+    pub dep: bool,
+}
+
+/// Everything one compile parses: the sources, their statements and the
+/// strings interned from them. Statements point at each other by index and
+/// at their text by span, so parsing allocates little besides these vectors.
+#[derive(Clone, Debug, Default)]
+pub struct Arena {
+    sources: Vec<Rc<str>>,
+    stmts: Vec<Stmt>,
+    children: Vec<NodeId>,
+    selectors: Vec<Sym>,
+    files: Vec<SourceFile>,
+    pub syms: Interner,
+}
+
+impl Arena {
+    /// Adds a file's text, returning its id for spans.
+    pub fn add_source(&mut self, src: &str) -> u32 {
+        self.sources.push(Rc::from(src));
+        self.sources.len() as u32 - 1
+    }
+
+    pub fn source(&self, file: u32) -> Rc<str> {
+        self.sources[file as usize].clone()
+    }
+
+    /// Marks the statements pushed from now on as coming from `path`.
+    pub fn begin_file(&mut self, path: PathBuf, imports: Vec<Frame>, dep: bool) {
+        self.files.push(SourceFile {
+            first: self.stmts.len() as u32,
+            path,
+            imports,
+            dep,
+        });
+    }
+
+    /// The file `id` was loaded from, unless it came from elsewhere.
+    pub fn file_of(&self, id: NodeId) -> Option<&SourceFile> {
+        let k = self.files.partition_point(|f| f.first <= id.0);
+        k.checked_sub(1).map(|k| &self.files[k])
+    }
+
+    /// Makes room for about `stmts` more statements.
+    pub fn reserve(&mut self, stmts: usize) {
+        self.stmts.reserve(stmts);
+        self.children.reserve(stmts);
+    }
+
+    pub fn push(&mut self, stmt: Stmt) -> NodeId {
+        self.stmts.push(stmt);
+        NodeId(self.stmts.len() as u32 - 1)
+    }
+
+    pub fn stmt(&self, id: NodeId) -> &Stmt {
+        &self.stmts[id.0 as usize]
+    }
+
+    pub fn stmt_mut(&mut self, id: NodeId) -> &mut Stmt {
+        &mut self.stmts[id.0 as usize]
+    }
+
+    pub fn block(&mut self, ids: &[NodeId]) -> Block {
+        let start = self.children.len() as u32;
+        self.children.extend_from_slice(ids);
+        Block {
+            start,
+            len: ids.len() as u32,
+        }
+    }
+
+    pub fn body(&self, block: Block) -> &[NodeId] {
+        &self.children[block.start as usize..(block.start + block.len) as usize]
+    }
+
+    pub fn selector_list<'s>(&mut self, selectors: impl IntoIterator<Item = &'s str>) -> Selectors {
+        let start = self.selectors.len() as u32;
+        for s in selectors {
+            let sym = self.syms.intern(s);
+            self.selectors.push(sym);
+        }
+        Selectors {
+            start,
+            len: self.selectors.len() as u32 - start,
+        }
+    }
+
+    pub fn selectors(&self, list: Selectors) -> &[Sym] {
+        &self.selectors[list.start as usize..(list.start + list.len) as usize]
+    }
+
+    pub fn span(file: u32, start: usize, end: usize) -> Text {
+        Text::Span(Span {
+            file,
+            start: start as u32,
+            end: end as u32,
+        })
+    }
+
+    pub fn text(&self, text: Text) -> &str {
+        match text {
+            Text::Span(s) => &self.sources[s.file as usize][s.start as usize..s.end as usize],
+            Text::Sym(sym) => self.syms.get(sym),
+        }
+    }
+
+    pub fn str(&self, sym: Sym) -> &str {
+        self.syms.get(sym)
+    }
+
+    pub fn intern(&mut self, s: &str) -> Sym {
+        self.syms.intern(s)
+    }
+
+    pub fn owned(&mut self, s: &str) -> Text {
+        Text::Sym(self.syms.intern(s))
+    }
+
+    /// Adds owned nodes, returning their ids in order.
+    pub fn import_nodes(&mut self, nodes: &[Node]) -> Vec<NodeId> {
+        nodes.iter().map(|n| self.import_node(n)).collect()
+    }
+
+    fn import_node(&mut self, node: &Node) -> NodeId {
+        let stmt = match node {
+            Node::VarDecl { name, value, pos } => Stmt::VarDecl {
+                name: self.intern(name),
+                value: self.owned(value),
+                pos: *pos,
+            },
+            Node::Rule { selectors, body } => {
+                let body = self.import_block(body);
+                Stmt::Rule {
+                    selectors: self.selector_list(selectors.iter().map(String::as_str)),
+                    body,
+                }
+            }
+            Node::Decl { prop, value, pos } => Stmt::Decl {
+                prop: self.intern(prop),
+                value: self.owned(value),
+                pos: *pos,
+            },
+            Node::RawAt { text } => Stmt::RawAt { text: self.owned(text) },
+            Node::Comment { text } => Stmt::Comment { text: self.owned(text) },
+            Node::Import { text, pos } => Stmt::Import {
+                text: self.owned(text),
+                pos: *pos,
+            },
+            Node::Log { level, message, pos } => Stmt::Log {
+                level: *level,
+                message: self.owned(message),
+                pos: *pos,
+            },
+            Node::AtBlock { name, prelude, body } => Stmt::AtBlock {
+                body: self.import_block(body),
+                name: self.intern(name),
+                prelude: self.owned(prelude),
+            },
+            Node::Mixin { name, params, body } => Stmt::Mixin {
+                body: self.import_block(body),
+                name: self.intern(name),
+                params: self.owned(params),
+            },
+            Node::Function { name, params, body } => Stmt::Function {
+                body: self.import_block(body),
+                name: self.intern(name),
+                params: self.owned(params),
+            },
+            Node::Include {
+                name,
+                args,
+                content,
+                pos,
+            } => Stmt::Include {
+                content: content.as_ref().map(|c| self.import_block(c)),
+                name: self.intern(name),
+                args: self.owned(args),
+                pos: *pos,
+            },
+            Node::Content { pos } => Stmt::Content { pos: *pos },
+            Node::Return { value, pos } => Stmt::Return {
+                value: self.owned(value),
+                pos: *pos,
+            },
+            Node::Extend { selector, pos } => Stmt::Extend {
+                selector: self.owned(selector),
+                pos: *pos,
+            },
+            Node::If {
+                cond,
+                body,
+                otherwise,
+                pos,
+            } => Stmt::If {
+                body: self.import_block(body),
+                otherwise: otherwise.as_ref().map(|o| self.import_block(o)),
+                cond: self.owned(cond),
+                pos: *pos,
+            },
+            Node::Each { vars, list, body, pos } => Stmt::Each {
+                body: self.import_block(body),
+                vars: self.owned(vars),
+                list: self.owned(list),
+                pos: *pos,
+            },
+            Node::For {
+                var,
+                from,
+                to,
+                through,
+                body,
+                pos,
+            } => Stmt::For {
+                body: self.import_block(body),
+                var: self.intern(var),
+                from: self.owned(from),
+                to: self.owned(to),
+                through: *through,
+                pos: *pos,
+            },
+            Node::While { cond, body, pos } => Stmt::While {
+                body: self.import_block(body),
+                cond: self.owned(cond),
+                pos: *pos,
+            },
+        };
+        self.push(stmt)
+    }
+
+    fn import_block(&mut self, nodes: &[Node]) -> Block {
+        let ids = self.import_nodes(nodes);
+        self.block(&ids)
+    }
+
+    pub fn export_nodes(&self, ids: &[NodeId]) -> Vec<Node> {
+        ids.iter().map(|&id| self.export_node(id)).collect()
+    }
+
+    fn export_node(&self, id: NodeId) -> Node {
+        let text = |t: Text| self.text(t).to_string();
+        let sym = |s: Sym| self.str(s).to_string();
+        let block = |b: Block| self.export_nodes(self.body(b));
+        match *self.stmt(id) {
+            Stmt::VarDecl { name, value, pos } => Node::VarDecl {
+                name: sym(name),
+                value: text(value),
+                pos,
+            },
+            Stmt::Rule { selectors, body } => Node::Rule {
+                selectors: self.selectors(selectors).iter().map(|&s| sym(s)).collect(),
+                body: block(body),
+            },
+            Stmt::Decl { prop, value, pos } => Node::Decl {
+                prop: sym(prop),
+                value: text(value),
+                pos,
+            },
+            Stmt::RawAt { text: t } => Node::RawAt { text: text(t) },
+            Stmt::Comment { text: t } => Node::Comment { text: text(t) },
+            Stmt::Import { text: t, pos } => Node::Import { text: text(t), pos },
+            Stmt::Log { level, message, pos } => Node::Log {
+                level,
+                message: text(message),
+                pos,
+            },
+            Stmt::AtBlock { name, prelude, body } => Node::AtBlock {
+                name: sym(name),
+                prelude: text(prelude),
+                body: block(body),
+            },
+            Stmt::Mixin { name, params, body } => Node::Mixin {
+                name: sym(name),
+                params: text(params),
+                body: block(body),
+            },
+            Stmt::Function { name, params, body } => Node::Function {
+                name: sym(name),
+                params: text(params),
+                body: block(body),
+            },
+            Stmt::Include {
+                name,
+                args,
+                content,
+                pos,
+            } => Node::Include {
+                name: sym(name),
+                args: text(args),
+                content: content.map(block),
+                pos,
+            },
+            Stmt::Content { pos } => Node::Content { pos },
+            Stmt::Return { value, pos } => Node::Return { value: text(value), pos },
+            Stmt::Extend { selector, pos } => Node::Extend { selector: text(selector), pos },
+            Stmt::If {
+                cond,
+                body,
+                otherwise,
+                pos,
+            } => Node::If {
+                cond: text(cond),
+                body: block(body),
+                otherwise: otherwise.map(block),
+                pos,
+            },
+            Stmt::Each { vars, list, body, pos } => Node::Each {
+                vars: text(vars),
+                list: text(list),
+                body: block(body),
+                pos,
+            },
+            Stmt::For {
+                var,
+                from,
+                to,
+                through,
+                body,
+                pos,
+            } => Node::For {
+                var: sym(var),
+                from: text(from),
+                to: text(to),
+                through,
+                body: block(body),
+                pos,
+            },
+            Stmt::While { cond, body, pos } => Node::While {
+                cond: text(cond),
+                body: block(body),
+                pos,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scss::parser::parse_scss;
+
+    #[test]
+    fn export_import_round_trip() {
+        let src = r#"
+/* kept */
+$gap: 4px !default;
+@import "a";
+@mixin pad($n: 1) { padding: $n * $gap; @content; }
+@function double($x) { @return $x * 2; }
+@layer base { .a, .b { margin: 0; } }
+@media print { .c { @include pad(2) { color: red; } } }
+@keyframes spin { to { opacity: 1; } }
+@if $gap == 4px { .d { e: f; } } @else if false { .g { h: i; } } @else { .j { k: l; } }
+@each $k, $v in (a: 1) { .x { y: $v; } }
+@for $i from 1 through 2 { .f { w: $i; } }
+@while false { .z { z: z; } }
+.e { @extend .a; @debug "hi"; --raw: { a: b }; font: { size: 1px; } }
+"#;
+        let mut arena = Arena::default();
+        let file = arena.add_source(src);
+        let block = parse_scss(src, file, &mut arena).unwrap();
+        let exported = arena.export_nodes(arena.body(block));
+
+        let mut copy = Arena::default();
+        let ids = copy.import_nodes(&exported);
+        let again = copy.export_nodes(&ids);
+        let json = |n: &[Node]| serde_json::to_string(n).unwrap();
+        assert_eq!(json(&again), json(&exported));
+        assert_eq!(exported.len(), arena.body(block).len());
+    }
+}
@@ -0,0 +1,305 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::log::Message;
+use super::ast::Node;
+use super::Compiled;
+
+/// Crate version plus a revision of the cached node format, bumped whenever
+/// `Node` changes shape so stale ASTs aren't reused.
+pub const COMPILER_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+ast.9");
+
+#[derive(Clone, Debug, Default)]
+pub struct CacheStats {
+    pub entry_hits: usize,
+    pub entry_misses: usize,
+    pub parse_hits: usize,
+    pub parse_misses: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Dep {
+    pub path: PathBuf,
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntryRecord {
+    version: String,
+    deps: Vec<Dep>,
+    #[serde(default)]
+    probed: Vec<PathBuf>,
+    css: String,
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AstRecord {
+    version: String,
+    nodes: Vec<Node>,
+}
+
+/// On-disk compile cache.
+///
+/// Parsed files live under `ast/`, keyed by a hash of their content and the
+/// compiler version, so an edited partial is the only file reparsed. Whole
+/// entries live under `entries/`, keyed by entry path and option fingerprint,
+/// and are served as long as every recorded dependency still hashes the same.
+/// Writes are best-effort: a failed one is reported and the compile goes on.
+pub struct Cache {
+    dir: PathBuf,
+    fingerprint: String,
+    pub stats: CacheStats,
+}
+
+impl Cache {
+    pub fn open(dir: &Path, fingerprint: &str) -> Result<Self> {
+        for sub in ["ast", "entries"] {
+            let p = dir.join(sub);
+            std::fs::create_dir_all(&p).with_context(|| format!("mkdir: {}", p.display()))?;
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            fingerprint: fingerprint.to_string(),
+            stats: CacheStats::default(),
+        })
+    }
+
+    pub fn lookup_entry(&mut self, entry: &Path) -> Option<Compiled> {
+        let rec: Option<EntryRecord> = read_json(&self.entry_path(entry));
+        // A file appearing at a probed location would change what an
+        // import resolves to, so it invalidates the entry too.
+        let hit = rec.filter(|r| {
+            r.version == COMPILER_VERSION
+                && deps_unchanged(&r.deps)
+                && !r.probed.iter().any(|p| p.exists())
+        });
+        match hit {
+            Some(r) => {
+                self.stats.entry_hits += 1;
+                Some(Compiled {
+                    css: r.css,
+                    loaded: r.deps.into_iter().map(|d| d.path).collect(),
+                    probed: r.probed,
+                    messages: r.messages,
+                })
+            }
+            None => {
+                self.stats.entry_misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn store_entry(
+        &self,
+        entry: &Path,
+        deps: &[Dep],
+        probed: &[PathBuf],
+        css: &str,
+        messages: &[Message],
+    ) {
+        let rec = EntryRecord {
+            version: COMPILER_VERSION.to_string(),
+            deps: deps.to_vec(),
+            probed: probed.to_vec(),
+            css: css.to_string(),
+            messages: messages.to_vec(),
+        };
+        store(&self.entry_path(entry), &rec);
+    }
+
+    pub fn load_ast(&mut self, content_hash: &str) -> Option<Vec<Node>> {
+        let rec: Option<AstRecord> = read_json(&self.ast_path(content_hash));
+        match rec.filter(|r| r.version == COMPILER_VERSION) {
+            Some(r) => {
+                self.stats.parse_hits += 1;
+                Some(r.nodes)
+            }
+            None => {
+                self.stats.parse_misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn store_ast(&self, content_hash: &str, nodes: &[Node]) {
+        let rec = AstRecord {
+            version: COMPILER_VERSION.to_string(),
+            nodes: nodes.to_vec(),
+        };
+        store(&self.ast_path(content_hash), &rec);
+    }
+
+    fn entry_path(&self, entry: &Path) -> PathBuf {
+        let key = hash_parts(&[
+            COMPILER_VERSION.as_bytes(),
+            self.fingerprint.as_bytes(),
+            entry.to_string_lossy().as_bytes(),
+        ]);
+        self.dir.join("entries").join(format!("{}.json", key))
+    }
+
+    fn ast_path(&self, content_hash: &str) -> PathBuf {
+        self.dir.join("ast").join(format!("{}.json", content_hash))
+    }
+}
+
+pub fn content_hash(bytes: &[u8]) -> String {
+    hash_parts(&[COMPILER_VERSION.as_bytes(), bytes])
+}
+
+fn hash_parts(parts: &[&[u8]]) -> String {
+    let mut h = Sha256::new();
+    for p in parts {
+        h.update((p.len() as u64).to_le_bytes());
+        h.update(p);
+    }
+    format!("{:x}", h.finalize())
+}
+
+fn deps_unchanged(deps: &[Dep]) -> bool {
+    deps.iter().all(|d| match std::fs::read(&d.path) {
+        Ok(bytes) => content_hash(&bytes) == d.hash,
+        Err(_) => false,
+    })
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(p: &Path) -> Option<T> {
+    let text = std::fs::read_to_string(p).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn store<T: Serialize>(p: &Path, value: &T) {
+    if let Err(e) = write_json(p, value) {
+        eprintln!("stylepack: cache not updated: {:#}", e);
+    }
+}
+
+/// Writes through a temp file named for this writer, so compiles running at
+/// once never rename each other's half-written files into place.
+fn write_json<T: Serialize>(p: &Path, value: &T) -> Result<()> {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let n = WRITES.fetch_add(1, Ordering::Relaxed);
+    let tmp = p.with_extension(format!("json.{}-{}.tmp", std::process::id(), n));
+    let text = serde_json::to_string(value)?;
+    std::fs::write(&tmp, text).with_context(|| format!("write: {}", tmp.display()))?;
+    if let Err(e) = std::fs::rename(&tmp, p) {
+        std::fs::remove_file(&tmp).ok();
+        return Err(e).with_context(|| format!("rename: {}", p.display()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scss::{compile_file, CompileOptions};
+
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str) -> Scratch {
+            let dir = std::env::temp_dir().join(format!("stylepack-cache-{}-{}", name, std::process::id()));
+            std::fs::remove_dir_all(&dir).ok();
+            std::fs::create_dir_all(&dir).unwrap();
+            let dir = std::fs::canonicalize(dir).unwrap();
+            std::fs::write(dir.join("main.scss"), "@import \"vars\";\n.a { color: $c; }\n").unwrap();
+            std::fs::write(dir.join("_vars.scss"), "$c: red;\n").unwrap();
+            Scratch(dir)
+        }
+
+        fn compile(&self) -> (String, CacheStats) {
+            let mut cache = Cache::open(&self.0.join("cache"), "test").unwrap();
+            let out = compile_file(&self.0.join("main.scss"), &CompileOptions::default(), Some(&mut cache)).unwrap();
+            (out.css, cache.stats)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.0).ok();
+        }
+    }
+
+    #[test]
+    fn entry_hit_and_miss() {
+        let dir = Scratch::new("entry");
+        let (css, stats) = dir.compile();
+        assert_eq!((stats.entry_hits, stats.entry_misses, stats.parse_misses), (0, 1, 2));
+        let (again, stats) = dir.compile();
+        assert_eq!((stats.entry_hits, stats.entry_misses, stats.parse_misses), (1, 0, 0));
+        assert_eq!(css, again);
+    }
+
+    #[test]
+    fn change_reparses_only_the_edited_file() {
+        let dir = Scratch::new("partial");
+        dir.compile();
+        std::fs::write(dir.0.join("main.scss"), "@import \"vars\";\n.b { color: $c; }\n").unwrap();
+        let (css, stats) = dir.compile();
+        assert_eq!(stats.entry_misses, 1);
+        assert_eq!((stats.parse_hits, stats.parse_misses), (1, 1));
+        assert!(css.contains(".b"));
+    }
+
+    #[test]
+    fn new_file_at_probed_path_invalidates() {
+        let dir = Scratch::new("probed");
+        dir.compile();
+        // `@import "vars"` looked for `vars.scss` before taking the partial.
+        std::fs::write(dir.0.join("vars.scss"), "$c: blue;\n").unwrap();
+        let mut cache = Cache::open(&dir.0.join("cache"), "test").unwrap();
+        assert!(cache.lookup_entry(&dir.0.join("main.scss")).is_none());
+    }
+
+    #[test]
+    fn version_bump_invalidates() {
+        let dir = Scratch::new("version");
+        dir.compile();
+        for sub in ["entries", "ast"] {
+            for f in std::fs::read_dir(dir.0.join("cache").join(sub)).unwrap() {
+                let p = f.unwrap().path();
+                let text = std::fs::read_to_string(&p).unwrap().replace(COMPILER_VERSION, "0.0.0+ast.0");
+                std::fs::write(&p, text).unwrap();
+            }
+        }
+        let (_, stats) = dir.compile();
+        assert_eq!((stats.entry_hits, stats.parse_hits, stats.parse_misses), (0, 0, 2));
+    }
+
+    #[test]
+    fn temp_files_are_per_writer() {
+        let dir = Scratch::new("tmp");
+        let p = dir.0.join("x.json");
+        write_json(&p, &1).unwrap();
+        write_json(&p, &2).unwrap();
+        let left = std::fs::read_dir(&dir.0)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().to_string_lossy().ends_with(".tmp"))
+            .count();
+        assert_eq!(left, 0);
+        assert_eq!(std::fs::read_to_string(&p).unwrap(), "2");
+    }
+}
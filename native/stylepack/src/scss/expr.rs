@@ -0,0 +1,704 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! SassScript operators, over text whose variables, interpolation and
+//! function calls have already been substituted. Anything it can't make
+//! sense of is left as written.
+
+use std::borrow::Cow;
+
+/// Whether `raw`, as written, may use an operator. Cheap, and only has to
+/// rule out the common case.
+pub fn has_operators(raw: &str) -> bool {
+    if raw.contains("/*") {
+        return false;
+    }
+    raw.bytes().any(|c| matches!(c, b'=' | b'<' | b'>' | b'+' | b'*'))
+        || [" - ", " % ", "-$", "-(", " and ", " or ", "not "]
+            .iter()
+            .any(|op| raw.contains(op))
+}
+
+/// Applies the operators in `s`, returning `None` when it has none that
+/// apply or it isn't an expression this understands.
+pub fn evaluate(s: &str) -> Option<String> {
+    let toks = tokenize(s)?;
+    let mut p = Parser {
+        toks: &toks,
+        i: 0,
+        applied: false,
+    };
+    let value = p.comma_list()?;
+    if p.i < toks.len() || !p.applied {
+        return None;
+    }
+    let mut out = String::with_capacity(s.len());
+    value.write(&mut out);
+    Some(out)
+}
+
+/// Sass's truthiness: everything but `false` and `null`.
+pub fn truthy(s: &str) -> bool {
+    !matches!(s.trim(), "false" | "null" | "")
+}
+
+/// A number and its unit, if `s` is just that.
+pub fn number(s: &str) -> Option<(f64, &str)> {
+    match tokenize(s.trim())?.as_slice() {
+        [Token { tok: Tok::Num(n, unit, _), .. }] => Some((*n, *unit)),
+        _ => None,
+    }
+}
+
+/// The elements of a list: split at commas if it has any at the top level,
+/// otherwise at spaces. A map's entries come out whole, as `key: value`.
+pub fn list_items(s: &str) -> Vec<&str> {
+    let s = strip_parens(s.trim());
+    let items = split_commas(s);
+    if items.len() > 1 {
+        return items.into_iter().map(str::trim).filter(|x| !x.is_empty()).collect();
+    }
+    split_top(s, |c| c.is_ascii_whitespace())
+        .into_iter()
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// What `@each` binds for one element when it has several variables: a
+/// map entry's key and value, or the items of a nested list.
+pub fn destructure(item: &str) -> Vec<&str> {
+    if let Some((k, v)) = split_map_entry(item) {
+        return vec![k, v];
+    }
+    list_items(item)
+}
+
+/// A map entry as one `@each` variable sees it: `key value`.
+pub fn entry_as_list(item: &str) -> Cow<'_, str> {
+    match split_map_entry(item) {
+        Some((k, v)) => Cow::Owned(format!("{} {}", k, v)),
+        None => Cow::Borrowed(item),
+    }
+}
+
+pub fn split_commas(s: &str) -> Vec<&str> {
+    split_top(s, |c| c == b',')
+}
+
+fn split_map_entry(item: &str) -> Option<(&str, &str)> {
+    let parts = split_top(item, |c| c == b':');
+    match parts.as_slice() {
+        [k, _] => {
+            let v = &item[k.len() + 1..];
+            Some((k.trim(), v.trim()))
+        }
+        _ => None,
+    }
+}
+
+/// `s` without parentheses around the whole of it.
+fn strip_parens(s: &str) -> &str {
+    if s.starts_with('(') && matching(s.as_bytes(), 0) == Some(s.len() - 1) {
+        s[1..s.len() - 1].trim()
+    } else {
+        s
+    }
+}
+
+/// Splits at bytes matching `sep` outside strings and brackets.
+fn split_top(s: &str, sep: impl Fn(u8) -> bool) -> Vec<&str> {
+    let b = s.as_bytes();
+    let mut out = Vec::new();
+    let (mut depth, mut from, mut i) = (0i32, 0, 0);
+    while i < b.len() {
+        match b[i] {
+            b'"' | b'\'' => {
+                i = skip_string(b, i);
+                continue;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            c if depth == 0 && sep(c) => {
+                out.push(&s[from..i]);
+                from = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    out.push(&s[from..]);
+    out
+}
+
+/// The index just past the string opening at `b[i]`.
+fn skip_string(b: &[u8], i: usize) -> usize {
+    let q = b[i];
+    let mut j = i + 1;
+    while let Some(&c) = b.get(j) {
+        j += 1;
+        if c == q {
+            break;
+        }
+        if c == b'\\' {
+            j += 1;
+        }
+    }
+    j.min(b.len())
+}
+
+/// The index of the bracket closing the one at `b[i]`.
+pub fn matching(b: &[u8], i: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut j = i;
+    while j < b.len() {
+        match b[j] {
+            b'"' | b'\'' => {
+                j = skip_string(b, j);
+                continue;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j);
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tok<'s> {
+    /// Value, unit, and the text as written.
+    Num(f64, &'s str, &'s str),
+    /// A quoted string, quotes included.
+    Str(&'s str),
+    /// Anything else that stands for itself: keywords, colors, function
+    /// calls, maps, `!important`.
+    Word(&'s str),
+    Op(&'static str),
+    Open,
+    Close,
+    Comma,
+    Slash,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'s> {
+    tok: Tok<'s>,
+    /// Whitespace came before it.
+    space: bool,
+}
+
+fn is_ident(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-' || c == b'_' || c >= 0x80
+}
+
+fn tokenize(s: &str) -> Option<Vec<Token<'_>>> {
+    let b = s.as_bytes();
+    let mut out: Vec<Token> = Vec::new();
+    let (mut i, mut space) = (0, false);
+    while i < b.len() {
+        let c = b[i];
+        if c.is_ascii_whitespace() {
+            space = true;
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let next = b.get(i + 1).copied().unwrap_or(0);
+        // Whether a sign here starts a number rather than being an
+        // operator: `1 -2` is a list, `1 - 2` and `1-2` are subtractions.
+        let operand_here = match out.last() {
+            None => true,
+            Some(t) => matches!(t.tok, Tok::Op(_) | Tok::Open | Tok::Comma | Tok::Slash) || space,
+        };
+        let tok = match c {
+            b'"' | b'\'' => {
+                i = skip_string(b, i);
+                Tok::Str(&s[start..i])
+            }
+            b'(' => {
+                let end = matching(b, i)?;
+                if split_top(&s[i + 1..end], |c| c == b':').len() > 1 {
+                    i = end + 1;
+                    Tok::Word(&s[start..i])
+                } else {
+                    i += 1;
+                    Tok::Open
+                }
+            }
+            b')' => {
+                i += 1;
+                Tok::Close
+            }
+            b'[' => {
+                i = matching(b, i)? + 1;
+                Tok::Word(&s[start..i])
+            }
+            b',' => {
+                i += 1;
+                Tok::Comma
+            }
+            b'/' => {
+                i += 1;
+                Tok::Slash
+            }
+            b'=' | b'!' if next == b'=' => {
+                i += 2;
+                Tok::Op(if c == b'=' { "==" } else { "!=" })
+            }
+            b'!' => {
+                i += 1;
+                while b.get(i).is_some_and(|&c| is_ident(c)) {
+                    i += 1;
+                }
+                Tok::Word(&s[start..i])
+            }
+            b'<' | b'>' => {
+                let eq = next == b'=';
+                i += 1 + eq as usize;
+                Tok::Op(match (c, eq) {
+                    (b'<', false) => "<",
+                    (b'<', true) => "<=",
+                    (_, false) => ">",
+                    (_, true) => ">=",
+                })
+            }
+            b'u' | b'U' if next == b'+' => {
+                // A unicode-range, not an addition.
+                i += 2;
+                while b.get(i).is_some_and(|&c| c.is_ascii_hexdigit() || c == b'?' || c == b'-') {
+                    i += 1;
+                }
+                Tok::Word(&s[start..i])
+            }
+            b'0'..=b'9' | b'.' => {
+                let (n, unit, len) = read_number(&s[i..])?;
+                i += len;
+                Tok::Num(n, unit, &s[start..i])
+            }
+            b'+' | b'-' if operand_here && (next.is_ascii_digit() || next == b'.') => {
+                let (n, unit, len) = read_number(&s[i + 1..])?;
+                i += 1 + len;
+                Tok::Num(if c == b'-' { -n } else { n }, unit, &s[start..i])
+            }
+            b'-' if operand_here && is_ident(next) && !next.is_ascii_digit() => read_word(s, &mut i)?,
+            b'+' | b'-' | b'*' | b'%' => {
+                i += 1;
+                Tok::Op(match c {
+                    b'+' => "+",
+                    b'-' => "-",
+                    b'*' => "*",
+                    _ => "%",
+                })
+            }
+            b'#' => {
+                i += 1;
+                while b.get(i).is_some_and(|&c| is_ident(c)) {
+                    i += 1;
+                }
+                Tok::Word(&s[start..i])
+            }
+            c if is_ident(c) => read_word(s, &mut i)?,
+            _ => return None,
+        };
+        out.push(Token { tok, space });
+        space = false;
+    }
+    Some(out)
+}
+
+/// An identifier, keyword operator or function call at `s[*i]`.
+fn read_word<'s>(s: &'s str, i: &mut usize) -> Option<Tok<'s>> {
+    let b = s.as_bytes();
+    let start = *i;
+    while b.get(*i).is_some_and(|&c| is_ident(c)) {
+        *i += 1;
+    }
+    if b.get(*i) == Some(&b'(') {
+        *i = matching(b, *i)? + 1;
+        return Some(Tok::Word(&s[start..*i]));
+    }
+    Some(match &s[start..*i] {
+        "and" => Tok::Op("and"),
+        "or" => Tok::Op("or"),
+        "not" => Tok::Op("not"),
+        w => Tok::Word(w),
+    })
+}
+
+/// A number at the start of `s`, with its unit and length.
+fn read_number(s: &str) -> Option<(f64, &str, usize)> {
+    let b = s.as_bytes();
+    let mut i = 0;
+    while b.get(i).is_some_and(u8::is_ascii_digit) {
+        i += 1;
+    }
+    if b.get(i) == Some(&b'.') && b.get(i + 1).is_some_and(u8::is_ascii_digit) {
+        i += 1;
+        while b.get(i).is_some_and(u8::is_ascii_digit) {
+            i += 1;
+        }
+    }
+    if matches!(b.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(b.get(j), Some(b'+' | b'-')) {
+            j += 1;
+        }
+        if b.get(j).is_some_and(u8::is_ascii_digit) {
+            i = j;
+            while b.get(i).is_some_and(u8::is_ascii_digit) {
+                i += 1;
+            }
+        }
+    }
+    let n: f64 = s[..i].parse().ok()?;
+    let unit_start = i;
+    if b.get(i) == Some(&b'%') {
+        i += 1;
+    } else {
+        while b.get(i).is_some_and(u8::is_ascii_alphabetic) {
+            i += 1;
+        }
+    }
+    Some((n, &s[unit_start..i], i))
+}
+
+#[derive(Clone, Debug)]
+enum Value<'s> {
+    /// Value, unit, and the text as written when it hasn't been computed.
+    Num(f64, &'s str, Option<&'s str>),
+    Str(Cow<'s, str>, bool),
+    Bool(bool),
+    Null,
+    Word(&'s str),
+    /// Items, and whether they're comma-separated.
+    List(Vec<Value<'s>>, bool),
+    Slash(Box<Value<'s>>, Box<Value<'s>>),
+}
+
+impl Value<'_> {
+    fn write(&self, out: &mut String) {
+        match self {
+            Value::Num(_, _, Some(text)) => out.push_str(text),
+            Value::Num(n, unit, None) => {
+                out.push_str(&format_number(*n));
+                out.push_str(unit);
+            }
+            Value::Str(s, true) => {
+                out.push('"');
+                out.push_str(s);
+                out.push('"');
+            }
+            Value::Str(s, false) => out.push_str(s),
+            Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Value::Null => {}
+            Value::Word(w) => out.push_str(w),
+            Value::List(items, comma) => {
+                let mut first = true;
+                for item in items.iter().filter(|v| !matches!(v, Value::Null)) {
+                    if !first {
+                        out.push_str(if *comma { ", " } else { " " });
+                    }
+                    first = false;
+                    item.write(out);
+                }
+            }
+            Value::Slash(a, b) => {
+                a.write(out);
+                out.push('/');
+                b.write(out);
+            }
+        }
+    }
+
+    /// The text of a string or keyword, without quotes.
+    fn as_str(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Value::Str(s, _) => Some(Cow::Borrowed(s)),
+            Value::Word(w) => Some(Cow::Borrowed(w)),
+            Value::Num(..) | Value::List(..) | Value::Slash(..) => {
+                let mut out = String::new();
+                self.write(&mut out);
+                Some(Cow::Owned(out))
+            }
+            Value::Bool(_) | Value::Null => None,
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        !matches!(self, Value::Bool(false) | Value::Null)
+    }
+}
+
+fn format_number(n: f64) -> String {
+    if (n - n.round()).abs() < 1e-10 {
+        // Also turns -0 into 0.
+        return format!("{}", n.round() as i64);
+    }
+    let s = format!("{:.10}", n);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn same_unit(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// The unit of a result from operands in `a` and `b`; a unitless number
+/// takes the other's unit.
+fn combine_units<'s>(a: &'s str, b: &'s str) -> Option<&'s str> {
+    if a.is_empty() {
+        Some(b)
+    } else if b.is_empty() || same_unit(a, b) {
+        Some(a)
+    } else {
+        None
+    }
+}
+
+fn equals(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Num(x, u, _), Value::Num(y, v, _)) => same_unit(u, v) && (x - y).abs() < 1e-10,
+        (Value::Bool(x), Value::Bool(y)) => x == y,
+        (Value::Null, Value::Null) => true,
+        (Value::List(x, c), Value::List(y, d)) => {
+            c == d && x.len() == y.len() && x.iter().zip(y).all(|(a, b)| equals(a, b))
+        }
+        (Value::Str(..) | Value::Word(_), Value::Str(..) | Value::Word(_)) => a.as_str() == b.as_str(),
+        _ => false,
+    }
+}
+
+struct Parser<'s, 't> {
+    toks: &'t [Token<'s>],
+    i: usize,
+    /// Some operator was evaluated, so the text changes.
+    applied: bool,
+}
+
+impl<'s> Parser<'s, '_> {
+    fn peek(&self) -> Option<Tok<'s>> {
+        self.toks.get(self.i).map(|t| t.tok)
+    }
+
+    fn eat(&mut self, tok: Tok) -> bool {
+        if self.peek() == Some(tok) {
+            self.i += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn comma_list(&mut self) -> Option<Value<'s>> {
+        let first = self.space_list()?;
+        if !self.eat(Tok::Comma) {
+            return Some(first);
+        }
+        let mut items = vec![first];
+        while !matches!(self.peek(), None | Some(Tok::Close)) {
+            items.push(self.space_list()?);
+            if !self.eat(Tok::Comma) {
+                break;
+            }
+        }
+        Some(Value::List(items, true))
+    }
+
+    fn space_list(&mut self) -> Option<Value<'s>> {
+        let mut items = vec![self.binary(0)?];
+        while let Some(t) = self.toks.get(self.i) {
+            let starts_value = matches!(t.tok, Tok::Num(..) | Tok::Str(_) | Tok::Word(_) | Tok::Open | Tok::Op("not"));
+            if !starts_value {
+                break;
+            }
+            if !t.space {
+                return None;
+            }
+            items.push(self.binary(0)?);
+        }
+        Some(match items.len() {
+            1 => items.pop().unwrap(),
+            _ => Value::List(items, false),
+        })
+    }
+
+    /// Binary operators, loosest first.
+    fn binary(&mut self, level: usize) -> Option<Value<'s>> {
+        const LEVELS: [&[&str]; 6] = [&["or"], &["and"], &["==", "!="], &["<", ">", "<=", ">="], &["+", "-"], &["*", "%"]];
+        if level == LEVELS.len() {
+            return self.slash();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(Tok::Op(op)) = self.peek() {
+            if !LEVELS[level].contains(&op) {
+                break;
+            }
+            self.i += 1;
+            let right = self.binary(level + 1)?;
+            left = apply(op, left, right)?;
+            self.applied = true;
+        }
+        Some(left)
+    }
+
+    /// `/` separates rather than divides, as in `font: 12px/1.5`.
+    fn slash(&mut self) -> Option<Value<'s>> {
+        let mut left = self.unary()?;
+        while self.eat(Tok::Slash) {
+            let right = self.unary()?;
+            left = Value::Slash(Box::new(left), Box::new(right));
+        }
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Option<Value<'s>> {
+        match self.peek()? {
+            Tok::Op("not") => {
+                self.i += 1;
+                let v = self.unary()?;
+                self.applied = true;
+                Some(Value::Bool(!v.truthy()))
+            }
+            Tok::Op(op @ ("-" | "+")) => {
+                self.i += 1;
+                let Value::Num(n, unit, _) = self.unary()? else {
+                    return None;
+                };
+                self.applied = true;
+                Some(Value::Num(if op == "-" { -n } else { n }, unit, None))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Option<Value<'s>> {
+        let tok = self.peek()?;
+        self.i += 1;
+        Some(match tok {
+            Tok::Num(n, unit, text) => Value::Num(n, unit, Some(text)),
+            Tok::Str(s) => Value::Str(Cow::Borrowed(&s[1..s.len() - 1]), true),
+            Tok::Word("true") => Value::Bool(true),
+            Tok::Word("false") => Value::Bool(false),
+            Tok::Word("null") => Value::Null,
+            Tok::Word(w) => Value::Word(w),
+            Tok::Open => {
+                if self.eat(Tok::Close) {
+                    return Some(Value::List(Vec::new(), false));
+                }
+                let v = self.comma_list()?;
+                if !self.eat(Tok::Close) {
+                    return None;
+                }
+                v
+            }
+            Tok::Op(_) | Tok::Close | Tok::Comma | Tok::Slash => return None,
+        })
+    }
+}
+
+fn apply<'s>(op: &str, a: Value<'s>, b: Value<'s>) -> Option<Value<'s>> {
+    Some(match op {
+        "or" => match a.truthy() {
+            true => a,
+            false => b,
+        },
+        "and" => match a.truthy() {
+            true => b,
+            false => a,
+        },
+        "==" => Value::Bool(equals(&a, &b)),
+        "!=" => Value::Bool(!equals(&a, &b)),
+        "<" | ">" | "<=" | ">=" => {
+            let (Value::Num(x, u, _), Value::Num(y, v, _)) = (&a, &b) else {
+                return None;
+            };
+            combine_units(u, v)?;
+            Value::Bool(match op {
+                "<" => x < y,
+                ">" => x > y,
+                "<=" => x <= y,
+                _ => x >= y,
+            })
+        }
+        _ => match (&a, &b) {
+            (Value::Num(x, u, _), Value::Num(y, v, _)) => {
+                let (x, y, u, v) = (*x, *y, *u, *v);
+                match op {
+                    "+" => Value::Num(x + y, combine_units(u, v)?, None),
+                    "-" => Value::Num(x - y, combine_units(u, v)?, None),
+                    "%" => Value::Num(x % y, combine_units(u, v)?, None),
+                    _ => match (u.is_empty(), v.is_empty()) {
+                        (_, true) => Value::Num(x * y, u, None),
+                        (true, false) => Value::Num(x * y, v, None),
+                        (false, false) => return None,
+                    },
+                }
+            }
+            // `+` on anything else concatenates, quoted if the left side is.
+            _ if op == "+" => {
+                let quoted = matches!(a, Value::Str(_, true));
+                let text = format!("{}{}", a.as_str()?, b.as_str()?);
+                Value::Str(Cow::Owned(text), quoted)
+            }
+            _ => return None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(evaluate("10px + 5px").as_deref(), Some("15px"));
+        assert_eq!(evaluate("2 * 8px - 1px").as_deref(), Some("15px"));
+        assert_eq!(evaluate("1.5 * 3").as_deref(), Some("4.5"));
+        assert_eq!(evaluate("(1px + 2px) 4px").as_deref(), Some("3px 4px"));
+        assert_eq!(evaluate("\"a\" + b").as_deref(), Some("\"ab\""));
+        assert_eq!(evaluate("1px + 1em"), None);
+    }
+
+    #[test]
+    fn comparisons_and_logic() {
+        assert_eq!(evaluate("3 < 4").as_deref(), Some("true"));
+        assert_eq!(evaluate("a == \"a\"").as_deref(), Some("true"));
+        assert_eq!(evaluate("1px != 1px or false").as_deref(), Some("false"));
+        assert_eq!(evaluate("not null and 2 >= 2").as_deref(), Some("true"));
+    }
+
+    #[test]
+    fn leaves_css_alone() {
+        for s in ["0 -1px", "calc(100% - 32px)", "12px/1.5 serif", "U+0025-00FF", "a, b", "alpha(opacity=50)"] {
+            assert_eq!(evaluate(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(list_items("a b c"), ["a", "b", "c"]);
+        assert_eq!(list_items("(1px 2px, 3px)"), ["1px 2px", "3px"]);
+        assert_eq!(list_items("(primary: red, muted: rgba(0, 0, 0, .5))"), ["primary: red", "muted: rgba(0, 0, 0, .5)"]);
+        assert_eq!(destructure("muted: rgba(0, 0, 0, .5)"), ["muted", "rgba(0, 0, 0, .5)"]);
+        assert_eq!(entry_as_list("a: 1"), "a 1");
+        assert_eq!(number("3px"), Some((3.0, "px")));
+    }
+}
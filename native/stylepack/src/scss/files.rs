@@ -0,0 +1,105 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Where the loader reads stylesheets from: the real filesystem, or a map
+/// of absolute virtual paths (as made by [`normalize`]) to contents for
+/// hosts without one.
+#[derive(Clone, Copy)]
+pub enum Files<'a> {
+    Disk,
+    Memory(&'a BTreeMap<String, &'a str>),
+}
+
+impl<'a> Files<'a> {
+    pub fn is_file(&self, p: &Path) -> bool {
+        match self {
+            Files::Disk => p.is_file(),
+            Files::Memory(map) => map.contains_key(&*key(p)),
+        }
+    }
+
+    pub fn is_dir(&self, p: &Path) -> bool {
+        match self {
+            Files::Disk => p.is_dir(),
+            Files::Memory(map) => {
+                let dir = key(p);
+                let dir = dir.trim_end_matches('/');
+                map.keys()
+                    .any(|k| k.strip_prefix(dir).is_some_and(|rest| rest.len() > 1 && rest.starts_with('/')))
+            }
+        }
+    }
+
+    pub fn read(&self, p: &Path) -> Result<Cow<'a, str>> {
+        match self {
+            Files::Disk => std::fs::read_to_string(p)
+                .map(Cow::Owned)
+                .with_context(|| format!("read: {}", p.display())),
+            Files::Memory(map) => map
+                .get(&*key(p))
+                .map(|s| Cow::Borrowed(*s))
+                .with_context(|| format!("read: {}: no such file", p.display())),
+        }
+    }
+
+    pub fn canonical(&self, p: &Path) -> Result<PathBuf> {
+        match self {
+            Files::Disk => std::fs::canonicalize(p).with_context(|| format!("canonicalize: {}", p.display())),
+            Files::Memory(_) => Ok(match key(p) {
+                Cow::Borrowed(_) => p.to_path_buf(),
+                Cow::Owned(k) => PathBuf::from(k),
+            }),
+        }
+    }
+}
+
+/// Resolves `.` and `..` without touching a filesystem, rooting relative
+/// paths at `/`.
+pub fn normalize(p: &Path) -> String {
+    let mut out = String::with_capacity(p.as_os_str().len() + 1);
+    for c in p.components() {
+        match c {
+            Component::Normal(s) => {
+                out.push('/');
+                out.push_str(&s.to_string_lossy());
+            }
+            Component::ParentDir => out.truncate(out.rfind('/').unwrap_or(0)),
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    if out.is_empty() {
+        out.push('/');
+    }
+    out
+}
+
+/// `p` as a key of a memory map, borrowed when it is already normalized.
+fn key(p: &Path) -> Cow<'_, str> {
+    match p.to_str() {
+        Some(s) if is_normal(s) => Cow::Borrowed(s),
+        _ => Cow::Owned(normalize(p)),
+    }
+}
+
+fn is_normal(s: &str) -> bool {
+    s.starts_with('/') && (s == "/" || s[1..].split('/').all(|seg| !matches!(seg, "" | "." | "..")))
+}
@@ -0,0 +1,113 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use rustc_hash::FxBuildHasher;
+use std::hash::BuildHasher;
+
+/// An interned string. Property names, variable names and selectors repeat
+/// across a stylesheet, so each is stored once and compared as an integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Sym(u32);
+
+impl Sym {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Strings are kept back to back in one buffer and found through an
+/// open-addressed table, so interning a new string doesn't allocate.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    text: String,
+    /// Where each symbol's string ends in `text`.
+    ends: Vec<u32>,
+    /// Symbol index + 1 per slot, 0 if empty. Its length is a power of two
+    /// and at least twice the number of symbols.
+    table: Vec<u32>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, s: &str) -> Sym {
+        if self.table.len() <= self.ends.len() * 2 {
+            self.grow();
+        }
+        let slot = self.slot(s);
+        if let Some(sym) = self.table[slot].checked_sub(1) {
+            return Sym(sym);
+        }
+        self.text.push_str(s);
+        self.ends.push(self.text.len() as u32);
+        let sym = self.ends.len() as u32 - 1;
+        self.table[slot] = sym + 1;
+        Sym(sym)
+    }
+
+    pub fn get(&self, sym: Sym) -> &str {
+        let i = sym.index();
+        let start = if i == 0 { 0 } else { self.ends[i - 1] as usize };
+        &self.text[start..self.ends[i] as usize]
+    }
+
+    /// The slot holding `s`, or the empty one where it belongs.
+    fn slot(&self, s: &str) -> usize {
+        let mask = self.table.len() - 1;
+        let mut slot = FxBuildHasher.hash_one(s) as usize & mask;
+        loop {
+            match self.table[slot] {
+                0 => return slot,
+                n if self.get(Sym(n - 1)) == s => return slot,
+                _ => slot = (slot + 1) & mask,
+            }
+        }
+    }
+
+    fn grow(&mut self) {
+        let len = (self.table.len() * 2).max(256);
+        self.table = vec![0; len];
+        for sym in 0..self.ends.len() as u32 {
+            let slot = self.slot(self.get(Sym(sym)));
+            self.table[slot] = sym + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_string_same_symbol() {
+        let mut i = Interner::default();
+        let a = i.intern(".btn");
+        let b = i.intern("color");
+        assert_ne!(a, b);
+        assert_eq!(i.intern(".btn"), a);
+        assert_eq!((i.get(a), i.get(b)), (".btn", "color"));
+        let empty = i.intern("");
+        assert_eq!(i.get(empty), "");
+    }
+
+    #[test]
+    fn symbols_survive_growth() {
+        let mut i = Interner::default();
+        let syms: Vec<Sym> = (0..1000).map(|n| i.intern(&format!(".c{}", n))).collect();
+        for (n, &sym) in syms.iter().enumerate() {
+            assert_eq!(i.get(sym), format!(".c{}", n));
+            assert_eq!(i.intern(&format!(".c{}", n)), sym);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LogLevel {
+    Debug,
+    Warn,
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Pos {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// One step of a stack trace: where in `file` evaluation was, and what it
+/// was doing there.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Frame {
+    pub file: PathBuf,
+    pub pos: Pos,
+    pub label: String,
+}
+
+/// An evaluated `@debug`, `@warn` or `@error`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Message {
+    pub level: LogLevel,
+    pub text: String,
+    /// Innermost frame first.
+    pub trace: Vec<Frame>,
+    /// Raised by a stylesheet loaded through an include path.
+    pub dep: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LogOptions {
+    /// Don't print warnings or `@debug` output.
+    pub quiet: bool,
+    /// Don't print warnings from stylesheets loaded through include paths.
+    pub quiet_deps: bool,
+}
+
+/// Same message text printed from this many places before the rest are
+/// only counted.
+const MAX_REPEATS: usize = 5;
+
+/// The text of a fatal `@error`, trace included.
+pub fn format_error(m: &Message) -> String {
+    format!("{}\n{}", m.text, format_trace(&m.trace))
+}
+
+/// Prints messages to stderr, deduplicated, and returns how many warnings
+/// were raised by the stylesheets themselves (for `--fatal-warnings`).
+pub fn print_messages(messages: &[Message], opts: &LogOptions) -> usize {
+    let mut printed: HashMap<(LogLevel, &str, Option<&Frame>), ()> = HashMap::new();
+    let mut repeats: HashMap<&str, usize> = HashMap::new();
+    let mut omitted = 0;
+    let mut warnings = 0;
+
+    for m in messages {
+        if printed.insert((m.level, &m.text, m.trace.first()), ()).is_some() {
+            continue;
+        }
+        match m.level {
+            LogLevel::Debug => {
+                if opts.quiet {
+                    continue;
+                }
+                let (file, line) = match m.trace.first() {
+                    Some(f) => (display(&f.file), f.pos.line),
+                    None => ("-".to_string(), 0),
+                };
+                eprintln!("{}:{} DEBUG: {}", file, line, m.text);
+            }
+            LogLevel::Warn | LogLevel::Error => {
+                if !m.dep {
+                    warnings += 1;
+                }
+                if opts.quiet || (opts.quiet_deps && m.dep) {
+                    continue;
+                }
+                let seen = repeats.entry(&m.text).or_default();
+                *seen += 1;
+                if *seen > MAX_REPEATS {
+                    omitted += 1;
+                    continue;
+                }
+                eprintln!("WARNING: {}\n{}\n", m.text, format_trace(&m.trace));
+            }
+        }
+    }
+    if omitted > 0 {
+        eprintln!("{} repetitive warning(s) omitted.", omitted);
+    }
+    warnings
+}
+
+fn format_trace(trace: &[Frame]) -> String {
+    let locs: Vec<String> = trace
+        .iter()
+        .map(|f| format!("{} {}:{}", display(&f.file), f.pos.line, f.pos.col))
+        .collect();
+    let width = locs.iter().map(String::len).max().unwrap_or(0);
+    locs.iter()
+        .zip(trace)
+        .map(|(loc, f)| format!("    {:<width$}  {}", loc, f.label, width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `path` relative to the working directory, for messages.
+pub fn display(path: &Path) -> String {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    path.strip_prefix(&cwd).unwrap_or(path).display().to_string()
+}
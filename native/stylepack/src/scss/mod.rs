@@ -15,132 +15,458 @@
 # along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use anyhow::{bail, Context, Result};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
 
+mod ast;
+mod cache;
+mod expr;
+mod files;
+mod intern;
+mod log;
 mod parser;
 mod render;
+mod resolve;
 mod scan;
 mod value;
 
-pub fn compile_file(entry: &Path, include_paths: &[PathBuf]) -> Result<String> {
-    let mut visited = HashSet::<PathBuf>::new();
-    let src = load_with_imports(entry, include_paths, &mut visited)?;
-    let ast = parser::parse_scss(&src)?;
-    let css = render::render_css(ast)?;
-    Ok(css)
+pub use ast::Node;
+pub use cache::{Cache, Dep};
+pub use log::{print_messages, LogOptions, Message};
+
+use crate::transform::Transform;
+use ast::{Arena, NodeId, Stmt, Text};
+use files::Files;
+use log::{Frame, Pos};
+use resolve::Origin;
+use scan::ImportTarget;
+
+#[derive(Debug, Default, Clone)]
+pub struct CompileOptions {
+    pub include_paths: Vec<PathBuf>,
+    /// Import prefixes such as `@styles`, mapped to directories. Tried
+    /// before the include paths.
+    pub aliases: Vec<(String, PathBuf)>,
+    /// Directory the CSS is written to. When set, relative `url()`s are
+    /// rebased from the file that wrote them onto this directory.
+    pub url_base: Option<PathBuf>,
+    /// Splice local `@import "x.css"` in like a partial instead of leaving
+    /// it to the browser.
+    pub inline_css_imports: bool,
+    /// `$name` values set before evaluation, so they win over `!default`
+    /// assignments. Names are without the `$`; values are used as written.
+    pub defines: Vec<(String, String)>,
+}
+
+pub struct Compiled {
+    pub css: String,
+    /// Every file read, entry first.
+    pub loaded: Vec<PathBuf>,
+    /// Import candidates that were checked and didn't exist.
+    pub probed: Vec<PathBuf>,
+    /// `@debug` and `@warn` output, in evaluation order.
+    pub messages: Vec<Message>,
+}
+
+pub fn compile_file(entry: &Path, opts: &CompileOptions, cache: Option<&mut Cache>) -> Result<Compiled> {
+    compile_file_with(entry, opts, cache, &mut [])
 }
 
-fn load_with_imports(
+/// `compile_file`, running the `before_eval` hooks of `transforms`. A cache
+/// must have been opened with the transforms in its fingerprint.
+pub fn compile_file_with(
     entry: &Path,
-    include_paths: &[PathBuf],
-    visited: &mut HashSet<PathBuf>,
-) -> Result<String> {
+    opts: &CompileOptions,
+    mut cache: Option<&mut Cache>,
+    transforms: &mut [Box<dyn Transform>],
+) -> Result<Compiled> {
     let real = canonical(entry)?;
-    if visited.contains(&real) {
-        return Ok(String::new());
-    }
-    visited.insert(real.clone());
-
-    let content = std::fs::read_to_string(&real)
-        .with_context(|| format!("read: {}", real.display()))?;
-
-    let mut out = String::new();
-    let mut i = 0usize;
-    let bytes = content.as_bytes();
-
-    while i < bytes.len() {
-        if starts_with_kw(bytes, i, b"@import") {
-            let (stmt, next) = read_until_semicolon(&content, i)?;
-            i = next;
-            let path_str = parse_import_path(&stmt)?;
-            let resolved = resolve_import(&path_str, real.parent().unwrap(), include_paths)?;
-            let inlined = load_with_imports(&resolved, include_paths, visited)?;
-            out.push_str(&inlined);
-            out.push('\n');
-            continue;
-        }
-        out.push(bytes[i] as char);
-        i += 1;
+    if let Some(hit) = cache.as_deref_mut().and_then(|c| c.lookup_entry(&real)) {
+        return Ok(hit);
+    }
+
+    let mut loader = Loader::new(opts, cache);
+    let roots = loader.load_entry(&real)?;
+    let roots = loader.before_eval(roots, transforms)?;
+    let mut messages = Vec::new();
+    let css = render::render_css(&loader.arena, &roots, &opts.defines, &mut messages)?;
+
+    if let Some(c) = loader.cache.as_deref() {
+        c.store_entry(&real, &loader.deps, &loader.probed, &css, &messages);
     }
+    Ok(Compiled {
+        css,
+        loaded: loader.deps.into_iter().map(|d| d.path).collect(),
+        probed: loader.probed,
+        messages,
+    })
+}
+
+/// Compiles `entry` once per set of `defines`, loading and parsing its
+/// imports only once. Results are in the order of `variants` and bypass the
+/// entry cache; parsed files are still cached.
+pub fn compile_variants(
+    entry: &Path,
+    opts: &CompileOptions,
+    variants: &[Vec<(String, String)>],
+    cache: Option<&mut Cache>,
+    transforms: &mut [Box<dyn Transform>],
+) -> Result<Vec<Compiled>> {
+    let real = canonical(entry)?;
+    let mut loader = Loader::new(opts, cache);
+    let roots = loader.load_entry(&real)?;
+    let roots = loader.before_eval(roots, transforms)?;
+    let loaded: Vec<PathBuf> = loader.deps.iter().map(|d| d.path.clone()).collect();
 
+    let mut out = Vec::with_capacity(variants.len());
+    for defines in variants {
+        let mut all = opts.defines.clone();
+        all.extend(defines.iter().cloned());
+        let mut messages = Vec::new();
+        let css = render::render_css(&loader.arena, &roots, &all, &mut messages)?;
+        out.push(Compiled {
+            css,
+            loaded: loaded.clone(),
+            probed: loader.probed.clone(),
+            messages,
+        });
+    }
     Ok(out)
 }
 
-fn canonical(p: &Path) -> Result<PathBuf> {
-    Ok(std::fs::canonicalize(p).with_context(|| format!("canonicalize: {}", p.display()))?)
+/// Compiles `source` as though it were the contents of `path`, which only
+/// needs to exist for import resolution. Used for style blocks embedded in
+/// other files; bypasses the entry cache.
+pub fn compile_source(
+    path: &Path,
+    source: &str,
+    opts: &CompileOptions,
+    cache: Option<&mut Cache>,
+) -> Result<Compiled> {
+    let real = canonical(path)?;
+    let loader = Loader::new(opts, cache);
+    loader.compile_source(real, source, opts)
 }
 
-fn starts_with_kw(bytes: &[u8], i: usize, kw: &[u8]) -> bool {
-    if i + kw.len() > bytes.len() {
-        return false;
-    }
-    if &bytes[i..i + kw.len()] != kw {
-        return false;
-    }
-    let prev = if i == 0 { b' ' } else { bytes[i - 1] };
-    if is_ident(prev) {
-        return false;
+/// Compiles `source` with imports read from `files` instead of the
+/// filesystem. Keys are paths such as `_vars.scss` or `lib/grid.scss`,
+/// taken relative to `/`, where `source` itself sits as `/stdin`.
+pub fn compile_string(source: &str, files: &BTreeMap<String, String>, opts: &CompileOptions) -> Result<Compiled> {
+    let files: BTreeMap<String, &str> = files
+        .iter()
+        .map(|(k, v)| (files::normalize(Path::new(k)), v.as_str()))
+        .collect();
+    let mut loader = Loader::new(opts, None);
+    loader.files = Files::Memory(&files);
+    loader.compile_source(PathBuf::from("/stdin"), source, opts)
+}
+
+/// Evaluates the top-level `$variables` of `entry` (imports included) and
+/// returns them in first-declaration order with their final values.
+pub fn top_level_variables(entry: &Path, include_paths: &[PathBuf]) -> Result<Vec<(String, String)>> {
+    let opts = CompileOptions {
+        include_paths: include_paths.to_vec(),
+        ..Default::default()
+    };
+    let mut loader = Loader::new(&opts, None);
+    let roots = loader.load(entry, false)?;
+
+    let arena = &loader.arena;
+    let mut vars = value::Vars::new();
+    let mut order: Vec<&str> = Vec::new();
+    for &id in &roots {
+        if let Stmt::VarDecl { name, value, .. } = arena.stmt(id) {
+            let name = arena.str(*name);
+            vars.assign(name, arena.text(*value));
+            if !order.contains(&name) {
+                order.push(name);
+            }
+        }
     }
-    true
+    Ok(order
+        .into_iter()
+        .filter_map(|name| vars.get(name).map(|v| (name.to_string(), v.to_string())))
+        .collect())
+}
+
+/// Where `@import "path_str"` written in a file in `base` resolves to,
+/// for tools that follow imports without compiling.
+pub fn find_import(path_str: &str, base: &Path, include_paths: &[PathBuf]) -> Option<PathBuf> {
+    let opts = CompileOptions {
+        include_paths: include_paths.to_vec(),
+        ..Default::default()
+    };
+    resolve::resolve(path_str, base, &opts, Files::Disk, &mut Vec::new())
+        .ok()
+        .flatten()
+        .map(|(p, _)| p)
+}
+
+/// Renders an already-built node tree, for front ends other than the SCSS
+/// parser.
+pub fn render_nodes(nodes: Vec<Node>) -> Result<String> {
+    let mut arena = Arena::default();
+    let roots = arena.import_nodes(&nodes);
+    render::render_css(&arena, &roots, &[], &mut Vec::new())
 }
 
-fn is_ident(b: u8) -> bool {
-    (b'A'..=b'Z').contains(&b) || (b'a'..=b'z').contains(&b) || (b'0'..=b'9').contains(&b) || b == b'_' || b == b'-'
+struct Loader<'a> {
+    opts: &'a CompileOptions,
+    cache: Option<&'a mut Cache>,
+    files: Files<'a>,
+    /// Every file loaded so far, parsed.
+    arena: Arena,
+    visited: HashSet<PathBuf>,
+    deps: Vec<Dep>,
+    probed: Vec<PathBuf>,
+    /// The `@import`s currently being followed, outermost first: the
+    /// importing file and where in it.
+    stack: Vec<(PathBuf, Pos)>,
+    /// Plain CSS `@import`s, hoisted to the top of the output.
+    css_imports: Vec<String>,
 }
 
-fn read_until_semicolon(s: &str, start: usize) -> Result<(String, usize)> {
-    let mut i = start;
-    let bytes = s.as_bytes();
-    while i < bytes.len() && bytes[i] != b';' {
-        i += 1;
+impl<'a> Loader<'a> {
+    fn new(opts: &'a CompileOptions, cache: Option<&'a mut Cache>) -> Self {
+        Self {
+            opts,
+            cache,
+            files: Files::Disk,
+            arena: Arena::default(),
+            visited: HashSet::new(),
+            deps: Vec::new(),
+            probed: Vec::new(),
+            stack: Vec::new(),
+            css_imports: Vec::new(),
+        }
     }
-    if i >= bytes.len() {
-        bail!("unterminated statement near {}", start);
+
+    /// Loads the entry with its imports expanded and plain CSS imports
+    /// hoisted to the top.
+    fn load_entry(&mut self, real: &Path) -> Result<Vec<NodeId>> {
+        let roots = self.load(real, false)?;
+        Ok(self.hoist_css_imports(roots))
     }
-    let stmt = s[start..=i].to_string();
-    Ok((stmt, i + 1))
-}
 
-fn parse_import_path(stmt: &str) -> Result<String> {
-    let mut t = stmt.trim().to_string();
-    if !t.starts_with("@import") {
-        bail!("not an import: {}", stmt);
+    fn compile_source(mut self, real: PathBuf, source: &str, opts: &CompileOptions) -> Result<Compiled> {
+        self.visited.insert(real.clone());
+        let roots = self.load_source(&real, source, false)?;
+        let roots = self.hoist_css_imports(roots);
+        let mut messages = Vec::new();
+        let css = render::render_css(&self.arena, &roots, &opts.defines, &mut messages)?;
+        Ok(Compiled {
+            css,
+            loaded: self.deps.into_iter().map(|d| d.path).collect(),
+            probed: self.probed,
+            messages,
+        })
     }
-    t = t["@import".len()..].trim().to_string();
-    if t.ends_with(';') {
-        t.pop();
+
+    /// Plain CSS imports are only valid before any other rule.
+    fn hoist_css_imports(&mut self, roots: Vec<NodeId>) -> Vec<NodeId> {
+        if self.css_imports.is_empty() {
+            return roots;
+        }
+        let mut out: Vec<NodeId> = std::mem::take(&mut self.css_imports)
+            .into_iter()
+            .map(|text| {
+                let text = self.arena.owned(&text);
+                self.arena.push(Stmt::RawAt { text })
+            })
+            .collect();
+        out.extend(roots);
+        out
     }
-    let t = t.trim();
-    if t.starts_with('"') && t.ends_with('"') && t.len() >= 2 {
-        return Ok(t[1..t.len() - 1].to_string());
+
+    /// Runs the `before_eval` hooks, which work on owned nodes.
+    fn before_eval(&mut self, roots: Vec<NodeId>, transforms: &mut [Box<dyn Transform>]) -> Result<Vec<NodeId>> {
+        if transforms.is_empty() {
+            return Ok(roots);
+        }
+        let mut nodes = self.arena.export_nodes(&roots);
+        crate::transform::before_eval(&mut nodes, transforms)?;
+        // Imports are spliced in by now, so stack traces can only point
+        // into the entry.
+        self.arena.begin_file(self.deps[0].path.clone(), Vec::new(), false);
+        Ok(self.arena.import_nodes(&nodes))
     }
-    if t.starts_with('\'') && t.ends_with('\'') && t.len() >= 2 {
-        return Ok(t[1..t.len() - 1].to_string());
+
+    /// `dep` marks stylesheets reached through an include path, directly or
+    /// not, for `--quiet-deps`.
+    fn load(&mut self, entry: &Path, dep: bool) -> Result<Vec<NodeId>> {
+        let real = self.files.canonical(entry)?;
+        if !self.visited.insert(real.clone()) {
+            return Ok(Vec::new());
+        }
+
+        let content = self.files.read(&real)?;
+        self.load_source(&real, &content, dep)
     }
-    bail!("unsupported @import path: {}", stmt)
-}
 
-fn resolve_import(path_str: &str, base: &Path, include_paths: &[PathBuf]) -> Result<PathBuf> {
-    let candidates = scan::import_candidates(path_str);
-    for c in &candidates {
-        let p = base.join(c);
-        if p.exists() {
-            return Ok(p);
+    fn load_source(&mut self, real: &Path, content: &str, dep: bool) -> Result<Vec<NodeId>> {
+        // The hash is only for the cache: deps records and parsed files.
+        let hash = match self.cache {
+            Some(_) => cache::content_hash(content.as_bytes()),
+            None => String::new(),
+        };
+        let cached = self.cache.as_deref_mut().and_then(|c| c.load_ast(&hash));
+        self.deps.push(Dep {
+            path: real.to_path_buf(),
+            hash: hash.clone(),
+        });
+        let label = |outer: bool| if outer { "root stylesheet" } else { "@import" }.to_string();
+        let imports = self
+            .stack
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(k, (f, pos))| Frame {
+                file: f.clone(),
+                pos: *pos,
+                label: label(k == 0),
+            })
+            .collect();
+        self.arena.begin_file(real.to_path_buf(), imports, dep);
+
+        let roots = match cached {
+            Some(nodes) => self.arena.import_nodes(&nodes),
+            None => {
+                let file = self.arena.add_source(content);
+                let src = self.arena.source(file);
+                let block = parser::parse_scss(&src, file, &mut self.arena)
+                    .with_context(|| format!("parse: {}", real.display()))?;
+                let roots = self.arena.body(block).to_vec();
+                if let Some(c) = self.cache.as_deref() {
+                    c.store_ast(&hash, &self.arena.export_nodes(&roots));
+                }
+                roots
+            }
+        };
+
+        if let Some(base) = &self.opts.url_base {
+            rebase_urls(&mut self.arena, &roots, real.parent().unwrap(), base);
+        }
+        self.expand_imports(roots, real, dep)
+    }
+
+    fn expand_imports(&mut self, ids: Vec<NodeId>, file: &Path, dep: bool) -> Result<Vec<NodeId>> {
+        let has_import = ids.iter().any(|&id| matches!(self.arena.stmt(id), Stmt::Import { .. }));
+        if !has_import {
+            for &id in &ids {
+                self.expand_nested(id, file, dep)?;
+            }
+            return Ok(ids);
+        }
+        let base = file.parent().unwrap();
+        let mut out = Vec::with_capacity(ids.len());
+        for id in ids {
+            let Stmt::Import { text, pos } = *self.arena.stmt(id) else {
+                self.expand_nested(id, file, dep)?;
+                out.push(id);
+                continue;
+            };
+            let text = self.arena.text(text);
+            let args = text.strip_prefix("@import").unwrap_or(text);
+            for target in scan::parse_imports(args)? {
+                let path_str = match target {
+                    ImportTarget::Sass(path) => path,
+                    ImportTarget::Css { path: Some(path), .. } if self.opts.inline_css_imports => path,
+                    ImportTarget::Css { text, .. } => {
+                        self.css_imports.push(format!("@import {};", text));
+                        continue;
+                    }
+                };
+                let (resolved, via_include) = self.resolve_import(&path_str, base).with_context(|| {
+                    format!("@import \"{}\" at {} {}:{}", path_str, log::display(file), pos.line, pos.col)
+                })?;
+                self.stack.push((file.to_path_buf(), pos));
+                let loaded = self.load(&resolved, dep || via_include);
+                self.stack.pop();
+                out.extend(loaded?);
+            }
         }
+        Ok(out)
     }
-    for inc in include_paths {
-        for c in &candidates {
-            let p = inc.join(c);
-            if p.exists() {
-                return Ok(p);
+
+    /// Expands imports nested in rules, rebuilding a block only when it had
+    /// one.
+    fn expand_nested(&mut self, id: NodeId, file: &Path, dep: bool) -> Result<()> {
+        if let Stmt::Rule { body, .. } | Stmt::AtBlock { body, .. } = *self.arena.stmt(id) {
+            let children = self.arena.body(body);
+            if children.iter().any(|&c| matches!(self.arena.stmt(c), Stmt::Import { .. })) {
+                let expanded = self.expand_imports(children.to_vec(), file, dep)?;
+                let block = self.arena.block(&expanded);
+                if let Stmt::Rule { body, .. } | Stmt::AtBlock { body, .. } = self.arena.stmt_mut(id) {
+                    *body = block;
+                }
+            } else {
+                for k in 0..children.len() {
+                    let child = self.arena.body(body)[k];
+                    self.expand_nested(child, file, dep)?;
+                }
             }
         }
+        Ok(())
+    }
+
+    /// Returns the resolved path and whether it is a dependency: a package,
+    /// or found through an include path outside the entry's own directory.
+    fn resolve_import(&mut self, path_str: &str, base: &Path) -> Result<(PathBuf, bool)> {
+        let found = resolve::resolve(path_str, base, self.opts, self.files, &mut self.probed)?;
+        let Some((path, origin)) = found else {
+            bail!("Can't find stylesheet to import.");
+        };
+        let dep = match origin {
+            Origin::Relative => false,
+            Origin::Package => true,
+            Origin::IncludePath => {
+                let root = self.deps[0].path.parent().unwrap();
+                !self.files.canonical(&path)?.starts_with(root)
+            }
+        };
+        Ok((path, dep))
     }
-    bail!("cannot resolve import: {}", path_str)
+}
+
+/// Rewrites `url()`s in a single file's statements before they are spliced
+/// into the importing file, while their own directory is still known.
+fn rebase_urls(arena: &mut Arena, ids: &[NodeId], dir: &Path, base: &Path) {
+    for &id in ids {
+        let blocks: Vec<_> = arena.stmt(id).blocks().collect();
+        for block in blocks {
+            let body = arena.body(block).to_vec();
+            rebase_urls(arena, &body, dir, base);
+        }
+        let text = match *arena.stmt(id) {
+            Stmt::VarDecl { value, .. } | Stmt::Decl { value, .. } | Stmt::Return { value, .. } => value,
+            Stmt::RawAt { text } | Stmt::Import { text, .. } => text,
+            Stmt::Include { args, .. } => args,
+            _ => continue,
+        };
+        let old = arena.text(text);
+        if !old.contains("url(") && !old.contains("inline-asset(") {
+            continue;
+        }
+        let new = crate::assets::rebase_urls(old, dir, base);
+        if new == old {
+            continue;
+        }
+        let new: Text = arena.owned(&new);
+        match arena.stmt_mut(id) {
+            Stmt::VarDecl { value, .. } | Stmt::Decl { value, .. } | Stmt::Return { value, .. } => *value = new,
+            Stmt::RawAt { text } | Stmt::Import { text, .. } => *text = new,
+            Stmt::Include { args, .. } => *args = new,
+            _ => {}
+        }
+    }
+}
+
+fn canonical(p: &Path) -> Result<PathBuf> {
+    Files::Disk.canonical(p)
 }
 
 pub fn minify_css(css: &str) -> String {
+    let css = strip_comments(css);
     let mut out = String::with_capacity(css.len());
     let mut prev_space = false;
     for ch in css.chars() {
@@ -162,3 +488,53 @@ pub fn minify_css(css: &str) -> String {
         .replace(" }", "}")
         .replace("} ", "}")
 }
+
+/// Drops `/* */` comments outside strings, keeping `/*! */` ones.
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut in_str: Option<char> = None;
+    let mut rest = css;
+    while let Some(c) = rest.chars().next() {
+        if let Some(q) = in_str {
+            if c == q {
+                in_str = None;
+            }
+        } else if c == '"' || c == '\'' {
+            in_str = Some(c);
+        } else if rest.starts_with("/*") && !rest.starts_with("/*!") {
+            rest = rest[2..].find("*/").map_or("", |e| &rest[2 + e + 2..]);
+            continue;
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(src: &str) -> Result<Compiled> {
+        compile_string(src, &BTreeMap::new(), &CompileOptions::default())
+    }
+
+    #[test]
+    fn bodies_that_never_run_dont_log() {
+        let src = "@mixin m { @error \"m\"; }\n@function f() { @warn \"f\"; @return 1; }\n@if false { @debug \"if\"; }\n.a { b: c; }\n";
+        let out = compile(src).unwrap();
+        assert!(out.messages.is_empty());
+        assert!(out.css.contains("b: c;"));
+    }
+
+    #[test]
+    fn log_traces_have_a_frame_per_call() {
+        let src = "@function f($x) {\n  @warn \"x is #{$x}\";\n  @return $x;\n}\n@mixin m {\n  a: f(1);\n}\n.b {\n  @include m;\n}\n";
+        let out = compile(src).unwrap();
+        let [m] = out.messages.as_slice() else { panic!("{:?}", out.messages) };
+        assert_eq!(m.text, "x is 1");
+        let frames: Vec<(usize, &str)> = m.trace.iter().map(|f| (f.pos.line, f.label.as_str())).collect();
+        assert_eq!(frames, [(2, "f()"), (6, "m()"), (9, "root stylesheet")]);
+        assert!(out.css.contains("a: 1;"));
+    }
+}
@@ -14,313 +14,678 @@
 # You should have received a copy of the GNU General Public License
 # along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use anyhow::{bail, Result};
-
-#[derive(Clone, Debug)]
-pub enum Node {
-    VarDecl { name: String, value: String },
-    Rule { selectors: Vec<String>, body: Vec<Node> },
-    Decl { prop: String, value: String },
-    RawAt { text: String },
-}
 
-#[derive(Clone, Debug)]
-pub struct Ast {
-    pub nodes: Vec<Node>,
-}
+use anyhow::{anyhow, bail, Context, Result};
+use std::borrow::Cow;
+
+use super::ast::{Arena, Block, NodeId, Stmt, Text};
+use super::intern::Sym;
+use super::log::{LogLevel, Pos};
 
-pub fn parse_scss(src: &str) -> Result<Ast> {
-    let mut p = Parser::new(src);
-    let nodes = p.parse_block_until(None)?;
-    Ok(Ast { nodes })
+/// Parses `src`, added to `arena` as `file`, into `arena`.
+pub fn parse_scss(src: &str, file: u32, arena: &mut Arena) -> Result<Block> {
+    // Statements run about 24 bytes of source each.
+    arena.reserve(src.len() / 24);
+    let mut p = Parser::new(src, file, arena);
+    p.parse_block_until(None)
 }
 
 struct Parser<'a> {
     s: &'a str,
+    b: &'a [u8],
     i: usize,
-    n: usize,
+    file: u32,
+    arena: &'a mut Arena,
+    /// Statements of the blocks being parsed, innermost last; each block
+    /// moves its own into the arena when it closes.
+    pending: Vec<NodeId>,
+    /// Where `pos` last counted lines up to: byte, line, start of line.
+    line_mark: (usize, usize, usize),
 }
 
 impl<'a> Parser<'a> {
-    fn new(s: &'a str) -> Self {
-        Self { s, i: 0, n: s.len() }
+    fn new(s: &'a str, file: u32, arena: &'a mut Arena) -> Self {
+        Self {
+            s,
+            b: s.as_bytes(),
+            i: 0,
+            file,
+            arena,
+            pending: Vec::new(),
+            line_mark: (0, 1, 0),
+        }
     }
 
-    fn parse_block_until(&mut self, until: Option<char>) -> Result<Vec<Node>> {
-        let mut out = Vec::new();
+    fn parse_block_until(&mut self, until: Option<u8>) -> Result<Block> {
+        let mark = self.pending.len();
         loop {
-            self.skip_ws_and_comments();
+            self.collect_comments();
             if self.eof() {
                 break;
             }
             if let Some(u) = until {
-                if self.peek_char() == Some(u) {
+                if self.peek() == Some(u) {
                     self.i += 1;
                     break;
                 }
             }
 
-            self.skip_ws_and_comments();
-            if self.eof() {
-                break;
-            }
-
-            if self.peek_char() == Some('$') {
-                out.push(self.parse_var_decl()?);
-                continue;
-            }
-
-            if self.peek_char() == Some('@') {
-                out.push(self.parse_at_stmt()?);
-                continue;
+            match self.peek() {
+                Some(b'$') => {
+                    let stmt = self.parse_var_decl()?;
+                    self.emit(stmt);
+                    continue;
+                }
+                Some(b'@') => {
+                    let stmt = self.parse_at_stmt()?;
+                    self.emit(stmt);
+                    continue;
+                }
+                _ if self.starts_with("--") => {
+                    let stmt = self.parse_custom_property()?;
+                    self.emit(stmt);
+                    continue;
+                }
+                _ => {}
             }
 
-            let head = self.read_until_any(&['{', ';'])?;
-            let head_trim = head.trim().to_string();
+            let pos = self.pos();
+            let head = self.read_until_any(b"{;").trim();
             self.skip_ws_and_comments();
 
-            match self.peek_char() {
-                Some('{') => {
+            match self.peek() {
+                Some(b'{') => {
                     self.i += 1;
-                    let selectors = split_selectors(&head_trim);
-                    let body = self.parse_block_until(Some('}'))?;
-                    out.push(Node::Rule { selectors, body });
+                    if let Some((prop, value)) = nested_property(head) {
+                        let body = self.parse_block_until(Some(b'}'))?;
+                        if !value.is_empty() {
+                            let stmt = Stmt::Decl {
+                                prop: self.arena.intern(prop),
+                                value: self.text(value),
+                                pos,
+                            };
+                            self.emit(stmt);
+                        }
+                        self.prefix_properties(prop, body)?;
+                        continue;
+                    }
+                    let head = strip_selector_comments(head);
+                    let selectors = self.arena.selector_list(split_selectors(&head));
+                    let body = self.parse_block_until(Some(b'}'))?;
+                    self.emit(Stmt::Rule { selectors, body });
                 }
-                Some(';') => {
+                Some(b';') => {
                     self.i += 1;
-                    if let Some((prop, value)) = split_decl(&head_trim) {
-                        out.push(Node::Decl { prop, value });
-                    } else {
-                        bail!("invalid declaration: {}", head_trim);
-                    }
+                    let Some((prop, value)) = split_decl(head) else {
+                        bail!("invalid declaration: {}", head);
+                    };
+                    let stmt = Stmt::Decl {
+                        prop: self.arena.intern(prop),
+                        value: self.text(value),
+                        pos,
+                    };
+                    self.emit(stmt);
                 }
-                _ => bail!("unexpected token near: {}", head_trim),
+                _ => bail!("unexpected token near: {}", head),
             }
         }
-        Ok(out)
+        let block = self.arena.block(&self.pending[mark..]);
+        self.pending.truncate(mark);
+        Ok(block)
     }
 
-    fn parse_var_decl(&mut self) -> Result<Node> {
-        self.expect_char('$')?;
+    fn emit(&mut self, stmt: Stmt) {
+        let id = self.arena.push(stmt);
+        self.pending.push(id);
+    }
+
+    /// The span of `sub`, a slice of the source.
+    fn text(&self, sub: &str) -> Text {
+        let start = sub.as_ptr() as usize - self.s.as_ptr() as usize;
+        Arena::span(self.file, start, start + sub.len())
+    }
+
+    fn parse_var_decl(&mut self) -> Result<Stmt> {
+        let pos = self.pos();
+        self.expect_char(b'$')?;
         let name = self.read_ident()?;
         self.skip_ws_and_comments();
-        self.expect_char(':')?;
-        let value = self.read_until_any(&[';'])?;
-        self.expect_char(';')?;
-        Ok(Node::VarDecl {
-            name: name.trim().to_string(),
-            value: value.trim().to_string(),
+        self.expect_char(b':')?;
+        let value = self.read_until_any(b";");
+        self.expect_char(b';')?;
+        Ok(Stmt::VarDecl {
+            name: self.arena.intern(name.trim()),
+            value: self.text(value.trim()),
+            pos,
+        })
+    }
+
+    /// `--name: value`. The value is kept as written, brackets and all, up
+    /// to a `;` or the end of the block outside any brackets.
+    fn parse_custom_property(&mut self) -> Result<Stmt> {
+        let pos = self.pos();
+        let prop = self.read_until_any(b":;{}").trim();
+        self.expect_char(b':')?;
+        let start = self.i;
+        let mut closers = Vec::new();
+        let mut in_str: Option<u8> = None;
+        while let Some(c) = self.peek() {
+            if let Some(q) = in_str {
+                if c == q {
+                    in_str = None;
+                } else if c == b'\\' {
+                    self.i += 2;
+                    continue;
+                }
+            } else {
+                match c {
+                    b'"' | b'\'' => in_str = Some(c),
+                    b'(' => closers.push(b')'),
+                    b'[' => closers.push(b']'),
+                    b'{' => closers.push(b'}'),
+                    b';' | b'}' if closers.is_empty() => break,
+                    b')' | b']' | b'}' if closers.last() == Some(&c) => {
+                        closers.pop();
+                    }
+                    b')' | b']' | b'}' => bail!("unbalanced '{}' in custom property {}", c as char, prop),
+                    _ => {}
+                }
+            }
+            self.i += 1;
+        }
+        self.i = self.i.min(self.b.len());
+        let value = self.s[start..self.i].trim();
+        if self.peek() == Some(b';') {
+            self.i += 1;
+        }
+        Ok(Stmt::Decl {
+            prop: self.arena.intern(prop),
+            value: self.text(value),
+            pos,
         })
     }
 
-    fn parse_at_stmt(&mut self) -> Result<Node> {
-        let text = self.read_until_any(&[';','{'])?;
+    fn parse_at_stmt(&mut self) -> Result<Stmt> {
+        let pos = self.pos();
+        let text = self.read_until_any(b";{}").trim();
         self.skip_ws_and_comments();
-        match self.peek_char() {
-            Some(';') => {
+        let (kw, rest) = split_at_keyword(text);
+        match self.peek() {
+            Some(b'{') => {
                 self.i += 1;
-                Ok(Node::RawAt { text: text.trim().to_string() })
+                self.parse_at_block(kw, rest, pos)
             }
-            Some('{') => {
+            Some(b';') => {
                 self.i += 1;
-                let inner = self.parse_block_until(Some('}'))?;
-                let mut rebuilt = String::new();
-                rebuilt.push_str(text.trim());
-                rebuilt.push_str(" {");
-                rebuilt.push_str(&serialize_nodes_as_css_like(&inner));
-                rebuilt.push('}');
-                Ok(Node::RawAt { text: rebuilt })
+                self.parse_at_statement(kw, rest, text, pos)
             }
+            // The last statement of a block may leave out its `;`.
+            Some(b'}') | None => self.parse_at_statement(kw, rest, text, pos),
             _ => bail!("invalid @ rule: {}", text),
         }
     }
 
-    fn skip_ws_and_comments(&mut self) {
-        loop {
-            while let Some(c) = self.peek_char() {
-                if c.is_whitespace() {
-                    self.i += 1;
-                } else {
-                    break;
-                }
+    fn parse_at_statement(&mut self, kw: &str, rest: &'a str, text: &'a str, pos: Pos) -> Result<Stmt> {
+        let level = match kw {
+            "debug" => LogLevel::Debug,
+            "warn" => LogLevel::Warn,
+            "error" => LogLevel::Error,
+            "import" => {
+                return Ok(Stmt::Import {
+                    text: self.text(text),
+                    pos,
+                })
+            }
+            "include" => {
+                let (name, args) = self.callable(rest)?;
+                return Ok(Stmt::Include {
+                    name,
+                    args,
+                    content: None,
+                    pos,
+                });
+            }
+            "content" => return Ok(Stmt::Content { pos }),
+            "extend" => {
+                return Ok(Stmt::Extend {
+                    selector: self.text(rest),
+                    pos,
+                })
+            }
+            "return" => {
+                return Ok(Stmt::Return {
+                    value: self.text(rest),
+                    pos,
+                })
             }
+            _ => {
+                return Ok(Stmt::RawAt {
+                    text: self.arena.owned(&format!("{};", text)),
+                })
+            }
+        };
+        Ok(Stmt::Log {
+            level,
+            message: self.text(rest),
+            pos,
+        })
+    }
 
-            if self.starts_with("/*") {
-                self.i += 2;
-                while !self.eof() && !self.starts_with("*/") {
-                    self.i += 1;
+    fn parse_at_block(&mut self, kw: &str, rest: &'a str, pos: Pos) -> Result<Stmt> {
+        let context = || format!("@{} at {}:{}", kw, pos.line, pos.col);
+        Ok(match kw {
+            "mixin" | "function" => {
+                let (name, params) = self.callable(rest).with_context(context)?;
+                let body = self.parse_block_until(Some(b'}'))?;
+                match kw {
+                    "mixin" => Stmt::Mixin { name, params, body },
+                    _ => Stmt::Function { name, params, body },
                 }
-                if self.starts_with("*/") {
-                    self.i += 2;
+            }
+            "include" => {
+                let (name, args) = self.callable(rest).with_context(context)?;
+                let body = self.parse_block_until(Some(b'}'))?;
+                Stmt::Include {
+                    name,
+                    args,
+                    content: Some(body),
+                    pos,
                 }
-                continue;
             }
-
-            if self.starts_with("//") {
-                while !self.eof() {
-                    let c = self.peek_char().unwrap();
-                    self.i += 1;
-                    if c == '\n' {
-                        break;
+            "if" => {
+                let body = self.parse_block_until(Some(b'}'))?;
+                let otherwise = self.parse_else()?;
+                Stmt::If {
+                    cond: self.text(rest),
+                    body,
+                    otherwise,
+                    pos,
+                }
+            }
+            "else" => bail!("@else at {}:{} must follow an @if", pos.line, pos.col),
+            "each" => {
+                let Some((vars, list)) = split_word(rest, "in") else {
+                    bail!("expected \"in\" in @each at {}:{}", pos.line, pos.col);
+                };
+                let body = self.parse_block_until(Some(b'}'))?;
+                Stmt::Each {
+                    vars: self.text(vars),
+                    list: self.text(list),
+                    body,
+                    pos,
+                }
+            }
+            "for" => {
+                let parsed = rest.strip_prefix('$').and_then(|r| {
+                    let (var, range) = r.split_once(char::is_whitespace)?;
+                    let range = range.trim_start().strip_prefix("from")?;
+                    match split_word(range, "through") {
+                        Some((from, to)) => Some((var, from, to, true)),
+                        None => split_word(range, "to").map(|(from, to)| (var, from, to, false)),
                     }
+                });
+                let Some((var, from, to, through)) = parsed else {
+                    bail!("expected \"$var from <start> through|to <end>\" in @for at {}:{}", pos.line, pos.col);
+                };
+                let body = self.parse_block_until(Some(b'}'))?;
+                Stmt::For {
+                    var: self.arena.intern(var),
+                    from: self.text(from),
+                    to: self.text(to),
+                    through,
+                    body,
+                    pos,
                 }
-                continue;
             }
+            "while" => Stmt::While {
+                body: self.parse_block_until(Some(b'}'))?,
+                cond: self.text(rest),
+                pos,
+            },
+            _ => Stmt::AtBlock {
+                body: self.parse_block_until(Some(b'}'))?,
+                name: self.arena.intern(kw),
+                prelude: self.text(rest),
+            },
+        })
+    }
 
-            break;
+    /// The `@else` or `@else if` chain after an `@if` body, if any. An
+    /// `@else if` becomes an `If` alone in the block.
+    fn parse_else(&mut self) -> Result<Option<Block>> {
+        let save = self.i;
+        self.skip_ws_and_comments();
+        let is_else = self.starts_with("@else")
+            && !self
+                .b
+                .get(self.i + 5)
+                .is_some_and(|&c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_');
+        if !is_else {
+            self.i = save;
+            return Ok(None);
         }
+        let pos = self.pos();
+        let text = self.read_until_any(b"{;}").trim();
+        self.expect_char(b'{')?;
+        let body = self.parse_block_until(Some(b'}'))?;
+        let rest = text["@else".len()..].trim_start();
+        let cond = rest
+            .strip_prefix("if")
+            .filter(|c| c.is_empty() || c.starts_with(|c: char| c.is_whitespace() || c == '('));
+        let Some(cond) = cond else {
+            return Ok(Some(body));
+        };
+        let stmt = Stmt::If {
+            cond: self.text(cond.trim()),
+            body,
+            otherwise: self.parse_else()?,
+            pos,
+        };
+        let id = self.arena.push(stmt);
+        Ok(Some(self.arena.block(&[id])))
     }
 
-    fn read_until_any(&mut self, stops: &[char]) -> Result<String> {
-        let start = self.i;
-        let mut depth_paren = 0i32;
-        let mut in_str: Option<char> = None;
-
-        while !self.eof() {
-            let c = self.peek_char().unwrap();
+    /// Splits `name(args)`, as written after `@include` or `@mixin`, into
+    /// the name, with `_` read as `-` like Sass does, and the text between
+    /// the parentheses.
+    fn callable(&mut self, head: &'a str) -> Result<(Sym, Text)> {
+        let end = head
+            .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(head.len());
+        if end == 0 {
+            bail!("expected a name: {}", head);
+        }
+        let rest = head[end..].trim_start();
+        let args = match rest.strip_prefix('(') {
+            Some(inner) => inner.strip_suffix(')').ok_or_else(|| anyhow!("expected ')': {}", head))?,
+            None if rest.is_empty() => rest,
+            None => bail!("unexpected {} after {}", rest, &head[..end]),
+        };
+        let name = self.arena.intern(&head[..end].replace('_', "-"));
+        Ok((name, self.text(args.trim())))
+    }
 
-            if let Some(q) = in_str {
-                self.i += 1;
-                if c == q {
-                    in_str = None;
-                } else if c == '\\' && !self.eof() {
-                    self.i += 1;
+    /// Turns the body of `prop: { ... }` into `prop-name` declarations in
+    /// the enclosing block.
+    fn prefix_properties(&mut self, prop: &str, body: Block) -> Result<()> {
+        for i in 0..self.arena.body(body).len() {
+            let id = self.arena.body(body)[i];
+            match self.arena.stmt(id).clone() {
+                Stmt::Decl { prop: name, value, pos } => {
+                    let full = format!("{}-{}", prop, self.arena.str(name));
+                    let prop = self.arena.intern(&full);
+                    *self.arena.stmt_mut(id) = Stmt::Decl { prop, value, pos };
                 }
-                continue;
+                Stmt::Rule { selectors, .. } => {
+                    let names: Vec<&str> = self.arena.selectors(selectors).iter().map(|s| self.arena.str(*s)).collect();
+                    bail!("nested rule {} inside property {}", names.join(", "), prop)
+                }
+                _ => {}
             }
+            self.pending.push(id);
+        }
+        Ok(())
+    }
 
-            if c == '"' || c == '\'' {
-                in_str = Some(c);
-                self.i += 1;
-                continue;
+    fn pos(&mut self) -> Pos {
+        let (from, mut line, mut line_start) = self.line_mark;
+        for (k, &c) in self.b[from..self.i].iter().enumerate() {
+            if c == b'\n' {
+                line += 1;
+                line_start = from + k + 1;
             }
+        }
+        self.line_mark = (self.i, line, line_start);
+        Pos {
+            line,
+            col: self.s[line_start..self.i].chars().count() + 1,
+        }
+    }
 
-            if c == '(' {
-                depth_paren += 1;
-                self.i += 1;
-                continue;
-            }
-            if c == ')' {
-                depth_paren -= 1;
-                self.i += 1;
-                continue;
+    /// Like `skip_ws_and_comments`, but keeps `/* */` comments as nodes.
+    fn collect_comments(&mut self) {
+        loop {
+            self.skip_ws_and_comments_until_loud();
+            if !self.starts_with("/*") {
+                break;
             }
+            let start = self.i;
+            let end = self.s[start + 2..].find("*/").map_or(self.b.len(), |e| start + 2 + e + 2);
+            self.i = end;
+            let text = self.text(&self.s[start..end]);
+            self.emit(Stmt::Comment { text });
+        }
+    }
 
-            if depth_paren == 0 && stops.contains(&c) {
+    fn skip_ws_and_comments_until_loud(&mut self) {
+        loop {
+            self.skip_ws();
+            if !self.starts_with("//") {
                 break;
             }
+            self.skip_line();
+        }
+    }
+
+    fn skip_ws_and_comments(&mut self) {
+        loop {
+            self.skip_ws();
+            if self.starts_with("/*") {
+                self.i = self.s[self.i + 2..].find("*/").map_or(self.b.len(), |e| self.i + 2 + e + 2);
+                continue;
+            }
+            if self.starts_with("//") {
+                self.skip_line();
+                continue;
+            }
+            break;
+        }
+    }
 
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
             self.i += 1;
         }
+    }
 
-        Ok(self.s[start..self.i].to_string())
+    fn skip_line(&mut self) {
+        self.i = self.s[self.i..].find('\n').map_or(self.b.len(), |e| self.i + e + 1);
     }
 
-    fn read_ident(&mut self) -> Result<String> {
-        let start = self.i;
-        while let Some(c) = self.peek_char() {
-            let ok = c.is_ascii_alphanumeric() || c == '_' || c == '-';
-            if !ok {
-                break;
+    /// Reads up to the first of `stops` outside strings, parentheses and
+    /// `#{}`.
+    fn read_until_any(&mut self, stops: &[u8]) -> &'a str {
+        let (b, start) = (self.b, self.i);
+        let mut i = start;
+        let mut depth_paren = 0i32;
+        while let Some(&c) = b.get(i) {
+            if !SPECIAL[c as usize] {
+                i += 1;
+                continue;
+            }
+            match c {
+                b'"' | b'\'' => {
+                    i = skip_string(b, i);
+                    continue;
+                }
+                b'#' if b.get(i + 1) == Some(&b'{') => {
+                    i = b[i..].iter().position(|&c| c == b'}').map_or(b.len(), |e| i + e + 1);
+                    continue;
+                }
+                b'(' => depth_paren += 1,
+                b')' => depth_paren -= 1,
+                _ if depth_paren == 0 && stops.contains(&c) => break,
+                _ => {}
             }
+            i += 1;
+        }
+        self.i = i.min(b.len());
+        &self.s[start..self.i]
+    }
+
+    fn read_ident(&mut self) -> Result<&'a str> {
+        let start = self.i;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'-')
+        {
             self.i += 1;
         }
         if self.i == start {
             bail!("expected identifier");
         }
-        Ok(self.s[start..self.i].to_string())
+        Ok(&self.s[start..self.i])
     }
 
-    fn expect_char(&mut self, ch: char) -> Result<()> {
+    fn expect_char(&mut self, ch: u8) -> Result<()> {
         self.skip_ws_and_comments();
-        if self.peek_char() == Some(ch) {
+        if self.peek() == Some(ch) {
             self.i += 1;
             Ok(())
         } else {
-            bail!("expected '{}'", ch)
+            bail!("expected '{}'", ch as char)
         }
     }
 
-    fn peek_char(&self) -> Option<char> {
-        self.s[self.i..].chars().next()
+    fn peek(&self) -> Option<u8> {
+        self.b.get(self.i).copied()
     }
 
     fn eof(&self) -> bool {
-        self.i >= self.n
+        self.i >= self.b.len()
     }
 
     fn starts_with(&self, t: &str) -> bool {
-        self.s[self.i..].starts_with(t)
+        self.b[self.i..].starts_with(t.as_bytes())
     }
 }
 
-fn split_decl(s: &str) -> Option<(String, String)> {
+/// Bytes `read_until_any` stops to look at: quotes, `#`, parentheses and
+/// every stop it is called with.
+const SPECIAL: [bool; 256] = {
+    let mut t = [false; 256];
+    let mut k = 0;
+    let special = b"\"'#();:{}";
+    while k < special.len() {
+        t[special[k] as usize] = true;
+        k += 1;
+    }
+    t
+};
+
+/// The index just past the string opening at `b[i]`.
+fn skip_string(b: &[u8], i: usize) -> usize {
+    let q = b[i];
+    let mut j = i + 1;
+    while let Some(&c) = b.get(j) {
+        j += 1;
+        if c == q {
+            break;
+        }
+        if c == b'\\' {
+            j += 1;
+        }
+    }
+    j
+}
+
+/// Splits `prop: value` at the first colon outside strings and parentheses.
+fn split_decl(s: &str) -> Option<(&str, &str)> {
     let mut depth = 0i32;
-    let mut in_str: Option<char> = None;
-    for (idx, c) in s.char_indices() {
+    let mut in_str: Option<u8> = None;
+    let mut escaped = false;
+    for (idx, c) in s.bytes().enumerate() {
         if let Some(q) = in_str {
-            if c == q {
+            if escaped {
+                escaped = false;
+            } else if c == b'\\' {
+                escaped = true;
+            } else if c == q {
                 in_str = None;
-            } else if c == '\\' {
-                continue;
             }
             continue;
         }
-        if c == '"' || c == '\'' {
-            in_str = Some(c);
-            continue;
-        }
-        if c == '(' {
-            depth += 1;
-            continue;
-        }
-        if c == ')' {
-            depth -= 1;
-            continue;
-        }
-        if depth == 0 && c == ':' {
-            let prop = s[..idx].trim().to_string();
-            let val = s[idx + 1..].trim().to_string();
-            if prop.is_empty() {
-                return None;
+        match c {
+            b'"' | b'\'' => in_str = Some(c),
+            b'(' => depth += 1,
+            b')' => depth -= 1,
+            b':' if depth == 0 => {
+                let prop = s[..idx].trim();
+                if prop.is_empty() {
+                    return None;
+                }
+                return Some((prop, s[idx + 1..].trim()));
             }
-            return Some((prop, val));
+            _ => {}
         }
     }
     None
 }
 
-fn split_selectors(s: &str) -> Vec<String> {
-    s.split(',')
-        .map(|x| x.trim().to_string())
-        .filter(|x| !x.is_empty())
-        .collect()
+/// `font: {` or `margin: 0 {` opens nested properties; `a:hover {`, with
+/// no space after the colon, is a selector. Returns the property and value.
+fn nested_property(head: &str) -> Option<(&str, &str)> {
+    let (prop, rest) = head.split_once(':')?;
+    let is_ident = prop
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '-' || c == '_')
+        && prop.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_ident || !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    Some((prop, rest.trim()))
 }
 
-fn serialize_nodes_as_css_like(nodes: &[Node]) -> String {
-    let mut out = String::new();
-    for n in nodes {
-        match n {
-            Node::VarDecl { .. } => {}
-            Node::Decl { prop, value } => {
-                out.push_str(prop);
-                out.push(':');
-                out.push_str(value);
-                out.push(';');
-            }
-            Node::Rule { selectors, body } => {
-                out.push_str(&selectors.join(","));
-                out.push('{');
-                out.push_str(&serialize_nodes_as_css_like(body));
-                out.push('}');
+/// Splits `@name rest` into `name` and the trimmed `rest`.
+fn split_at_keyword(text: &str) -> (&str, &str) {
+    let body = text.strip_prefix('@').unwrap_or(text);
+    let end = body
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(body.len());
+    (&body[..end], body[end..].trim())
+}
+
+/// Splits `s` around the first whitespace-delimited `word`, trimming both
+/// sides.
+fn split_word<'s>(s: &'s str, word: &str) -> Option<(&'s str, &'s str)> {
+    s.match_indices(word).find_map(|(at, _)| {
+        let (before, after) = (&s[..at], &s[at + word.len()..]);
+        let spaced = before.ends_with(char::is_whitespace) && after.starts_with(char::is_whitespace);
+        spaced.then(|| (before.trim(), after.trim()))
+    })
+}
+
+/// `head` without the `//` and `/* */` comments a selector list may carry
+/// between its selectors.
+fn strip_selector_comments(head: &str) -> Cow<'_, str> {
+    if !head.contains("//") && !head.contains("/*") {
+        return Cow::Borrowed(head);
+    }
+    let b = head.as_bytes();
+    let mut out = String::with_capacity(head.len());
+    let mut i = 0;
+    let mut from = 0;
+    while i < b.len() {
+        match b[i] {
+            b'"' | b'\'' => i = skip_string(b, i),
+            b'/' if b.get(i + 1) == Some(&b'/') => {
+                out.push_str(&head[from..i]);
+                i = head[i..].find('\n').map_or(b.len(), |n| i + n);
+                from = i;
             }
-            Node::RawAt { text } => {
-                out.push_str(text);
-                if !text.trim_end().ends_with(';') && !text.trim_end().ends_with('}') {
-                    out.push(';');
-                }
+            b'/' if b.get(i + 1) == Some(&b'*') => {
+                out.push_str(&head[from..i]);
+                i = head[i + 2..].find("*/").map_or(b.len(), |n| i + n + 4);
+                from = i;
             }
+            _ => i += 1,
         }
     }
-    out
-          }
+    out.push_str(&head[from..]);
+    Cow::Owned(out)
+}
+
+fn split_selectors(s: &str) -> impl Iterator<Item = &str> {
+    s.split(',').map(str::trim).filter(|x| !x.is_empty())
+}
@@ -14,125 +14,1142 @@
 # You should have received a copy of the GNU General Public License
 # along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use anyhow::Result;
 
-use super::parser::{Ast, Node};
-use super::value::Vars;
+use anyhow::{anyhow, bail, Context, Result};
+use rustc_hash::FxHashMap;
+use std::borrow::Cow;
+use std::rc::Rc;
 
-#[derive(Clone, Debug)]
+use super::ast::{Arena, Block, NodeId, Stmt, Text};
+use super::expr;
+use super::intern::{Interner, Sym};
+use super::log::{self, Frame, LogLevel, Message, Pos};
+use super::value::{self, Vars};
+
+/// Separates `@layer` from the tag that tells anonymous layers apart in
+/// `FlatRule::at`; never printed.
+const ANON_LAYER: char = '\0';
+
+/// The selector list of an at-rule body like `@keyframes` or `@font-face`:
+/// one empty selector, so its rules aren't nested under the enclosing ones
+/// and its declarations print inside the at-rule as they are.
+const BARE: Run = Run { start: 1, len: 1 };
+
+/// Mixin and function calls nested deeper than this are an error rather
+/// than a stack overflow.
+const MAX_DEPTH: usize = 100;
+
+/// A run of one of `Render`'s buffers.
+#[derive(Clone, Copy)]
+struct Run {
+    start: u32,
+    len: u32,
+}
+
+impl Run {
+    fn range(self) -> std::ops::Range<usize> {
+        self.start as usize..(self.start + self.len) as usize
+    }
+}
+
+/// A declaration value: the parsed text when evaluation left it alone,
+/// otherwise a run of `Render::values`.
+#[derive(Clone, Copy)]
+enum Value {
+    Text(Text),
+    Resolved(Run),
+}
+
+#[derive(Clone, Copy)]
+enum Entry {
+    Decl(Sym, Value),
+    Comment(Text),
+}
+
+enum Body {
+    /// Selectors (a run of `Render::lists`) and entries (a run of
+    /// `Render::entries`).
+    Rule(Run, Run),
+    /// An at-rule statement or top-level comment, printed as is.
+    Raw(Text),
+}
+
+/// A rule with its nesting resolved. `at` holds symbols of
+/// `Render::names`.
 struct FlatRule {
-    selectors: Vec<String>,
-    decls: Vec<(String, String)>,
+    at: Rc<[Sym]>,
+    body: Body,
+}
+
+/// A statement and where it starts in its file.
+type Site = (NodeId, Pos);
+
+/// A mixin, function or content block being evaluated.
+struct Call {
+    /// How stack traces name it, e.g. `button()`.
+    label: String,
+    /// Where it was called from.
+    site: Site,
+    kind: CallKind,
+}
+
+#[derive(Clone, Copy)]
+enum CallKind {
+    /// The content block passed to the mixin, if any, and the scope of the
+    /// `@include` it runs in.
+    Mixin(Option<(Block, usize)>),
+    Function,
+    /// The content block of the mixin call at this index of
+    /// `Render::calls`.
+    Content(usize),
+}
+
+/// An `@extend`: rules matching `target` also apply to `extenders`, a run
+/// of `Render::lists`.
+struct Extend {
+    target: String,
+    extenders: Run,
+    optional: bool,
+    site: Site,
+}
+
+/// Evaluated arguments of a mixin or function call.
+#[derive(Default)]
+struct Args<'a> {
+    positional: Vec<String>,
+    named: Vec<(&'a str, String)>,
 }
 
-pub fn render_css(ast: Ast) -> Result<String> {
+/// Runs a control directive body in `Render::control`; returns whether to
+/// stop, as after a function's `@return`.
+type RunBody<'r, 'a> = dyn FnMut(&mut Render<'a>, &'a [NodeId], &mut Vars<'a>) -> Result<bool> + 'r;
+
+pub fn render_css(
+    arena: &Arena,
+    roots: &[NodeId],
+    defines: &[(String, String)],
+    messages: &mut Vec<Message>,
+) -> Result<String> {
     let mut vars = Vars::new();
-    let mut flat = Vec::<FlatRule>::new();
-    flatten_nodes(&ast.nodes, &mut vars, &mut flat, &[])?;
+    for (name, value) in defines {
+        vars.set(name, value);
+    }
+    let mut r = Render::new(arena);
+    let mut flat = Vec::new();
+    let top = Run { start: 0, len: 0 };
+    r.flatten(roots, &mut vars, &mut flat, top, &Rc::from([]))?;
+    messages.append(&mut r.messages);
+    flat = r.apply_extends(flat)?;
+    if r.has_layers {
+        flat = r.merge_layers(flat, 0);
+    }
+    Ok(r.print(flat))
+}
 
-    let mut out = String::new();
-    for r in flat {
-        if r.decls.is_empty() {
-            continue;
+struct Render<'a> {
+    arena: &'a Arena,
+    /// Selectors and at-rule headers, which nesting combines into new
+    /// strings.
+    names: Interner,
+    /// Arena symbol index to its symbol in `names`.
+    remap: Vec<Option<Sym>>,
+    /// `(parent, child)` to the combined selector.
+    merged: FxHashMap<(Sym, Sym), Sym>,
+    /// Selector lists, starting with the `:root` one for top-level
+    /// declarations and then `BARE`.
+    lists: Vec<Sym>,
+    entries: Vec<Entry>,
+    /// Declaration values that evaluation changed.
+    values: String,
+    scratch: String,
+    has_layers: bool,
+    extends: Vec<Extend>,
+    /// Mixins and functions by name, defined as evaluation reaches them.
+    mixins: FxHashMap<&'a str, NodeId>,
+    functions: FxHashMap<&'a str, NodeId>,
+    /// Innermost last.
+    calls: Vec<Call>,
+    messages: Vec<Message>,
+}
+
+impl<'a> Render<'a> {
+    fn new(arena: &'a Arena) -> Self {
+        let mut names = Interner::default();
+        let root = names.intern(":root");
+        let bare = names.intern("");
+        Self {
+            arena,
+            names,
+            remap: Vec::new(),
+            merged: FxHashMap::default(),
+            lists: vec![root, bare],
+            entries: Vec::new(),
+            values: String::new(),
+            scratch: String::new(),
+            has_layers: false,
+            extends: Vec::new(),
+            mixins: FxHashMap::default(),
+            functions: FxHashMap::default(),
+            calls: Vec::new(),
+            messages: Vec::new(),
         }
+    }
 
-        if r.decls.len() == 1 && r.decls[0].0 == "@__RAW_AT__" {
-            out.push_str(&r.decls[0].1);
-            if !r.decls[0].1.trim_end().ends_with('\n') {
-                out.push('\n');
+    fn flatten(
+        &mut self,
+        ids: &'a [NodeId],
+        vars: &mut Vars<'a>,
+        out: &mut Vec<FlatRule>,
+        parents: Run,
+        at: &Rc<[Sym]>,
+    ) -> Result<()> {
+        let mut start = self.entries.len();
+        self.exec(ids, vars, out, parents, at, &mut start)?;
+        self.flush(start, out, parents, at);
+        Ok(())
+    }
+
+    /// Runs statements into the rule being built, whose entries begin at
+    /// `start`. Mixin bodies and control directives run here too, so their
+    /// declarations join that rule.
+    fn exec(
+        &mut self,
+        ids: &'a [NodeId],
+        vars: &mut Vars<'a>,
+        out: &mut Vec<FlatRule>,
+        parents: Run,
+        at: &Rc<[Sym]>,
+        start: &mut usize,
+    ) -> Result<()> {
+        let arena = self.arena;
+        for &id in ids {
+            match *arena.stmt(id) {
+                Stmt::VarDecl { name, value, pos } => {
+                    self.assign(arena.str(name), arena.text(value), vars, (id, pos))?;
+                }
+                Stmt::Decl { prop, value, pos } => {
+                    let text = arena.text(value);
+                    let evaluated = if arena.str(prop).starts_with("--") {
+                        self.interpolate(text, vars, (id, pos))?
+                    } else {
+                        self.eval(text, vars, (id, pos))?
+                    };
+                    let value = match evaluated {
+                        Cow::Borrowed(_) => Value::Text(value),
+                        // A null value drops the declaration.
+                        Cow::Owned(s) if s.trim().is_empty() || s == "null" => continue,
+                        Cow::Owned(s) => {
+                            let start = self.values.len();
+                            self.values.push_str(&s);
+                            Value::Resolved(Run {
+                                start: start as u32,
+                                len: s.len() as u32,
+                            })
+                        }
+                    };
+                    self.entries.push(Entry::Decl(prop, value));
+                }
+                Stmt::Comment { text } if parents.len == 0 => {
+                    *start = self.flush(*start, out, parents, at);
+                    out.push(FlatRule {
+                        at: at.clone(),
+                        body: Body::Raw(text),
+                    });
+                }
+                Stmt::Comment { text } => self.entries.push(Entry::Comment(text)),
+                Stmt::RawAt { text } | Stmt::Import { text, .. } => {
+                    // Printed before the declarations around it, which still
+                    // collect into the current rule.
+                    out.push(FlatRule {
+                        at: at.clone(),
+                        body: Body::Raw(text),
+                    });
+                }
+                Stmt::Log { level, message, pos } => self.log(level, arena.text(message), vars, (id, pos))?,
+                Stmt::Rule { selectors, body } => {
+                    self.flush(*start, out, parents, at);
+                    let selectors = arena.selectors(selectors);
+                    let merged = if selectors.iter().any(|&s| arena.str(s).contains("#{")) {
+                        let list = self.interpolate_selectors(selectors, vars, (id, Pos::default()))?;
+                        self.merge_names(parents, &list)
+                    } else {
+                        self.merge_selectors(parents, selectors)
+                    };
+                    self.flatten(arena.body(body), vars, out, merged, at)?;
+                    *start = self.entries.len();
+                }
+                Stmt::AtBlock { name, prelude, body } => {
+                    self.flush(*start, out, parents, at);
+                    let name = arena.str(name);
+                    let prelude = arena.text(prelude);
+                    let header = if name == "layer" && prelude.is_empty() {
+                        // Each anonymous layer is distinct, so tag it to keep it
+                        // from sharing a block with its neighbours. Only one
+                        // that emits rules can be adjacent to another.
+                        format!("@layer{}{}", ANON_LAYER, out.len())
+                    } else {
+                        let prelude = self.substitute(prelude, vars, (id, Pos::default()))?;
+                        format!("@{} {}", name, prelude).trim_end().to_string()
+                    };
+                    self.has_layers |= name == "layer";
+                    let mut inner = at.to_vec();
+                    inner.push(self.names.intern(&header));
+                    // Conditional group rules apply to the rules around them;
+                    // any other at-rule starts afresh.
+                    let parents = match name {
+                        "layer" | "media" | "supports" | "container" => parents,
+                        _ => BARE,
+                    };
+                    self.flatten(arena.body(body), vars, out, parents, &Rc::from(inner))?;
+                    *start = self.entries.len();
+                }
+                Stmt::Mixin { name, .. } => {
+                    self.mixins.insert(arena.str(name), id);
+                }
+                Stmt::Function { name, .. } => {
+                    self.functions.insert(arena.str(name), id);
+                }
+                Stmt::Include { .. } => self.include(id, vars, out, parents, at, start)?,
+                Stmt::Content { pos } => self.content((id, pos), vars, out, parents, at, start)?,
+                Stmt::Extend { selector, pos } => {
+                    let site = (id, pos);
+                    if parents.len == 0 || parents.start == BARE.start {
+                        bail!("@extend at {} may only be used within style rules.", self.locate(site));
+                    }
+                    let target = self.interpolate(arena.text(selector), vars, site)?;
+                    let (target, optional) = match target.trim().strip_suffix("!optional") {
+                        Some(t) => (t.trim_end().to_string(), true),
+                        None => (target.trim().to_string(), false),
+                    };
+                    self.extends.push(Extend {
+                        target,
+                        extenders: parents,
+                        optional,
+                        site,
+                    });
+                }
+                Stmt::Return { pos, .. } => {
+                    bail!("@return at {} is only allowed within a function", self.locate((id, pos)))
+                }
+                Stmt::If { .. } | Stmt::Each { .. } | Stmt::For { .. } | Stmt::While { .. } => {
+                    self.control(id, vars, &mut |r, body, vars| {
+                        r.exec(body, vars, out, parents, at, start)?;
+                        Ok(false)
+                    })?;
+                }
             }
-            out.push('\n');
-            continue;
         }
+        Ok(())
+    }
+
+    fn include(
+        &mut self,
+        id: NodeId,
+        vars: &mut Vars<'a>,
+        out: &mut Vec<FlatRule>,
+        parents: Run,
+        at: &Rc<[Sym]>,
+        start: &mut usize,
+    ) -> Result<()> {
+        let arena = self.arena;
+        let Stmt::Include { name, args, content, pos } = *arena.stmt(id) else {
+            unreachable!()
+        };
+        let (name, site) = (arena.str(name), (id, pos));
+        let context = |r: &Self| format!("@include {} at {}", name, r.locate(site));
+        let Some(&mixin) = self.mixins.get(name) else {
+            return Err(anyhow!("Undefined mixin.").context(context(self)));
+        };
+        let Stmt::Mixin { params, body, .. } = *arena.stmt(mixin) else {
+            unreachable!()
+        };
+        let args = self.eval_args(arena.text(args), vars, site)?;
+        self.enter(Call {
+            label: format!("{}()", name),
+            site,
+            kind: CallKind::Mixin(content.map(|c| (c, vars.current()))),
+        })?;
+        vars.push(0, false);
+        let result = match self.bind(arena.text(params), args, vars, site) {
+            Ok(()) => self.exec(arena.body(body), vars, out, parents, at, start),
+            Err(e) => Err(e.context(context(self))),
+        };
+        vars.pop();
+        self.calls.pop();
+        result
+    }
+
+    /// Runs the content block of the mixin whose body is running, in the
+    /// scope it was passed from.
+    fn content(
+        &mut self,
+        site: Site,
+        vars: &mut Vars<'a>,
+        out: &mut Vec<FlatRule>,
+        parents: Run,
+        at: &Rc<[Sym]>,
+        start: &mut usize,
+    ) -> Result<()> {
+        let Some((of, Some((block, scope)))) = self.content_block() else {
+            return Ok(());
+        };
+        self.enter(Call {
+            label: "@content".to_string(),
+            site,
+            kind: CallKind::Content(of),
+        })?;
+        vars.push(scope, true);
+        let result = self.exec(self.arena.body(block), vars, out, parents, at, start);
+        vars.pop();
+        self.calls.pop();
+        result
+    }
 
-        out.push_str(&r.selectors.join(", "));
-        out.push_str(" {\n");
-        for (k, v) in r.decls {
-            out.push_str("  ");
-            out.push_str(&k);
-            out.push_str(": ");
-            out.push_str(&v);
-            out.push_str(";\n");
+    /// The innermost mixin call whose own body is running, with its content
+    /// block. A content block runs as part of its caller, so calls made from
+    /// one are skipped back to that caller.
+    fn content_block(&self) -> Option<(usize, Option<(Block, usize)>)> {
+        let mut i = self.calls.len();
+        while i > 0 {
+            i -= 1;
+            match self.calls[i].kind {
+                CallKind::Mixin(content) => return Some((i, content)),
+                CallKind::Content(of) => i = of,
+                CallKind::Function => return None,
+            }
         }
-        out.push_str("}\n\n");
+        None
     }
-    Ok(out)
-}
 
-fn flatten_nodes(
-    nodes: &[Node],
-    vars: &mut Vars,
-    out: &mut Vec<FlatRule>,
-    parents: &[String],
-) -> Result<()> {
-    let mut decls = Vec::<(String, String)>::new();
+    fn call(&mut self, function: NodeId, name: &str, args: &'a str, vars: &mut Vars<'a>, site: Site) -> Result<String> {
+        let arena = self.arena;
+        let Stmt::Function { params, body, .. } = *arena.stmt(function) else {
+            unreachable!()
+        };
+        let context = |r: &Self| format!("{}() at {}", name, r.locate(site));
+        let args = self.eval_args(args, vars, site)?;
+        self.enter(Call {
+            label: format!("{}()", name),
+            site,
+            kind: CallKind::Function,
+        })?;
+        vars.push(0, false);
+        let result = match self.bind(arena.text(params), args, vars, site) {
+            Ok(()) => self.run_function(arena.body(body), vars),
+            Err(e) => Err(e.context(context(self))),
+        };
+        vars.pop();
+        self.calls.pop();
+        match result? {
+            Some(v) => Ok(v),
+            None => Err(anyhow!("Function finished without @return.").context(context(self))),
+        }
+    }
 
-    for n in nodes {
-        match n {
-            Node::VarDecl { name, value } => {
-                vars.set(name, value);
+    /// Runs a function body, returning the value of its `@return`.
+    fn run_function(&mut self, ids: &'a [NodeId], vars: &mut Vars<'a>) -> Result<Option<String>> {
+        let arena = self.arena;
+        for &id in ids {
+            match *arena.stmt(id) {
+                Stmt::VarDecl { name, value, pos } => {
+                    self.assign(arena.str(name), arena.text(value), vars, (id, pos))?;
+                }
+                Stmt::Return { value, pos } => {
+                    return Ok(Some(self.eval(arena.text(value), vars, (id, pos))?.into_owned()));
+                }
+                Stmt::Log { level, message, pos } => self.log(level, arena.text(message), vars, (id, pos))?,
+                Stmt::Comment { .. } => {}
+                Stmt::If { .. } | Stmt::Each { .. } | Stmt::For { .. } | Stmt::While { .. } => {
+                    let mut returned = None;
+                    self.control(id, vars, &mut |r, body, vars| {
+                        returned = r.run_function(body, vars)?;
+                        Ok(returned.is_some())
+                    })?;
+                    if returned.is_some() {
+                        return Ok(returned);
+                    }
+                }
+                _ => bail!("functions can only hold variables, control directives, @return and log directives"),
             }
-            Node::Decl { prop, value } => {
-                let v = vars.resolve_value(value);
-                decls.push((prop.to_string(), v));
+        }
+        Ok(None)
+    }
+
+    /// Runs an `@if`, `@each`, `@for` or `@while`, handing each body it
+    /// enters to `run` until that asks to stop; returns whether it did.
+    fn control(&mut self, id: NodeId, vars: &mut Vars<'a>, run: &mut RunBody<'_, 'a>) -> Result<bool> {
+        let arena = self.arena;
+        match *arena.stmt(id) {
+            Stmt::If { cond, body, otherwise, pos } => {
+                let cond = self.eval(arena.text(cond), vars, (id, pos))?;
+                match if expr::truthy(&cond) { Some(body) } else { otherwise } {
+                    Some(branch) => self.scoped(vars, Vec::new(), branch, run),
+                    None => Ok(false),
+                }
             }
-            Node::RawAt { text } => {
-                out.push(FlatRule {
-                    selectors: vec![],
-                    decls: vec![("@__RAW_AT__".to_string(), text.to_string())],
-                });
+            Stmt::Each { vars: names, list, body, pos } => {
+                let names: Vec<&'a str> = expr::split_commas(arena.text(names))
+                    .into_iter()
+                    .map(|n| n.trim().trim_start_matches('$'))
+                    .collect();
+                let list = self.eval(arena.text(list), vars, (id, pos))?;
+                for item in expr::list_items(&list) {
+                    let bound = match names.as_slice() {
+                        [name] => vec![(*name, Cow::Owned(expr::entry_as_list(item).into_owned()))],
+                        _ => {
+                            let parts = expr::destructure(item);
+                            let part = |k: usize| parts.get(k).map_or("null", |p| p).to_string();
+                            names.iter().enumerate().map(|(k, &n)| (n, Cow::Owned(part(k)))).collect()
+                        }
+                    };
+                    if self.scoped(vars, bound, body, run)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
             }
-            Node::Rule { selectors, body } => {
-                if !decls.is_empty() {
-                    let sel = if parents.is_empty() {
-                        vec![":root".to_string()]
-                    } else {
-                        parents.to_vec()
+            Stmt::For {
+                var,
+                from,
+                to,
+                through,
+                body,
+                pos,
+            } => {
+                let site = (id, pos);
+                let from = self.eval(arena.text(from), vars, site)?;
+                let to = self.eval(arena.text(to), vars, site)?;
+                let (Some((a, unit)), Some((b, _))) = (expr::number(&from), expr::number(&to)) else {
+                    bail!("@for at {} needs numbers, not {} and {}", self.locate(site), from, to);
+                };
+                let (a, b, end) = (a as i64, b as i64, through as i64);
+                let steps: Vec<i64> = match a <= b {
+                    true => (a..b + end).collect(),
+                    false => (b + 1 - end..=a).rev().collect(),
+                };
+                for i in steps {
+                    let bound = vec![(arena.str(var), Cow::Owned(format!("{}{}", i, unit)))];
+                    if self.scoped(vars, bound, body, run)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Stmt::While { cond, body, pos } => {
+                while expr::truthy(&self.eval(arena.text(cond), vars, (id, pos))?) {
+                    if self.scoped(vars, Vec::new(), body, run)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Runs `body` in a new control directive scope holding `bound`.
+    fn scoped(
+        &mut self,
+        vars: &mut Vars<'a>,
+        bound: Vec<(&'a str, Cow<'a, str>)>,
+        body: Block,
+        run: &mut RunBody<'_, 'a>,
+    ) -> Result<bool> {
+        vars.push(vars.current(), true);
+        for (k, v) in bound {
+            vars.declare(k, v);
+        }
+        let result = run(self, self.arena.body(body), vars);
+        vars.pop();
+        result
+    }
+
+    fn enter(&mut self, call: Call) -> Result<()> {
+        if self.calls.len() >= MAX_DEPTH {
+            bail!("Stack depth exceeded at {}", self.locate(call.site));
+        }
+        self.calls.push(call);
+        Ok(())
+    }
+
+    /// Evaluates call arguments in the caller's scope. A trailing `...`
+    /// spreads a list into positional arguments.
+    fn eval_args(&mut self, raw: &'a str, vars: &mut Vars<'a>, site: Site) -> Result<Args<'a>> {
+        let mut args = Args::default();
+        for arg in expr::split_commas(raw) {
+            let arg = arg.trim();
+            if arg.is_empty() {
+                continue;
+            }
+            if let Some(list) = arg.strip_suffix("...") {
+                let list = self.eval(list.trim_end(), vars, site)?;
+                args.positional.extend(expr::list_items(&list).into_iter().map(str::to_string));
+            } else if let Some((name, value)) = named_arg(arg) {
+                let value = self.eval(value, vars, site)?.into_owned();
+                args.named.push((name, value));
+            } else {
+                let value = self.eval(arg, vars, site)?.into_owned();
+                args.positional.push(value);
+            }
+        }
+        Ok(args)
+    }
+
+    /// Declares each parameter in `params` in the current scope, from
+    /// `args` or its default.
+    fn bind(&mut self, params: &'a str, args: Args<'a>, vars: &mut Vars<'a>, site: Site) -> Result<()> {
+        let Args { positional, mut named } = args;
+        let passed = positional.len();
+        let mut positional = positional.into_iter();
+        let mut declared = 0;
+        for param in expr::split_commas(params) {
+            let (name, default) = match param.split_once(':') {
+                Some((name, default)) => (name.trim(), Some(default.trim())),
+                None => (param.trim(), None),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            if let Some(rest) = name.strip_suffix("...") {
+                let items: Vec<String> = positional.by_ref().collect();
+                vars.declare(rest.trim_start_matches('$'), Cow::Owned(items.join(", ")));
+                continue;
+            }
+            let name = name.trim_start_matches('$');
+            let value = match positional.next() {
+                Some(v) => Cow::Owned(v),
+                None => match named.iter().position(|(n, _)| *n == name) {
+                    Some(k) => Cow::Owned(named.swap_remove(k).1),
+                    None => match default {
+                        Some(default) => self.eval(default, vars, site)?,
+                        None => bail!("Missing argument ${}.", name),
+                    },
+                },
+            };
+            vars.declare(name, value);
+            declared += 1;
+        }
+        if positional.next().is_some() {
+            bail!("Only {} argument(s) allowed, but {} were passed.", declared, passed);
+        }
+        if let Some((name, _)) = named.first() {
+            bail!("No argument named ${}.", name);
+        }
+        Ok(())
+    }
+
+    /// Applies `$name: value`, with Sass's `!default` and `!global` flags.
+    fn assign(&mut self, name: &'a str, raw: &'a str, vars: &mut Vars<'a>, site: Site) -> Result<()> {
+        let (value, is_default, global) = value::split_flags(raw);
+        if is_default && vars.is_set(name) {
+            return Ok(());
+        }
+        let value = self.eval(value, vars, site)?;
+        vars.store(name, value, global);
+        Ok(())
+    }
+
+    fn log(&mut self, level: LogLevel, raw: &'a str, vars: &mut Vars<'a>, site: Site) -> Result<()> {
+        let value = self.eval(raw.trim(), vars, site)?;
+        let text = match level {
+            // dart-sass prints an `@error` string with its quotes.
+            LogLevel::Error => value.into_owned(),
+            _ => value::unquote(&value).to_string(),
+        };
+        let m = Message {
+            level,
+            text,
+            trace: self.trace(site),
+            dep: self.arena.file_of(site.0).is_some_and(|f| f.dep),
+        };
+        if level == LogLevel::Error {
+            bail!("{}", log::format_error(&m));
+        }
+        self.messages.push(m);
+        Ok(())
+    }
+
+    /// Where evaluation is, innermost first: `site`, each call site out to
+    /// the top level, then the imports that led to that file.
+    fn trace(&self, site: Site) -> Vec<Frame> {
+        let mut trace = Vec::with_capacity(self.calls.len() + 1);
+        let mut here = site;
+        for call in self.calls.iter().rev() {
+            trace.push(self.frame(here, &call.label));
+            here = call.site;
+        }
+        let file = self.arena.file_of(here.0);
+        let imported = file.is_some_and(|f| !f.imports.is_empty());
+        trace.push(self.frame(here, if imported { "@import" } else { "root stylesheet" }));
+        trace.extend(file.into_iter().flat_map(|f| f.imports.iter().cloned()));
+        trace
+    }
+
+    fn frame(&self, (id, pos): Site, label: &str) -> Frame {
+        Frame {
+            file: self.arena.file_of(id).map(|f| f.path.clone()).unwrap_or_default(),
+            pos,
+            label: label.to_string(),
+        }
+    }
+
+    /// `site` for error messages.
+    fn locate(&self, (id, pos): Site) -> String {
+        match self.arena.file_of(id) {
+            Some(f) => format!("{} {}:{}", log::display(&f.path), pos.line, pos.col),
+            None => format!("{}:{}", pos.line, pos.col),
+        }
+    }
+
+    /// Evaluates an expression: substitution, then any operators.
+    fn eval(&mut self, text: &'a str, vars: &mut Vars<'a>, site: Site) -> Result<Cow<'a, str>> {
+        let substituted = self.substitute(text, vars, site)?;
+        if !expr::has_operators(text) {
+            return Ok(substituted);
+        }
+        Ok(match expr::evaluate(&substituted) {
+            Some(v) => Cow::Owned(v),
+            None => substituted,
+        })
+    }
+
+    /// Substitutes variables, `#{}` and calls to user functions. Variables
+    /// aren't looked for inside strings; undefined ones are left as written.
+    fn substitute(&mut self, text: &'a str, vars: &mut Vars<'a>, site: Site) -> Result<Cow<'a, str>> {
+        let calls = !self.functions.is_empty() && text.contains('(');
+        if !calls && !text.contains('$') && !text.contains("#{") {
+            return Ok(Cow::Borrowed(text));
+        }
+        let b = text.as_bytes();
+        let mut out = String::with_capacity(text.len() + 16);
+        let (mut i, mut from) = (0, 0);
+        let mut quote: Option<u8> = None;
+        while i < b.len() {
+            match b[i] {
+                b'\\' => i += 2,
+                b'#' if b.get(i + 1) == Some(&b'{') => {
+                    let end = expr::matching(b, i + 1).with_context(|| format!("unclosed #{{ at {}", self.locate(site)))?;
+                    out.push_str(&text[from..i]);
+                    let inner = self.eval(text[i + 2..end].trim(), vars, site)?;
+                    out.push_str(value::unquote(&inner));
+                    i = end + 1;
+                    from = i;
+                }
+                c @ (b'"' | b'\'') => {
+                    match quote {
+                        None => quote = Some(c),
+                        Some(q) if q == c => quote = None,
+                        Some(_) => {}
+                    }
+                    i += 1;
+                }
+                _ if quote.is_some() => i += 1,
+                b'$' => {
+                    let len = ident_len(&b[i + 1..]);
+                    let name = &text[i + 1..i + 1 + len];
+                    if len > 0 {
+                        let Some(v) = vars.get(name) else {
+                            return Err(anyhow!("Undefined variable.").context(format!("${} at {}", name, self.locate(site))));
+                        };
+                        out.push_str(&text[from..i]);
+                        out.push_str(v);
+                        from = i + 1 + len;
+                    }
+                    i += 1 + len;
+                }
+                c if calls && (c.is_ascii_alphabetic() || c == b'_' || c == b'-') => {
+                    let end = i + ident_len(&b[i..]);
+                    let function = match b.get(end) {
+                        Some(b'(') => self.functions.get(&*text[i..end].replace('_', "-")).copied(),
+                        _ => None,
                     };
-                    out.push(FlatRule {
-                        selectors: sel,
-                        decls: decls.drain(..).collect(),
-                    });
+                    let Some(function) = function else {
+                        i = end;
+                        continue;
+                    };
+                    let close = expr::matching(b, end).with_context(|| format!("unclosed ( at {}", self.locate(site)))?;
+                    out.push_str(&text[from..i]);
+                    let result = self.call(function, &text[i..end], &text[end + 1..close], vars, site)?;
+                    out.push_str(&result);
+                    i = close + 1;
+                    from = i;
                 }
+                _ => i += 1,
+            }
+        }
+        if from == 0 {
+            return Ok(Cow::Borrowed(text));
+        }
+        out.push_str(&text[from.min(text.len())..]);
+        Ok(Cow::Owned(out))
+    }
+
+    /// Evaluates only the `#{}` interpolations in `text`, as Sass does for
+    /// custom property values.
+    fn interpolate(&mut self, text: &'a str, vars: &mut Vars<'a>, site: Site) -> Result<Cow<'a, str>> {
+        if !text.contains("#{") {
+            return Ok(Cow::Borrowed(text));
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(idx) = rest.find("#{") {
+            let Some(end) = expr::matching(rest.as_bytes(), idx + 1) else {
+                break;
+            };
+            out.push_str(&rest[..idx]);
+            let inner = self.eval(rest[idx + 2..end].trim(), vars, site)?;
+            out.push_str(value::unquote(&inner));
+            rest = &rest[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(Cow::Owned(out))
+    }
 
-                let merged = merge_selectors(parents, selectors);
-                flatten_nodes(body, vars, out, &merged)?;
+    /// Emits the entries collected since `start` as a rule, returning where
+    /// the next one starts.
+    fn flush(&self, start: usize, out: &mut Vec<FlatRule>, parents: Run, at: &Rc<[Sym]>) -> usize {
+        let end = self.entries.len();
+        if end > start {
+            // The `:root` list for declarations outside any rule.
+            let selectors = if parents.len == 0 { Run { start: 0, len: 1 } } else { parents };
+            let entries = Run {
+                start: start as u32,
+                len: (end - start) as u32,
+            };
+            out.push(FlatRule {
+                at: at.clone(),
+                body: Body::Rule(selectors, entries),
+            });
+        }
+        end
+    }
+
+    fn merge_selectors(&mut self, parents: Run, children: &[Sym]) -> Run {
+        let start = self.lists.len() as u32;
+        if parents.len == 0 {
+            for &c in children {
+                let c = self.name(c);
+                self.lists.push(c);
             }
+        } else {
+            for p in parents.range() {
+                let p = self.lists[p];
+                for &c in children {
+                    let c = self.name(c);
+                    let merged = self.merge(p, c);
+                    self.lists.push(merged);
+                }
+            }
+        }
+        Run {
+            start,
+            len: self.lists.len() as u32 - start,
         }
     }
 
-    if !decls.is_empty() {
-        let sel = if parents.is_empty() {
-            vec![":root".to_string()]
+    /// Like `merge_selectors`, for children already in `names`.
+    fn merge_names(&mut self, parents: Run, children: &[Sym]) -> Run {
+        let start = self.lists.len() as u32;
+        if parents.len == 0 {
+            self.lists.extend_from_slice(children);
         } else {
-            parents.to_vec()
-        };
-        out.push(FlatRule {
-            selectors: sel,
-            decls,
+            for p in parents.range() {
+                let p = self.lists[p];
+                for &c in children {
+                    let merged = self.merge(p, c);
+                    self.lists.push(merged);
+                }
+            }
+        }
+        Run {
+            start,
+            len: self.lists.len() as u32 - start,
+        }
+    }
+
+    fn merge(&mut self, parent: Sym, child: Sym) -> Sym {
+        if self.names.get(parent).is_empty() {
+            return child;
+        }
+        if let Some(&m) = self.merged.get(&(parent, child)) {
+            return m;
+        }
+        self.scratch.clear();
+        expand_parent(&mut self.scratch, self.names.get(parent), self.names.get(child));
+        let m = self.names.intern(&self.scratch);
+        self.merged.insert((parent, child), m);
+        m
+    }
+
+    /// Evaluates `#{}` in a selector list; the result is split again, since
+    /// an interpolated value may hold several selectors.
+    fn interpolate_selectors(&mut self, selectors: &'a [Sym], vars: &mut Vars<'a>, site: Site) -> Result<Vec<Sym>> {
+        let mut list = Vec::with_capacity(selectors.len());
+        for &s in selectors {
+            let text = self.interpolate(self.arena.str(s), vars, site)?;
+            for part in expr::split_commas(&text) {
+                list.push(self.names.intern(part.trim()));
+            }
+        }
+        Ok(list)
+    }
+
+    /// The `names` symbol for an arena symbol.
+    fn name(&mut self, sym: Sym) -> Sym {
+        let i = sym.index();
+        if i >= self.remap.len() {
+            self.remap.resize(i + 1, None);
+        }
+        match self.remap[i] {
+            Some(s) => s,
+            None => {
+                let s = self.names.intern(self.arena.str(sym));
+                self.remap[i] = Some(s);
+                s
+            }
+        }
+    }
+
+    /// Adds each extender to the selector lists holding its target, then
+    /// drops selectors with placeholders, and rules left with none.
+    fn apply_extends(&mut self, flat: Vec<FlatRule>) -> Result<Vec<FlatRule>> {
+        let placeholders = flat.iter().any(|r| match r.body {
+            Body::Rule(selectors, _) => self.lists[selectors.range()].iter().any(|&s| has_placeholder(self.names.get(s))),
+            Body::Raw(_) => false,
         });
+        if self.extends.is_empty() && !placeholders {
+            return Ok(flat);
+        }
+        let extends = std::mem::take(&mut self.extends);
+        let mut used = vec![false; extends.len()];
+        let mut out = Vec::with_capacity(flat.len());
+        for mut r in flat {
+            let Body::Rule(selectors, entries) = r.body else {
+                out.push(r);
+                continue;
+            };
+            let start = self.lists.len();
+            for k in selectors.range() {
+                let sel = self.lists[k];
+                self.lists.push(sel);
+                for (e, ext) in extends.iter().enumerate() {
+                    let Some(at) = find_simple(self.names.get(sel), &ext.target) else {
+                        continue;
+                    };
+                    used[e] = true;
+                    for x in ext.extenders.range() {
+                        self.scratch.clear();
+                        let text = self.names.get(sel);
+                        self.scratch.push_str(&text[..at]);
+                        self.scratch.push_str(self.names.get(self.lists[x]));
+                        self.scratch.push_str(&text[at + ext.target.len()..]);
+                        let extended = self.names.intern(&self.scratch);
+                        if !self.lists[start..].contains(&extended) {
+                            self.lists.push(extended);
+                        }
+                    }
+                }
+            }
+            let mut kept = start;
+            for k in start..self.lists.len() {
+                if !has_placeholder(self.names.get(self.lists[k])) {
+                    self.lists[kept] = self.lists[k];
+                    kept += 1;
+                }
+            }
+            self.lists.truncate(kept);
+            if kept > start {
+                r.body = Body::Rule(
+                    Run {
+                        start: start as u32,
+                        len: (kept - start) as u32,
+                    },
+                    entries,
+                );
+                out.push(r);
+            }
+        }
+        if let Some(ext) = extends.iter().zip(&used).find_map(|(ext, &u)| (!u && !ext.optional).then_some(ext)) {
+            return Err(anyhow!("The target selector was not found.")
+                .context(format!("@extend {} at {}", ext.target, self.locate(ext.site))));
+        }
+        Ok(out)
     }
 
-    Ok(())
-}
+    fn is_named_layer(&self, header: Sym) -> bool {
+        self.names
+            .get(header)
+            .strip_prefix("@layer ")
+            .is_some_and(|name| !name.trim().is_empty())
+    }
 
-fn merge_selectors(parents: &[String], children: &[String]) -> Vec<String> {
-    if parents.is_empty() {
-        return children.to_vec();
+    /// Pulls every rule of a named `@layer` up to where that layer first
+    /// appears, so each layer prints as one block. Layer order is fixed by
+    /// first appearance and rules keep their order within a layer, so the
+    /// cascade is unchanged. Nested layers are merged the same way within
+    /// their parent.
+    fn merge_layers(&self, flat: Vec<FlatRule>, depth: usize) -> Vec<FlatRule> {
+        let mut groups: Vec<(Option<Sym>, Vec<FlatRule>)> = Vec::new();
+        for r in flat {
+            let layer = r.at.get(depth).copied().filter(|&h| self.is_named_layer(h));
+            let existing = match layer {
+                Some(_) => groups.iter_mut().find(|(name, _)| *name == layer),
+                None => None,
+            };
+            match existing {
+                Some((_, rules)) => rules.push(r),
+                None => groups.push((layer, vec![r])),
+            }
+        }
+        groups
+            .into_iter()
+            .flat_map(|(layer, rules)| match layer {
+                Some(_) => self.merge_layers(rules, depth + 1),
+                None => rules,
+            })
+            .collect()
     }
-    let mut out = Vec::new();
-    for p in parents {
-        for c in children {
-            out.push(expand_parent(p, c));
+
+    fn print(&self, flat: Vec<FlatRule>) -> String {
+        let arena = self.arena;
+        let mut out = String::with_capacity(self.values.len() + flat.len() * 64);
+        let mut open: Vec<Sym> = Vec::new();
+        for r in flat {
+            let common = open.iter().zip(r.at.iter()).take_while(|(a, b)| a == b).count();
+            while open.len() > common {
+                open.pop();
+                push_indent(&mut out, open.len());
+                out.push_str("}\n");
+                if open.is_empty() {
+                    out.push('\n');
+                }
+            }
+            for &w in &r.at[common..] {
+                push_indent(&mut out, open.len());
+                out.push_str(self.names.get(w).split(ANON_LAYER).next().unwrap());
+                out.push_str(" {\n");
+                open.push(w);
+            }
+            let depth = open.len();
+
+            let (selectors, entries) = match r.body {
+                Body::Raw(text) => {
+                    let text = arena.text(text);
+                    push_indent(&mut out, depth);
+                    out.push_str(text);
+                    if !text.trim_end().ends_with('\n') {
+                        out.push('\n');
+                    }
+                    if open.is_empty() {
+                        out.push('\n');
+                    }
+                    continue;
+                }
+                Body::Rule(selectors, entries) => (selectors, entries),
+            };
+
+            let bare = selectors.start == BARE.start;
+            if !bare {
+                push_indent(&mut out, depth);
+                for (k, &sel) in self.lists[selectors.range()].iter().enumerate() {
+                    if k > 0 {
+                        out.push_str(", ");
+                    }
+                    out.push_str(self.names.get(sel));
+                }
+                out.push_str(" {\n");
+            }
+            for entry in &self.entries[entries.range()] {
+                push_indent(&mut out, depth + usize::from(!bare));
+                match *entry {
+                    Entry::Decl(prop, value) => {
+                        out.push_str(arena.str(prop));
+                        out.push_str(": ");
+                        out.push_str(match value {
+                            Value::Text(t) => arena.text(t),
+                            Value::Resolved(run) => &self.values[run.range()],
+                        });
+                        out.push_str(";\n");
+                    }
+                    Entry::Comment(text) => {
+                        out.push_str(arena.text(text));
+                        out.push('\n');
+                    }
+                }
+            }
+            if bare {
+                continue;
+            }
+            push_indent(&mut out, depth);
+            out.push_str("}\n");
+            if open.is_empty() {
+                out.push('\n');
+            }
         }
+        while open.pop().is_some() {
+            push_indent(&mut out, open.len());
+            out.push_str("}\n");
+            if open.is_empty() {
+                out.push('\n');
+            }
+        }
+        out
     }
-    out
 }
 
-fn expand_parent(parent: &str, child: &str) -> String {
+fn push_indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+fn expand_parent(out: &mut String, parent: &str, child: &str) {
     if child.contains('&') {
-        child.replace("&", parent)
+        for (k, part) in child.split('&').enumerate() {
+            if k > 0 {
+                out.push_str(parent);
+            }
+            out.push_str(part);
+        }
     } else {
-        format!("{} {}", parent, child)
+        out.push_str(parent);
+        out.push(' ');
+        out.push_str(child);
     }
-                        }
+}
+
+/// Where `target` appears in `selector` as a whole simple selector.
+fn find_simple(selector: &str, target: &str) -> Option<usize> {
+    let is_ident = |c: u8| c.is_ascii_alphanumeric() || c == b'-' || c == b'_';
+    let b = selector.as_bytes();
+    let bare_name = target.as_bytes().first().is_some_and(|&c| is_ident(c));
+    selector.match_indices(target).map(|(i, _)| i).find(|&i| {
+        let end = i + target.len();
+        let ends = b.get(end).is_none_or(|&c| !is_ident(c));
+        let starts = !bare_name || i == 0 || !(is_ident(b[i - 1]) || matches!(b[i - 1], b'.' | b'#' | b'%'));
+        ends && starts
+    })
+}
+
+/// Whether `selector` has a `%placeholder`; the `%` of a keyframe
+/// selector like `50%` isn't one.
+fn has_placeholder(selector: &str) -> bool {
+    let b = selector.as_bytes();
+    b.iter()
+        .enumerate()
+        .any(|(i, &c)| c == b'%' && b.get(i + 1).is_some_and(|&n| n.is_ascii_alphabetic() || n == b'_' || n == b'-'))
+}
+
+fn ident_len(b: &[u8]) -> usize {
+    b.iter()
+        .position(|&c| !(c.is_ascii_alphanumeric() || c == b'_' || c == b'-'))
+        .unwrap_or(b.len())
+}
+
+/// Splits `$name: value` into the name, without its `$`, and the value.
+fn named_arg(arg: &str) -> Option<(&str, &str)> {
+    let rest = arg.strip_prefix('$')?;
+    let len = ident_len(rest.as_bytes());
+    let value = rest[len..].trim_start().strip_prefix(':')?;
+    Some((&rest[..len], value.trim()))
+}
@@ -0,0 +1,255 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use super::files::Files;
+use super::{scan, CompileOptions};
+
+/// Where an import was found, which decides whether its warnings count as
+/// coming from a dependency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origin {
+    Relative,
+    IncludePath,
+    Package,
+}
+
+/// Resolves an `@import` path written in a file in `base`: through a
+/// matching alias, relative to `base`, then each include path, then as a
+/// package in the nearest `node_modules`. `pkg:` and `~` URLs only look in
+/// packages. Candidates checked and not found are appended to `probed`.
+pub fn resolve(
+    spec: &str,
+    base: &Path,
+    opts: &CompileOptions,
+    files: Files,
+    probed: &mut Vec<PathBuf>,
+) -> Result<Option<(PathBuf, Origin)>> {
+    if let Some(pkg) = spec.strip_prefix("pkg:").or_else(|| spec.strip_prefix('~')) {
+        return Ok(resolve_package(pkg, base, files, probed)?.map(|p| (p, Origin::Package)));
+    }
+    for (alias, target) in &opts.aliases {
+        let Some(rest) = spec.strip_prefix(alias.as_str()) else {
+            continue;
+        };
+        if !(rest.is_empty() || rest.starts_with('/')) {
+            continue;
+        }
+        let full = format!("{}{}", target.display(), rest);
+        if let Some(p) = find_in_dir(Path::new(""), &full, files, probed)? {
+            return Ok(Some((p, Origin::Relative)));
+        }
+    }
+    if let Some(p) = find_in_dir(base, spec, files, probed)? {
+        return Ok(Some((p, Origin::Relative)));
+    }
+    for dir in &opts.include_paths {
+        if let Some(p) = find_in_dir(dir, spec, files, probed)? {
+            return Ok(Some((p, Origin::IncludePath)));
+        }
+    }
+    if spec.starts_with('.') || Path::new(spec).is_absolute() {
+        return Ok(None);
+    }
+    Ok(resolve_package(spec, base, files, probed)?.map(|p| (p, Origin::Package)))
+}
+
+/// Tries the Sass candidates for `spec` under `dir`. A file and its partial
+/// both existing is an error, as in Sass, so the missing one of a pair that
+/// matched is probed too: creating it later makes the import ambiguous.
+fn find_in_dir(dir: &Path, spec: &str, files: Files, probed: &mut Vec<PathBuf>) -> Result<Option<PathBuf>> {
+    let candidates = scan::import_candidates(spec);
+    for pair in candidates.chunks(2) {
+        let (file, partial) = (dir.join(&pair[0]), dir.join(&pair[1]));
+        match (files.is_file(&file), files.is_file(&partial)) {
+            (false, false) => probed.extend([file, partial]),
+            (true, false) => {
+                probed.push(partial);
+                return Ok(Some(file));
+            }
+            (false, true) => {
+                probed.push(file);
+                return Ok(Some(partial));
+            }
+            (true, true) => bail!(
+                "ambiguous import \"{}\": both {} and {} exist",
+                spec,
+                file.display(),
+                partial.display()
+            ),
+        }
+    }
+    Ok(None)
+}
+
+/// Resolves `name[/subpath]` against the first `node_modules/name` found
+/// walking up from `base`, honoring package.json `exports` (with the `sass`
+/// and `style` conditions), then its `sass` and `style` fields.
+fn resolve_package(spec: &str, base: &Path, files: Files, probed: &mut Vec<PathBuf>) -> Result<Option<PathBuf>> {
+    let (name, subpath) = split_package(spec);
+    if name.is_empty() {
+        bail!("invalid package import: {}", spec);
+    }
+    let Some(pkg_dir) = find_package_dir(name, base, files, probed) else {
+        return Ok(None);
+    };
+
+    let manifest_path = pkg_dir.join("package.json");
+    let manifest: Value = match files.read(&manifest_path) {
+        Ok(text) => serde_json::from_str(&text).with_context(|| format!("parse: {}", manifest_path.display()))?,
+        Err(_) => Value::Null,
+    };
+
+    if let Some(exports) = manifest.get("exports") {
+        let keys: Vec<String> = match subpath {
+            "" => vec![".".to_string()],
+            sub => std::iter::once(sub.to_string())
+                .chain(scan::import_candidates(sub))
+                .map(|s| format!("./{}", s))
+                .collect(),
+        };
+        for key in &keys {
+            if let Some(target) = export_target(exports, key) {
+                let p = pkg_dir.join(target.trim_start_matches("./"));
+                if files.is_file(&p) {
+                    return Ok(Some(p));
+                }
+                probed.push(p);
+            }
+        }
+    }
+
+    if subpath.is_empty() {
+        for field in ["sass", "style"] {
+            if let Some(entry) = manifest.get(field).and_then(Value::as_str) {
+                let p = pkg_dir.join(entry);
+                if files.is_file(&p) {
+                    return Ok(Some(p));
+                }
+                probed.push(p);
+            }
+        }
+        return find_in_dir(&pkg_dir, "index", files, probed);
+    }
+    find_in_dir(&pkg_dir, subpath, files, probed)
+}
+
+/// `@scope/name/sub/path` -> (`@scope/name`, `sub/path`).
+fn split_package(spec: &str) -> (&str, &str) {
+    let segments = if spec.starts_with('@') { 2 } else { 1 };
+    let mut end = 0;
+    for _ in 0..segments {
+        match spec[end..].find('/') {
+            Some(i) => end += i + 1,
+            None => return (spec, ""),
+        }
+    }
+    (&spec[..end - 1], &spec[end..])
+}
+
+fn find_package_dir(name: &str, base: &Path, files: Files, probed: &mut Vec<PathBuf>) -> Option<PathBuf> {
+    for dir in base.ancestors() {
+        let candidate = dir.join("node_modules").join(name);
+        if files.is_dir(&candidate) {
+            return Some(candidate);
+        }
+        probed.push(candidate);
+    }
+    None
+}
+
+/// Looks `subpath` (e.g. `.` or `./scss/grid`) up in an `exports` value,
+/// including `*` patterns.
+fn export_target(exports: &Value, subpath: &str) -> Option<String> {
+    let map = match exports {
+        Value::Object(map) if map.keys().any(|k| k.starts_with('.')) => map,
+        // Sugar for `{ ".": ... }`.
+        other => return (subpath == ".").then(|| conditional(other)).flatten(),
+    };
+    if let Some(v) = map.get(subpath) {
+        return conditional(v);
+    }
+    for (key, v) in map {
+        let Some((prefix, suffix)) = key.split_once('*') else {
+            continue;
+        };
+        let star = subpath.strip_prefix(prefix).and_then(|s| s.strip_suffix(suffix));
+        if let Some(star) = star {
+            return conditional(v).map(|t| t.replace('*', star));
+        }
+    }
+    None
+}
+
+/// Picks a target from a conditional export, preferring `sass`, then
+/// `style`, then `default`.
+fn conditional(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => items.iter().find_map(conditional),
+        Value::Object(map) => ["sass", "style", "default"]
+            .iter()
+            .find_map(|c| map.get(*c).and_then(conditional)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn resolve_in(present: &[&str], spec: &str) -> (Option<PathBuf>, Vec<PathBuf>) {
+        let map: BTreeMap<String, &str> = present.iter().map(|p| (p.to_string(), "")).collect();
+        let mut probed = Vec::new();
+        let found = resolve(spec, Path::new("/src"), &CompileOptions::default(), Files::Memory(&map), &mut probed)
+            .unwrap()
+            .map(|(p, _)| p);
+        (found, probed)
+    }
+
+    #[test]
+    fn partial_match_probes_the_plain_file() {
+        let (found, probed) = resolve_in(&["/src/_foo.scss"], "foo");
+        assert_eq!(found, Some(PathBuf::from("/src/_foo.scss")));
+        assert_eq!(probed, [PathBuf::from("/src/foo.scss")]);
+    }
+
+    #[test]
+    fn plain_match_probes_the_partial() {
+        let (found, probed) = resolve_in(&["/src/foo.css"], "foo.css");
+        assert_eq!(found, Some(PathBuf::from("/src/foo.css")));
+        assert_eq!(probed, [PathBuf::from("/src/_foo.css")]);
+    }
+
+    #[test]
+    fn index_match_probes_every_candidate_before_it() {
+        let (found, probed) = resolve_in(&["/src/foo/index.scss"], "foo");
+        assert_eq!(found, Some(PathBuf::from("/src/foo/index.scss")));
+        let expected = ["/src/foo.scss", "/src/_foo.scss", "/src/foo/_index.scss"];
+        assert_eq!(probed, expected.map(PathBuf::from));
+    }
+
+    #[test]
+    fn file_and_partial_is_ambiguous() {
+        let map: BTreeMap<String, &str> = [("/src/foo.scss".to_string(), ""), ("/src/_foo.scss".to_string(), "")].into();
+        let err = resolve("foo", Path::new("/src"), &CompileOptions::default(), Files::Memory(&map), &mut Vec::new());
+        assert!(err.unwrap_err().to_string().contains("ambiguous"));
+    }
+}
@@ -14,30 +14,77 @@
 # You should have received a copy of the GNU General Public License
 # along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-pub fn import_candidates(path_str: &str) -> Vec<String> {
-    let mut out = Vec::new();
+use anyhow::{bail, Result};
+use std::borrow::Cow;
 
-    let raw = path_str.replace("\\", "/");
-    let has_ext = raw.ends_with(".scss") || raw.ends_with(".sass") || raw.ends_with(".css");
+/// One entry of an `@import` list.
+#[derive(Debug, PartialEq)]
+pub enum ImportTarget {
+    /// A stylesheet to load and splice in place.
+    Sass(String),
+    /// Left to the browser as a plain CSS `@import`. `path` is set for local
+    /// `"x.css"` imports without media queries, which can be inlined instead.
+    Css { text: String, path: Option<String> },
+}
 
-    if has_ext {
-        out.push(raw.clone());
-        out.push(partialize(&raw));
-        return out;
+/// Splits the arguments of `@import` and applies Sass's rules for which
+/// entries stay plain CSS: `url()`, remote URLs, a `.css` extension or any
+/// media query.
+pub fn parse_imports(args: &str) -> Result<Vec<ImportTarget>> {
+    let mut out = Vec::new();
+    // Nearly every `@import` names one file.
+    let parts = match args.trim() {
+        _ if args.contains(',') => crate::css::split_top_level(args, ','),
+        "" => Vec::new(),
+        one => vec![one.to_string()],
+    };
+    for part in &parts {
+        let part = part.trim();
+        if part.starts_with("url(") {
+            out.push(ImportTarget::Css { text: part.to_string(), path: None });
+            continue;
+        }
+        let Some(q) = part.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            bail!("unsupported @import: {}", part);
+        };
+        let Some(end) = part[1..].find(q) else {
+            bail!("unterminated @import string: {}", part);
+        };
+        let path = &part[1..1 + end];
+        let media = part[2 + end..].trim();
+        let remote = ["http://", "https://", "//"].iter().any(|p| path.starts_with(p));
+        if media.is_empty() && !remote && !path.ends_with(".css") {
+            out.push(ImportTarget::Sass(path.to_string()));
+        } else {
+            out.push(ImportTarget::Css {
+                text: part.to_string(),
+                path: (media.is_empty() && !remote).then(|| path.to_string()),
+            });
+        }
     }
-
-    out.push(format!("{}.scss", raw));
-    out.push(partialize(&format!("{}.scss", raw)));
-    out.push(format!("{}/index.scss", raw));
-    out.push(partialize(&format!("{}/index.scss", raw)));
-
-    out
+    if out.is_empty() {
+        bail!("empty @import");
+    }
+    Ok(out)
 }
 
-fn partialize(p: &str) -> String {
-    if let Some(idx) = p.rfind('/') {
-        let (dir, file) = p.split_at(idx + 1);
-        return format!("{}_{file}", dir);
+/// The files `@import "path_str"` may mean, in pairs of a file and its
+/// partial.
+pub fn import_candidates(path_str: &str) -> Vec<String> {
+    let raw = match path_str.contains('\\') {
+        true => Cow::Owned(path_str.replace('\\', "/")),
+        false => Cow::Borrowed(path_str),
+    };
+    let has_ext = raw.ends_with(".scss") || raw.ends_with(".sass") || raw.ends_with(".css");
+
+    let (dir, file) = raw.split_at(raw.rfind('/').map_or(0, |i| i + 1));
+    if has_ext {
+        return vec![raw.to_string(), [dir, "_", file].concat()];
     }
-    format!("_{}", p)
+    vec![
+        [&raw, ".scss"].concat(),
+        [dir, "_", file, ".scss"].concat(),
+        [&raw, "/index.scss"].concat(),
+        [&raw, "/_index.scss"].concat(),
+    ]
 }
@@ -14,64 +14,169 @@
 # You should have received a copy of the GNU General Public License
 # along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use rustc_hash::FxHashMap;
 
+/// Variables by name, in nested scopes. Names and values borrow from the
+/// sources where they can; only values that needed resolving are owned.
 #[derive(Clone, Debug)]
-pub struct Vars {
-    map: HashMap<String, String>,
+pub struct Vars<'a> {
+    /// The global scope first, the current one last.
+    scopes: Vec<Scope<'a>>,
 }
 
-impl Vars {
+#[derive(Clone, Debug, Default)]
+struct Scope<'a> {
+    map: FxHashMap<&'a str, Cow<'a, str>>,
+    /// Where lookups continue; `None` for the global scope.
+    parent: Option<usize>,
+    /// Control directive bodies assign to the variables around them that
+    /// already exist; mixin and function bodies shadow them instead.
+    flow: bool,
+}
+
+impl<'a> Vars<'a> {
     pub fn new() -> Self {
-        Self { map: HashMap::new() }
+        Self {
+            scopes: vec![Scope::default()],
+        }
+    }
+
+    pub fn set(&mut self, k: &'a str, v: &'a str) {
+        self.scopes[0].map.insert(k, Cow::Borrowed(v));
+    }
+
+    /// Applies `$name: value` with Sass's `!default` and `!global` flags,
+    /// substituting variables but evaluating nothing else.
+    pub fn assign(&mut self, name: &'a str, raw: &'a str) {
+        let (value, is_default, global) = split_flags(raw);
+        if is_default && self.is_set(name) {
+            return;
+        }
+        let resolved = self.resolve_value(value);
+        self.store(name, resolved, global);
+    }
+
+    pub fn get(&self, k: &str) -> Option<&str> {
+        let mut scope = Some(self.current());
+        while let Some(i) = scope {
+            if let Some(v) = self.scopes[i].map.get(k) {
+                return Some(v);
+            }
+            scope = self.scopes[i].parent;
+        }
+        None
+    }
+
+    /// Whether `!default` should leave `k` alone.
+    pub fn is_set(&self, k: &str) -> bool {
+        self.get(k).is_some_and(|v| v != "null")
+    }
+
+    /// Assigns `k` in the nearest scope that has it, looking out through
+    /// control directive bodies, or else declares it in the current one.
+    pub fn store(&mut self, k: &'a str, v: Cow<'a, str>, global: bool) {
+        let mut i = if global { 0 } else { self.current() };
+        loop {
+            let scope = &mut self.scopes[i];
+            if let Some(slot) = scope.map.get_mut(k) {
+                *slot = v;
+                return;
+            }
+            match scope.parent {
+                Some(p) if scope.flow => i = p,
+                _ => break,
+            }
+        }
+        let i = if global { 0 } else { self.current() };
+        self.scopes[i].map.insert(k, v);
+    }
+
+    /// Declares `k` in the current scope, shadowing any outer one.
+    pub fn declare(&mut self, k: &'a str, v: Cow<'a, str>) {
+        let i = self.current();
+        self.scopes[i].map.insert(k, v);
+    }
+
+    pub fn current(&self) -> usize {
+        self.scopes.len() - 1
     }
 
-    pub fn set(&mut self, k: &str, v: &str) {
-        self.map.insert(k.to_string(), v.to_string());
+    /// Opens a scope whose lookups continue in `parent`.
+    pub fn push(&mut self, parent: usize, flow: bool) {
+        self.scopes.push(Scope {
+            map: FxHashMap::default(),
+            parent: Some(parent),
+            flow,
+        });
     }
 
-    pub fn get(&self, k: &str) -> Option<&String> {
-        self.map.get(k)
+    pub fn pop(&mut self) {
+        debug_assert!(self.scopes.len() > 1);
+        self.scopes.pop();
     }
 
-    pub fn resolve_value(&self, s: &str) -> String {
+    /// Substitutes `$variables`; undefined ones are left as written.
+    pub fn resolve_value<'s>(&self, s: &'s str) -> Cow<'s, str> {
         let mut out = String::new();
-        let mut i = 0usize;
-        let bytes = s.as_bytes();
-        while i < bytes.len() {
-            if bytes[i] == b'$' {
-                let (name, next) = read_ident(bytes, i + 1);
-                if !name.is_empty() {
-                    if let Some(v) = self.get(&name) {
-                        out.push_str(v);
-                    } else {
-                        out.push('$');
-                        out.push_str(&name);
-                    }
-                    i = next;
-                    continue;
+        match self.resolve_into(s, &mut out) {
+            true => Cow::Owned(out),
+            false => Cow::Borrowed(s),
+        }
+    }
+
+    /// Like `resolve_value`, but appends the result to `out`, and only if
+    /// `s` has variables to substitute; returns whether it did.
+    pub fn resolve_into(&self, s: &str, out: &mut String) -> bool {
+        if !s.contains('$') {
+            return false;
+        }
+        let mut rest = s;
+        while let Some(idx) = rest.find('$') {
+            out.push_str(&rest[..idx]);
+            let after = &rest[idx + 1..];
+            let len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(after.len());
+            let name = &after[..len];
+            match self.get(name) {
+                Some(v) if !name.is_empty() => out.push_str(v),
+                _ => {
+                    out.push('$');
+                    out.push_str(name);
                 }
             }
-            out.push(bytes[i] as char);
-            i += 1;
+            rest = &after[len..];
         }
-        out
+        out.push_str(rest);
+        true
     }
 }
 
-fn read_ident(bytes: &[u8], mut i: usize) -> (String, usize) {
-    let start = i;
-    while i < bytes.len() {
-        let b = bytes[i];
-        let ok = (b'A'..=b'Z').contains(&b)
-            || (b'a'..=b'z').contains(&b)
-            || (b'0'..=b'9').contains(&b)
-            || b == b'_'
-            || b == b'-';
-        if !ok {
+/// Splits the `!default` and `!global` flags off a variable's value.
+pub fn split_flags(raw: &str) -> (&str, bool, bool) {
+    let mut value = raw.trim();
+    let (mut is_default, mut global) = (false, false);
+    loop {
+        if let Some(v) = value.strip_suffix("!default") {
+            is_default = true;
+            value = v.trim_end();
+        } else if let Some(v) = value.strip_suffix("!global") {
+            global = true;
+            value = v.trim_end();
+        } else {
             break;
         }
-        i += 1;
     }
-    (String::from_utf8_lossy(&bytes[start..i]).to_string(), i)
+    (value, is_default, global)
+}
+
+pub fn unquote(s: &str) -> &str {
+    let quoted = s.len() >= 2
+        && ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')));
+    if quoted {
+        &s[1..s.len() - 1]
+    } else {
+        s
+    }
 }
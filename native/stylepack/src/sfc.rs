@@ -0,0 +1,683 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use walkdir::WalkDir;
+
+use crate::css::{self, Item};
+use crate::scss;
+
+#[derive(clap::Args, Debug)]
+pub struct SfcArgs {
+    /// `.vue`/`.svelte` files, or directories searched for them.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Aggregated stylesheet; scope tokens per component go to `<output>.json`.
+    #[arg(short, long)]
+    output: PathBuf,
+
+    #[arg(long)]
+    include: Vec<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = ScopeStyle::Attribute)]
+    scope: ScopeStyle,
+
+    /// Write `<output>.map` with one source per component.
+    #[arg(long)]
+    source_map: bool,
+}
+
+/// How scoped rules are tied to their component's markup.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ScopeStyle {
+    /// `[data-v-<id>]`, with the id the Vue compiler gives the template.
+    Attribute,
+    /// `.s-<hash>`, as Svelte does.
+    Class,
+}
+
+const SKIP_DIRS: &[&str] = &["node_modules", ".git", "dist", "target", ".jen"];
+
+/// A `<style>` block and where its content starts (0-based line).
+struct Block {
+    scoped: bool,
+    content: String,
+    line: usize,
+}
+
+pub fn run(args: &SfcArgs) -> Result<()> {
+    let files = collect(&args.paths)?;
+    let cwd = std::env::current_dir()?;
+    let opts = scss::CompileOptions {
+        include_paths: args.include.clone(),
+        ..Default::default()
+    };
+
+    let mut out = String::new();
+    let mut lines: Vec<Option<(usize, usize)>> = Vec::new();
+    let mut sources: Vec<(PathBuf, String)> = Vec::new();
+    let mut scopes: BTreeMap<String, String> = BTreeMap::new();
+
+    for file in &files {
+        let text = std::fs::read_to_string(file).with_context(|| format!("read: {}", file.display()))?;
+        let blocks = extract(file, &text)?;
+        if blocks.is_empty() {
+            continue;
+        }
+        let rel = file.strip_prefix(&cwd).unwrap_or(file);
+        let scope = Scope::new(&cwd.join(file), rel, args.scope);
+        let source = sources.len();
+        sources.push((file.clone(), text.clone()));
+
+        for block in blocks {
+            let compiled = scss::compile_source(file, &block.content, &opts, None)
+                .with_context(|| format!("compile failed: {}", file.display()))?;
+            scss::print_messages(&compiled.messages, &scss::LogOptions::default());
+            let mut sheet = css::parse_stylesheet(&compiled.css)?;
+            if block.scoped {
+                scope.apply(&mut sheet.items);
+                scopes.insert(rel.display().to_string(), scope.token.clone());
+            }
+
+            let body = css::to_css(&sheet);
+            let body = body.trim_end();
+            lines.push(Some((source, block.line)));
+            for line in line_map(body, &block.content, block.scoped.then_some(&scope)) {
+                lines.push(Some((source, block.line + line)));
+            }
+            out.push_str(&format!("/* {} */\n{}", rel.display(), body));
+            out.push_str("\n\n");
+            lines.push(None);
+        }
+    }
+
+    if let Some(parent) = args.output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("mkdir: {}", parent.display()))?;
+    }
+    if args.source_map {
+        let map_path = PathBuf::from(format!("{}.map", args.output.display()));
+        let map = source_map(&args.output, &map_path, &sources, &lines);
+        std::fs::write(&map_path, map).with_context(|| format!("write: {}", map_path.display()))?;
+        let name = map_path.file_name().unwrap().to_string_lossy();
+        out.push_str(&format!("/*# sourceMappingURL={} */\n", name));
+    }
+    std::fs::write(&args.output, &out).with_context(|| format!("write: {}", args.output.display()))?;
+
+    let json_path = PathBuf::from(format!("{}.json", args.output.display()));
+    let json = serde_json::to_string_pretty(&scopes)?;
+    std::fs::write(&json_path, json + "\n").with_context(|| format!("write: {}", json_path.display()))?;
+
+    eprintln!(
+        "stylepack sfc: {} component(s) with styles, {} scoped",
+        sources.len(),
+        scopes.len()
+    );
+    Ok(())
+}
+
+fn collect(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    for root in paths {
+        if !root.exists() {
+            bail!("path does not exist: {}", root.display());
+        }
+        if root.is_file() {
+            out.push(root.clone());
+            continue;
+        }
+        let walker = WalkDir::new(root).sort_by_file_name().into_iter().filter_entry(|e| {
+            e.depth() == 0 || !SKIP_DIRS.contains(&e.file_name().to_string_lossy().as_ref())
+        });
+        for entry in walker {
+            let entry = entry?;
+            let is_sfc = entry.path().extension().is_some_and(|e| e == "vue" || e == "svelte");
+            if entry.file_type().is_file() && is_sfc {
+                out.push(entry.into_path());
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Finds the top-level `<style>` blocks of a component, skipping HTML
+/// comments, the contents of `<script>` and other raw text elements, and
+/// styles nested in markup. Vue blocks are scoped when marked `scoped`;
+/// Svelte blocks always are, unless marked `global`.
+fn extract(file: &Path, text: &str) -> Result<Vec<Block>> {
+    static LANG: OnceLock<Regex> = OnceLock::new();
+    let lang_re = LANG.get_or_init(|| Regex::new(r#"\blang\s*=\s*["']?([\w-]+)"#).unwrap());
+    let svelte = file.extension().is_some_and(|e| e == "svelte");
+
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while let Some(k) = text[i..].find('<') {
+        let at = i + k;
+        if text[at..].starts_with("<!--") {
+            i = text[at..].find("-->").map_or(text.len(), |e| at + e + 3);
+            continue;
+        }
+        let closing = text[at + 1..].starts_with('/');
+        let name_start = at + 1 + usize::from(closing);
+        let name_len = text[name_start..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':'))
+            .unwrap_or(text.len() - name_start);
+        if name_len == 0 {
+            // A `<` in text.
+            i = at + 1;
+            continue;
+        }
+        let name = text[name_start..name_start + name_len].to_ascii_lowercase();
+        let end = tag_end(text, name_start + name_len);
+        if closing {
+            depth = depth.saturating_sub(1);
+            i = end;
+            continue;
+        }
+        let attrs = text[name_start + name_len..end].trim_end_matches('>');
+        let self_closing = attrs.ends_with('/');
+        if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+            i = end;
+            continue;
+        }
+        if !RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            depth += 1;
+            i = end;
+            continue;
+        }
+        let close = find_close_tag(text, end, &name);
+        if name == "style" && depth == 0 {
+            let lang = lang_re.captures(attrs).map_or("css".to_string(), |c| c[1].to_string());
+            if !matches!(lang.as_str(), "css" | "scss") {
+                bail!("{}: unsupported <style lang=\"{}\">", file.display(), lang);
+            }
+            let has = |word: &str| attrs.split_whitespace().any(|a| a == word);
+            out.push(Block {
+                scoped: if svelte { !has("global") } else { has("scoped") },
+                content: text[end..close].to_string(),
+                line: text[..end].matches('\n').count(),
+            });
+        }
+        i = tag_end(text, close);
+    }
+    Ok(out)
+}
+
+/// Elements whose content is text, not markup, up to their closing tag.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// The index just past the `>` closing the tag whose attributes start at
+/// `from`, skipping quoted values and Svelte `{expressions}`.
+fn tag_end(text: &str, from: usize) -> usize {
+    let b = text.as_bytes();
+    let (mut i, mut braces) = (from, 0usize);
+    let mut quote: Option<u8> = None;
+    while let Some(&c) = b.get(i) {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(c),
+            (None, b'{') => braces += 1,
+            (None, b'}') => braces = braces.saturating_sub(1),
+            (None, b'>') if braces == 0 => return i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    text.len()
+}
+
+/// Where `</name` starts at or after `from`, in any case.
+fn find_close_tag(text: &str, from: usize, name: &str) -> usize {
+    let mut i = from;
+    while let Some(k) = text[i..].find("</") {
+        let at = i + k;
+        let rest = &text[at + 2..];
+        if rest.len() >= name.len()
+            && rest.as_bytes()[..name.len()].eq_ignore_ascii_case(name.as_bytes())
+            && !rest[name.len()..].starts_with(|c: char| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return at;
+        }
+        i = at + 2;
+    }
+    text.len()
+}
+
+/// How a scoped block is tied to its component: the selector fragment
+/// added to its rules, and the id its `@keyframes` names are tagged with.
+struct Scope {
+    token: String,
+    id: String,
+    style: ScopeStyle,
+}
+
+impl Scope {
+    /// For Vue, the id is the one `src/compilers/vue.ts` gives the
+    /// component's template: its absolute path with every character but
+    /// ASCII letters and digits replaced by `_` (one per UTF-16 unit, as
+    /// the JS regex sees them). For Svelte, a hash of the path relative to
+    /// the working directory, so it is stable across machines.
+    fn new(abs: &Path, rel: &Path, style: ScopeStyle) -> Scope {
+        match style {
+            ScopeStyle::Attribute => {
+                let abs = std::fs::canonicalize(abs).unwrap_or_else(|_| abs.to_path_buf());
+                let mut id = String::new();
+                for c in abs.to_string_lossy().chars() {
+                    if c.is_ascii_alphanumeric() {
+                        id.push(c);
+                    } else {
+                        id.extend(std::iter::repeat_n('_', c.len_utf16()));
+                    }
+                }
+                Scope {
+                    token: format!("[data-v-{}]", id),
+                    id,
+                    style,
+                }
+            }
+            ScopeStyle::Class => {
+                let hash = format!("{:x}", Sha256::digest(rel.to_string_lossy().replace('\\', "/").as_bytes()));
+                let id = format!("s-{}", &hash[..8]);
+                Scope {
+                    token: format!(".{}", id),
+                    id,
+                    style,
+                }
+            }
+        }
+    }
+
+    fn apply(&self, items: &mut [Item]) {
+        let mut names = Vec::new();
+        collect_keyframes(items, &mut names);
+        scope_items(items, self, &names);
+    }
+
+    /// `name` as Vue (`name-id`) or Svelte (`id-name`) renames it.
+    fn keyframes(&self, name: &str) -> String {
+        match self.style {
+            ScopeStyle::Attribute => format!("{}-{}", name, self.id),
+            ScopeStyle::Class => format!("{}-{}", self.id, name),
+        }
+    }
+}
+
+fn is_keyframes(at: &css::AtRule) -> bool {
+    at.name == "keyframes" || (at.name.starts_with('-') && at.name.ends_with("-keyframes"))
+}
+
+fn collect_keyframes(items: &[Item], names: &mut Vec<String>) {
+    for item in items {
+        if let Item::At(at) = item {
+            if is_keyframes(at) {
+                names.push(at.prelude.trim().to_string());
+            } else if let Some(body) = &at.body {
+                collect_keyframes(body, names);
+            }
+        }
+    }
+}
+
+/// Scopes selectors, and renames the block's own `@keyframes` along with
+/// the animations that use them.
+fn scope_items(items: &mut [Item], scope: &Scope, keyframes: &[String]) {
+    for item in items {
+        match item {
+            Item::Rule(rule) => {
+                for sel in &mut rule.selectors {
+                    *sel = scope_selector(sel, &scope.token);
+                }
+                for item in &mut rule.body {
+                    if let Item::Decl(d) = item {
+                        // Unprefixed, as in `-webkit-animation`.
+                        let prop = match d.prop.strip_prefix('-') {
+                            Some(p) => p.split_once('-').map_or("", |(_, p)| p),
+                            None => &d.prop,
+                        };
+                        if matches!(prop, "animation" | "animation-name") {
+                            d.value = rename_words(&d.value, |w| keyframes.iter().any(|k| k == w).then(|| scope.keyframes(w)));
+                        }
+                    }
+                }
+            }
+            Item::At(at) if is_keyframes(at) => {
+                at.prelude = scope.keyframes(at.prelude.trim());
+            }
+            Item::At(at) => {
+                if let Some(body) = &mut at.body {
+                    scope_items(body, scope, keyframes);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Replaces the identifiers in `value` that `rename` maps to something.
+fn rename_words(value: &str, rename: impl Fn(&str) -> Option<String>) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find(is_word) {
+        out.push_str(&rest[..start]);
+        let len = rest[start..].find(|c: char| !is_word(c)).unwrap_or(rest.len() - start);
+        let word = &rest[start..start + len];
+        match rename(word) {
+            Some(new) => out.push_str(&new),
+            None => out.push_str(word),
+        }
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Adds `token` to the last compound selector, before its pseudo-classes.
+/// `:global(x)` leaves `x` unscoped, so the token goes on the last compound
+/// outside it, if any; `:deep(x)` scopes what precedes it and leaves `x` to
+/// match inside child components.
+fn scope_selector(sel: &str, token: &str) -> String {
+    if let Some(idx) = sel.find(":deep(") {
+        let before = sel[..idx].trim_end();
+        let inner = unwrap_pseudo(&sel[idx..], ":deep(");
+        if before.is_empty() {
+            return format!("{} {}", token, inner.trim());
+        }
+        return format!("{} {}", scope_selector(before, token), inner.trim());
+    }
+
+    let scoped = compound_ranges(sel)
+        .into_iter()
+        .rev()
+        .find(|&(start, end)| !sel[start..end].starts_with(":global("));
+    let sel = match scoped {
+        Some((start, end)) => {
+            let insert = start + pseudo_start(&sel[start..end]).unwrap_or(end - start);
+            format!("{}{}{}", &sel[..insert], token, &sel[insert..])
+        }
+        None => sel.to_string(),
+    };
+    unwrap_pseudo(&sel, ":global(")
+}
+
+/// Replaces each `prefix…)` with its argument.
+fn unwrap_pseudo(sel: &str, prefix: &str) -> String {
+    let mut out = String::new();
+    let mut rest = sel;
+    while let Some(idx) = rest.find(prefix) {
+        out.push_str(&rest[..idx]);
+        let after = &rest[idx + prefix.len()..];
+        let mut depth = 1;
+        let end = after
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .map_or(after.len(), |(i, _)| i);
+        out.push_str(&after[..end]);
+        rest = after.get(end + 1..).unwrap_or("");
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Byte ranges of the compound selectors in `sel`, between combinators.
+fn compound_ranges(sel: &str) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in sel.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ' ' | '>' | '+' | '~' if depth == 0 => {
+                if i > start {
+                    out.push((start, i));
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if sel.len() > start {
+        out.push((start, sel.len()));
+    }
+    out
+}
+
+fn pseudo_start(compound: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, c) in compound.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ':' if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// For each line of `css`, compiled from the `<style>` block `source`, the
+/// line of `source` it came from. The compiler keeps no positions, so a rule
+/// is found by its selector and a declaration by its property, searching
+/// forward from the rule it is in. A line that can't be placed, like a
+/// closing brace, takes the line of its rule.
+fn line_map(css: &str, source: &str, scope: Option<&Scope>) -> Vec<usize> {
+    let mut out = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    let mut cursor = 0;
+    for line in css.lines() {
+        let line = line.trim();
+        let rule = open.last().copied().unwrap_or(0);
+        let at = if let Some(head) = line.strip_suffix('{') {
+            let head = match scope {
+                Some(s) => head.replace(&s.token, ""),
+                None => head.to_string(),
+            };
+            let at = find_rule(source, head.trim(), cursor).unwrap_or(rule);
+            cursor = at;
+            open.push(at);
+            at
+        } else if line == "}" {
+            open.pop().unwrap_or(rule)
+        } else if let Some((prop, _)) = line.split_once(':').filter(|_| line.ends_with(';')) {
+            find_word(source, &format!("{}:", prop.trim()), rule).unwrap_or(rule)
+        } else {
+            rule
+        };
+        out.push(source[..at].matches('\n').count());
+    }
+    out
+}
+
+/// Where a rule with `head` (a selector list or an at-rule prelude) is
+/// written: the whole first selector, or else its last compound, or the
+/// pseudo-classes a nested `&:hover` would leave.
+fn find_rule(source: &str, head: &str, from: usize) -> Option<usize> {
+    let first = head.split(',').next().unwrap_or(head).trim();
+    let last = first.rsplit([' ', '>', '+', '~']).next().unwrap_or(first);
+    let pseudo = pseudo_start(last).map(|k| &last[k..]);
+    let at_name = first.starts_with('@').then(|| first.split_whitespace().next().unwrap_or(first));
+    [Some(first), Some(last), pseudo, at_name]
+        .into_iter()
+        .flatten()
+        .filter(|c| !c.is_empty())
+        .find_map(|c| find_word(source, c, from))
+}
+
+/// Where `needle` appears in `source` not as part of a longer name,
+/// searching from `from` and then from the start. A `:` at the end of
+/// `needle` may have whitespace before it.
+fn find_word(source: &str, needle: &str, from: usize) -> Option<usize> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    let (word, colon) = match needle.strip_suffix(':') {
+        Some(w) => (w, true),
+        None => (needle, false),
+    };
+    let matches = |at: usize| {
+        let before = source[..at].chars().next_back();
+        let after = &source[at + word.len()..];
+        let starts = !word.starts_with(is_name) || !before.is_some_and(is_name);
+        let ends = match colon {
+            true => after.trim_start().starts_with(':'),
+            false => !word.ends_with(is_name) || !after.starts_with(is_name),
+        };
+        starts && ends
+    };
+    let search = |start: usize| source[start..].match_indices(word).map(|(k, _)| start + k).find(|&at| matches(at));
+    search(from.min(source.len())).or_else(|| search(0))
+}
+
+/// A v3 source map with each component as a source, mapping each generated
+/// line to the line `line_map` places it on.
+fn source_map(output: &Path, map_path: &Path, sources: &[(PathBuf, String)], lines: &[Option<(usize, usize)>]) -> String {
+    let map_dir = map_path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let map_dir = std::fs::canonicalize(map_dir).unwrap_or_else(|_| map_dir.to_path_buf());
+
+    let mut mappings = String::new();
+    let (mut prev_source, mut prev_line) = (0i64, 0i64);
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            mappings.push(';');
+        }
+        if let Some((source, orig)) = line {
+            vlq(&mut mappings, 0);
+            vlq(&mut mappings, *source as i64 - prev_source);
+            vlq(&mut mappings, *orig as i64 - prev_line);
+            vlq(&mut mappings, 0);
+            (prev_source, prev_line) = (*source as i64, *orig as i64);
+        }
+    }
+
+    let names: Vec<String> = sources
+        .iter()
+        .map(|(p, _)| {
+            let abs = std::fs::canonicalize(p).unwrap_or_else(|_| p.clone());
+            crate::assets::url_path(&crate::assets::relative_path(&map_dir, &abs))
+        })
+        .collect();
+    let map = serde_json::json!({
+        "version": 3,
+        "file": output.file_name().map(|n| n.to_string_lossy().to_string()),
+        "sources": names,
+        "sourcesContent": sources.iter().map(|(_, text)| text).collect::<Vec<_>>(),
+        "names": [],
+        "mappings": mappings,
+    });
+    map.to_string()
+}
+
+fn vlq(out: &mut String, value: i64) {
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut v = if value < 0 { ((-value) << 1) | 1 } else { value << 1 };
+    loop {
+        let mut digit = v & 31;
+        v >>= 5;
+        if v > 0 {
+            digit |= 32;
+        }
+        out.push(CHARS[digit as usize] as char);
+        if v == 0 {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "[data-v-1]";
+
+    #[test]
+    fn scopes_last_compound_before_pseudo_classes() {
+        assert_eq!(scope_selector(".card .title", TOKEN), ".card .title[data-v-1]");
+        assert_eq!(scope_selector(".a > .b:hover", TOKEN), ".a > .b[data-v-1]:hover");
+        assert_eq!(scope_selector("a[href^='x y']", TOKEN), "a[href^='x y'][data-v-1]");
+    }
+
+    #[test]
+    fn global_only_exempts_its_argument() {
+        assert_eq!(scope_selector(":global(.body) .card", TOKEN), ".body .card[data-v-1]");
+        assert_eq!(scope_selector(".card :global(.x)", TOKEN), ".card[data-v-1] .x");
+        assert_eq!(scope_selector(".card:global(.dark)", TOKEN), ".card[data-v-1].dark");
+        assert_eq!(scope_selector(":global(.a > .b)", TOKEN), ".a > .b");
+    }
+
+    #[test]
+    fn only_top_level_styles_are_extracted() {
+        let text = "<!-- <style>.a{}</style> -->\n<template><div><style>.b{}</style></div></template>\n\
+                    <script>const s = \"<style>.c{}</style>\";</script>\n<style scoped>.d{}</style>\n";
+        let blocks = extract(Path::new("x.vue"), text).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!((blocks[0].content.as_str(), blocks[0].scoped, blocks[0].line), (".d{}", true, 3));
+    }
+
+    #[test]
+    fn vue_scope_matches_the_template_id() {
+        let scope = Scope::new(Path::new("/no/such/src/App.vue"), Path::new("src/App.vue"), ScopeStyle::Attribute);
+        assert_eq!(scope.token, "[data-v-_no_such_src_App_vue]");
+    }
+
+    #[test]
+    fn scoped_keyframes_are_renamed_with_their_animations() {
+        let scope = Scope {
+            token: ".s-1".to_string(),
+            id: "s-1".to_string(),
+            style: ScopeStyle::Class,
+        };
+        let mut sheet = css::parse_stylesheet(
+            "@keyframes spin { to { opacity: 1; } }\n.a { animation: spin 1s, fade 2s; -webkit-animation-name: spin; }",
+        )
+        .unwrap();
+        scope.apply(&mut sheet.items);
+        let out = css::to_css(&sheet);
+        assert!(out.contains("@keyframes s-1-spin {"));
+        assert!(out.contains("animation: s-1-spin 1s, fade 2s;"));
+        assert!(out.contains("-webkit-animation-name: s-1-spin;"));
+    }
+
+    #[test]
+    fn lines_map_to_their_rule_and_declaration() {
+        let source = "\n.card {\n  color: red;\n  .title {\n    margin: 0;\n  }\n}\n";
+        let css = ".card {\n  color: red;\n}\n\n.card .title {\n  margin: 0;\n}";
+        assert_eq!(line_map(css, source, None), [1, 2, 1, 0, 3, 4, 3]);
+    }
+
+    #[test]
+    fn deep_leaves_its_argument_unscoped() {
+        assert_eq!(scope_selector(".a :deep(.b)", TOKEN), ".a[data-v-1] .b");
+        assert_eq!(scope_selector(":deep(.b)", TOKEN), "[data-v-1] .b");
+    }
+}
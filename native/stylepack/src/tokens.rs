@@ -0,0 +1,297 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use crate::css::split_top_level;
+use crate::scss;
+
+#[derive(clap::Args, Debug)]
+pub struct TokensArgs {
+    #[arg(short, long)]
+    input: PathBuf,
+
+    #[arg(long)]
+    include: Vec<PathBuf>,
+
+    #[arg(long)]
+    json: Option<PathBuf>,
+
+    #[arg(long)]
+    ts: Option<PathBuf>,
+
+    #[arg(long)]
+    css: Option<PathBuf>,
+
+    /// Key style for the JSON and TypeScript outputs.
+    #[arg(long, value_enum, default_value_t = Naming::Camel)]
+    naming: Naming,
+
+    /// Prefix for generated custom properties, e.g. `jen` gives `--jen-bg`.
+    #[arg(long)]
+    prefix: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Naming {
+    Kebab,
+    Camel,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Scalar(String),
+    Map(Vec<(String, Token)>),
+}
+
+pub fn run(args: &TokensArgs) -> Result<()> {
+    if args.json.is_none() && args.ts.is_none() && args.css.is_none() {
+        bail!("nothing to write: pass --json, --ts and/or --css");
+    }
+    let mut include = args.include.clone();
+    if let Some(parent) = args.input.parent() {
+        include.push(parent.to_path_buf());
+    }
+    let vars = scss::top_level_variables(&args.input, &include)
+        .with_context(|| format!("evaluate: {}", args.input.display()))?;
+    let tokens: Vec<(String, Token)> = vars
+        .into_iter()
+        .filter(|(name, _)| !name.starts_with('_') && !name.starts_with('-'))
+        .map(|(name, value)| (name, parse_token(&value)))
+        .collect();
+
+    if let Some(p) = &args.json {
+        write(p, &to_json(&tokens, args.naming))?;
+    }
+    if let Some(p) = &args.ts {
+        write(p, &to_ts(&tokens, args.naming))?;
+    }
+    if let Some(p) = &args.css {
+        write(p, &to_css(&tokens, args.prefix.as_deref()))?;
+    }
+    eprintln!("stylepack tokens: {} token(s) exported", tokens.len());
+    Ok(())
+}
+
+/// Sass maps `(key: value, ...)` become nested tokens; everything else,
+/// lists included, stays a scalar string.
+pub fn parse_token(value: &str) -> Token {
+    let v = value.trim();
+    if v.starts_with('(') && v.ends_with(')') {
+        let inner = &v[1..v.len() - 1];
+        let entries = split_top_level(inner, ',');
+        let pairs: Option<Vec<(String, Token)>> = entries
+            .iter()
+            .map(|e| {
+                let (k, val) = split_map_entry(e)?;
+                Some((k, parse_token(&val)))
+            })
+            .collect();
+        if let Some(pairs) = pairs.filter(|p| !p.is_empty()) {
+            return Token::Map(pairs);
+        }
+    }
+    Token::Scalar(v.to_string())
+}
+
+fn split_map_entry(e: &str) -> Option<(String, String)> {
+    let mut depth = 0i32;
+    let mut in_str: Option<char> = None;
+    for (i, c) in e.char_indices() {
+        if let Some(q) = in_str {
+            if c == q {
+                in_str = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => in_str = Some(c),
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ':' if depth == 0 => {
+                let key = e[..i].trim().trim_matches(['"', '\'']).to_string();
+                return Some((key, e[i + 1..].trim().to_string()));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn key(name: &str, naming: Naming) -> String {
+    match naming {
+        Naming::Kebab => name.replace('_', "-"),
+        Naming::Camel => crate::modules::camel_case(&name.replace('_', "-")),
+    }
+}
+
+fn json_value(t: &Token, naming: Naming) -> Value {
+    match t {
+        Token::Scalar(s) if s.starts_with('+') => Value::String(s.clone()),
+        Token::Scalar(s) => {
+            if let Ok(n) = s.parse::<i64>() {
+                return Value::from(n);
+            }
+            match s.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+                Some(n) => Value::Number(n),
+                None => Value::String(unquote(s)),
+            }
+        }
+        Token::Map(pairs) => {
+            let mut m = Map::new();
+            for (k, v) in pairs {
+                m.insert(key(k, naming), json_value(v, naming));
+            }
+            Value::Object(m)
+        }
+    }
+}
+
+fn unquote(s: &str) -> String {
+    let t = s.trim();
+    if t.len() >= 2 && (t.starts_with('"') && t.ends_with('"') || t.starts_with('\'') && t.ends_with('\'')) {
+        t[1..t.len() - 1].to_string()
+    } else {
+        t.to_string()
+    }
+}
+
+pub fn to_json(tokens: &[(String, Token)], naming: Naming) -> String {
+    let mut m = Map::new();
+    for (k, v) in tokens {
+        m.insert(key(k, naming), json_value(v, naming));
+    }
+    let mut out = serde_json::to_string_pretty(&Value::Object(m)).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+pub fn to_ts(tokens: &[(String, Token)], naming: Naming) -> String {
+    let mut out = String::from("// Generated by `stylepack tokens`. Do not edit.\n\nexport const tokens = ");
+    write_ts_object(tokens, naming, 0, &mut out);
+    out.push_str(" as const;\n\nexport type Tokens = typeof tokens;\n");
+    out
+}
+
+fn write_ts_object(pairs: &[(String, Token)], naming: Naming, depth: usize, out: &mut String) {
+    let pad = "  ".repeat(depth + 1);
+    out.push_str("{\n");
+    for (k, v) in pairs {
+        let k = key(k, naming);
+        out.push_str(&pad);
+        if k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+            && !k.starts_with(|c: char| c.is_ascii_digit())
+        {
+            out.push_str(&k);
+        } else {
+            out.push_str(&format!("{:?}", k));
+        }
+        out.push_str(": ");
+        match v {
+            Token::Map(inner) => write_ts_object(inner, naming, depth + 1, out),
+            Token::Scalar(_) => out.push_str(&json_value(v, naming).to_string()),
+        }
+        out.push_str(",\n");
+    }
+    out.push_str(&"  ".repeat(depth));
+    out.push('}');
+}
+
+/// Maps are flattened with `-` so `$colors: (primary: ...)` becomes
+/// `--colors-primary`.
+pub fn to_css(tokens: &[(String, Token)], prefix: Option<&str>) -> String {
+    let mut decls = Vec::new();
+    for (k, v) in tokens {
+        let base = match prefix {
+            Some(p) => format!("{}-{}", p, key(k, Naming::Kebab)),
+            None => key(k, Naming::Kebab),
+        };
+        flatten_css(&base, v, &mut decls);
+    }
+    let mut out = String::from(":root {\n");
+    for (k, v) in decls {
+        out.push_str(&format!("  --{}: {};\n", k, v));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn flatten_css(name: &str, t: &Token, out: &mut Vec<(String, String)>) {
+    match t {
+        Token::Scalar(s) => out.push((name.to_string(), s.clone())),
+        Token::Map(pairs) => {
+            for (k, v) in pairs {
+                flatten_css(&format!("{}-{}", name, key(k, Naming::Kebab)), v, out);
+            }
+        }
+    }
+}
+
+fn write(path: &Path, text: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("mkdir: {}", parent.display()))?;
+    }
+    std::fs::write(path, text).with_context(|| format!("write: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> Vec<(String, Token)> {
+        vec![
+            ("space_unit".to_string(), parse_token("4px")),
+            ("columns".to_string(), parse_token("12")),
+            ("font".to_string(), parse_token("\"Inter\", sans-serif")),
+            ("colors".to_string(), parse_token("(primary: #06f, \"on-primary\": white, shades: (light: #eef))")),
+        ]
+    }
+
+    #[test]
+    fn maps_become_nested_tokens() {
+        let Token::Map(pairs) = parse_token("(a: 1, b: (c: 2))") else { panic!("not a map") };
+        assert_eq!(pairs[0], ("a".to_string(), Token::Scalar("1".to_string())));
+        assert!(matches!(&pairs[1].1, Token::Map(inner) if inner.len() == 1));
+        assert_eq!(parse_token("(1, 2)"), Token::Scalar("(1, 2)".to_string()));
+    }
+
+    #[test]
+    fn json_uses_numbers_and_naming() {
+        let json: Value = serde_json::from_str(&to_json(&tokens(), Naming::Camel)).unwrap();
+        assert_eq!(json["spaceUnit"], "4px");
+        assert_eq!(json["columns"], 12);
+        assert_eq!(json["colors"]["onPrimary"], "white");
+        assert_eq!(json["colors"]["shades"]["light"], "#eef");
+    }
+
+    #[test]
+    fn css_flattens_maps() {
+        let css = to_css(&tokens(), Some("ds"));
+        assert!(css.contains("  --ds-space-unit: 4px;\n"), "{}", css);
+        assert!(css.contains("  --ds-colors-shades-light: #eef;\n"), "{}", css);
+    }
+
+    #[test]
+    fn ts_quotes_keys_that_are_not_identifiers() {
+        let ts = to_ts(&tokens(), Naming::Kebab);
+        assert!(ts.contains("  \"space-unit\": \"4px\",\n"), "{}", ts);
+        assert!(ts.contains("  columns: 12,\n"), "{}", ts);
+        assert!(ts.ends_with("export type Tokens = typeof tokens;\n"));
+    }
+}
@@ -0,0 +1,275 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Result};
+
+use super::{walk, walk_nodes, Transform, Visitor};
+use crate::css::{AtRule, Decl, Item, Stylesheet};
+use crate::scss::Node;
+
+/// Names accepted by [`builtin`], as `name` or `name=option`.
+pub const BUILTINS: &[&str] = &["px-to-rem", "layer", "no-literal-colors"];
+
+/// Builds a built-in transform from a CLI spec such as `px-to-rem=16` or
+/// `layer=components`.
+pub fn builtin(spec: &str) -> Result<Box<dyn Transform>> {
+    let (name, option) = match spec.split_once('=') {
+        Some((n, o)) => (n, Some(o)),
+        None => (spec, None),
+    };
+    Ok(match name {
+        "px-to-rem" => {
+            let base = match option {
+                Some(o) => match o.parse::<f64>() {
+                    Ok(b) if b > 0.0 => b,
+                    _ => bail!("px-to-rem: root size must be a positive number: {}", o),
+                },
+                None => 16.0,
+            };
+            Box::new(PxToRem { spec: spec.to_string(), base })
+        }
+        "layer" => match option {
+            Some(layer) if !layer.is_empty() => Box::new(Layer {
+                spec: spec.to_string(),
+                layer: layer.to_string(),
+            }),
+            _ => bail!("layer: needs a layer name, e.g. layer=components"),
+        },
+        "no-literal-colors" => Box::new(NoLiteralColors),
+        _ => bail!("unknown transform: {} (built-in: {})", name, BUILTINS.join(", ")),
+    })
+}
+
+/// Converts `px` lengths in declarations to `rem`. Hairlines (1px and
+/// under) are left alone.
+struct PxToRem {
+    spec: String,
+    base: f64,
+}
+
+impl Transform for PxToRem {
+    fn name(&self) -> &str {
+        &self.spec
+    }
+
+    fn after_flatten(&mut self, sheet: &mut Stylesheet) -> Result<()> {
+        walk(&mut sheet.items, self)
+    }
+}
+
+impl Visitor for PxToRem {
+    fn visit_decl(&mut self, decl: &mut Decl) -> Result<()> {
+        decl.value = px_to_rem(&decl.value, self.base);
+        Ok(())
+    }
+}
+
+fn px_to_rem(value: &str, base: f64) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    let mut prev: Option<char> = None;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' || c == '\'' {
+            let end = rest[1..].find(c).map_or(rest.len(), |e| e + 2);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            prev = Some(c);
+            continue;
+        }
+        if rest.starts_with("url(") {
+            let end = rest.find(')').map_or(rest.len(), |e| e + 1);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+            prev = Some(')');
+            continue;
+        }
+        let starts_number = c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|d: char| d.is_ascii_digit()));
+        let at_boundary = prev.is_none_or(|p| !(p.is_alphanumeric() || p == '_' || p == '.' || p == '#'));
+        if starts_number && at_boundary {
+            let end = rest.find(|d: char| !(d.is_ascii_digit() || d == '.')).unwrap_or(rest.len());
+            let unit_end = end + 2;
+            let is_px = rest[end..].starts_with("px")
+                && !rest[unit_end.min(rest.len())..].starts_with(|d: char| d.is_alphanumeric() || d == '-' || d == '_');
+            if let (true, Ok(px)) = (is_px, rest[..end].parse::<f64>()) {
+                if px > 1.0 {
+                    let rem = (px / base * 10000.0).round() / 10000.0;
+                    out.push_str(&format!("{}rem", rem));
+                    rest = &rest[unit_end..];
+                    prev = Some('m');
+                    continue;
+                }
+            }
+            out.push_str(&rest[..end]);
+            prev = rest[..end].chars().last();
+            rest = &rest[end..];
+            continue;
+        }
+        out.push(c);
+        prev = Some(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Wraps the whole stylesheet in `@layer <name> { }`. `@charset` and
+/// `@import` stay in front, where CSS requires them.
+struct Layer {
+    spec: String,
+    layer: String,
+}
+
+impl Transform for Layer {
+    fn name(&self) -> &str {
+        &self.spec
+    }
+
+    fn before_serialize(&mut self, sheet: &mut Stylesheet) -> Result<()> {
+        let items = std::mem::take(&mut sheet.items);
+        let (head, body): (Vec<Item>, Vec<Item>) = items
+            .into_iter()
+            .partition(|i| matches!(i, Item::At(a) if a.name == "charset" || a.name == "import"));
+        sheet.items = head;
+        sheet.items.push(Item::At(AtRule {
+            name: "layer".to_string(),
+            prelude: self.layer.clone(),
+            body: Some(body),
+        }));
+        Ok(())
+    }
+}
+
+/// Fails the build when a declaration uses a literal color instead of a
+/// variable. `$variable` and custom property definitions are where tokens
+/// live, so they are exempt.
+struct NoLiteralColors;
+
+impl Transform for NoLiteralColors {
+    fn name(&self) -> &str {
+        "no-literal-colors"
+    }
+
+    fn before_eval(&mut self, nodes: &mut Vec<Node>) -> Result<()> {
+        let mut found = Vec::new();
+        walk_nodes(nodes, &mut |n| {
+            if let Node::Decl { prop, value, .. } = n {
+                if !prop.starts_with("--") && has_literal_color(value) {
+                    found.push(format!("  {}: {}", prop, value));
+                }
+            }
+            Ok(())
+        })?;
+        if !found.is_empty() {
+            bail!("literal colors outside tokens:\n{}", found.join("\n"));
+        }
+        Ok(())
+    }
+}
+
+fn has_literal_color(value: &str) -> bool {
+    let value = without_urls_and_strings(value);
+    let bytes = value.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'#' {
+            let hex = bytes[i + 1..].iter().take_while(|c| c.is_ascii_hexdigit()).count();
+            let next = bytes.get(i + 1 + hex);
+            if matches!(hex, 3 | 4 | 6 | 8) && !next.is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'-' || *c == b'_') {
+                return true;
+            }
+        }
+    }
+    let lower = value.to_ascii_lowercase();
+    ["rgb(", "rgba(", "hsl(", "hsla(", "hwb(", "lab(", "lch(", "oklab(", "oklch("]
+        .iter()
+        .any(|f| lower.contains(f))
+}
+
+/// `value` with the contents of its `url()`s and strings left out, as
+/// `px_to_rem` skips them; a file name or fragment there isn't a color.
+fn without_urls_and_strings(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(c) = rest.chars().next() {
+        let end = if c == '"' || c == '\'' {
+            rest[1..].find(c).map_or(rest.len(), |e| e + 2)
+        } else if rest.get(..4).is_some_and(|u| u.eq_ignore_ascii_case("url(")) {
+            rest.find(')').map_or(rest.len(), |e| e + 1)
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        out.push(' ');
+        rest = &rest[end..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finish(css: &str, specs: &[&str]) -> String {
+        let mut transforms: Vec<Box<dyn Transform>> = specs.iter().map(|s| builtin(s).unwrap()).collect();
+        super::super::finish(css, &mut transforms).unwrap()
+    }
+
+    #[test]
+    fn px_to_rem_skips_hairlines_and_non_lengths() {
+        assert_eq!(px_to_rem("16px 8px 1px 0.5px", 16.0), "1rem 0.5rem 1px 0.5px");
+        assert_eq!(px_to_rem("calc(100% - 24px)", 16.0), "calc(100% - 1.5rem)");
+        assert_eq!(px_to_rem("url(a-20px.png) \"20px\" #20px a20px 20pxx", 16.0), "url(a-20px.png) \"20px\" #20px a20px 20pxx");
+        assert_eq!(px_to_rem("10px", 10.0), "1rem");
+        assert_eq!(finish("a { margin: 32px; }", &["px-to-rem"]), "a {\n  margin: 2rem;\n}\n\n");
+    }
+
+    #[test]
+    fn layer_keeps_charset_and_imports_in_front() {
+        let out = finish("@charset \"utf-8\"; @import url(x.css); a { b: c; }", &["layer=components"]);
+        assert_eq!(
+            out,
+            "@charset \"utf-8\";\n\n@import url(x.css);\n\n@layer components {\n  a {\n    b: c;\n  }\n}\n\n"
+        );
+    }
+
+    #[test]
+    fn literal_colors() {
+        for v in ["#fff", "#a1b2c3 solid", "1px solid RGBA(0, 0, 0, .5)", "oklch(70% 0.1 200)"] {
+            assert!(has_literal_color(v), "{}", v);
+        }
+        for v in [
+            "$accent",
+            "var(--accent)",
+            "#{$x}",
+            "url(#frag)",
+            "#abcdefg",
+            "#ab",
+            "url(#abc)",
+            "url(icons/rgb(1).svg)",
+            "URL(\"sprite.svg#fade\") no-repeat",
+            "\"#fff\"",
+        ] {
+            assert!(!has_literal_color(v), "{}", v);
+        }
+    }
+
+    #[test]
+    fn specs() {
+        assert_eq!(builtin("px-to-rem=10").unwrap().name(), "px-to-rem=10");
+        for bad in ["px-to-rem=0", "px-to-rem=x", "layer", "layer=", "minify"] {
+            assert!(builtin(bad).is_err(), "{}", bad);
+        }
+    }
+}
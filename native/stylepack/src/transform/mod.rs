@@ -0,0 +1,130 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::Result;
+
+use crate::css::{self, AtRule, Decl, Item, Rule, Stylesheet};
+use crate::scss::Node;
+
+mod builtin;
+
+pub use builtin::{builtin, BUILTINS};
+
+/// A custom compile step. Each hook defaults to doing nothing, so a
+/// transform only implements the stages it cares about.
+pub trait Transform {
+    /// How it is enabled from the CLI; also part of the cache fingerprint.
+    fn name(&self) -> &str;
+
+    /// Parsed SCSS with imports expanded, before variables are evaluated.
+    fn before_eval(&mut self, _nodes: &mut Vec<Node>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Plain CSS straight out of the compiler, nesting resolved.
+    fn after_flatten(&mut self, _sheet: &mut Stylesheet) -> Result<()> {
+        Ok(())
+    }
+
+    /// The final stylesheet, after modules, prefixing and RTL, right before
+    /// it is turned back into text.
+    fn before_serialize(&mut self, _sheet: &mut Stylesheet) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Callbacks for [`walk`]. Rules and at-rules are visited before their
+/// bodies.
+pub trait Visitor {
+    fn visit_rule(&mut self, _rule: &mut Rule) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_at_rule(&mut self, _at: &mut AtRule) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_decl(&mut self, _decl: &mut Decl) -> Result<()> {
+        Ok(())
+    }
+
+    fn visit_comment(&mut self, _text: &mut String) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub fn walk<V: Visitor + ?Sized>(items: &mut [Item], v: &mut V) -> Result<()> {
+    for item in items {
+        match item {
+            Item::Rule(r) => {
+                v.visit_rule(r)?;
+                walk(&mut r.body, v)?;
+            }
+            Item::At(a) => {
+                v.visit_at_rule(a)?;
+                if let Some(body) = &mut a.body {
+                    walk(body, v)?;
+                }
+            }
+            Item::Decl(d) => v.visit_decl(d)?,
+            Item::Comment(c) => v.visit_comment(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Calls `f` on every SCSS node, parents before children.
+pub fn walk_nodes(nodes: &mut [Node], f: &mut dyn FnMut(&mut Node) -> Result<()>) -> Result<()> {
+    for n in nodes {
+        f(n)?;
+        for body in n.bodies_mut() {
+            walk_nodes(body, f)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn before_eval(nodes: &mut Vec<Node>, transforms: &mut [Box<dyn Transform>]) -> Result<()> {
+    for t in transforms {
+        t.before_eval(nodes)?;
+    }
+    Ok(())
+}
+
+pub fn after_flatten(sheet: &mut Stylesheet, transforms: &mut [Box<dyn Transform>]) -> Result<()> {
+    for t in transforms {
+        t.after_flatten(sheet)?;
+    }
+    Ok(())
+}
+
+pub fn before_serialize(sheet: &mut Stylesheet, transforms: &mut [Box<dyn Transform>]) -> Result<()> {
+    for t in transforms {
+        t.before_serialize(sheet)?;
+    }
+    Ok(())
+}
+
+/// Runs the post-compile hooks over compiled CSS text.
+pub fn finish(css: &str, transforms: &mut [Box<dyn Transform>]) -> Result<String> {
+    if transforms.is_empty() {
+        return Ok(css.to_string());
+    }
+    let mut sheet = css::parse_stylesheet(css)?;
+    after_flatten(&mut sheet, transforms)?;
+    before_serialize(&mut sheet, transforms)?;
+    Ok(css::to_css(&sheet))
+}
@@ -0,0 +1,184 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::css::{Item, Stylesheet};
+
+/// A named set of variable overrides, from a variants file:
+///
+/// ```toml
+/// [[variant]]
+/// name = "light"
+///
+/// [[variant]]
+/// name = "dark"
+/// selector = "[data-theme=dark]"
+/// define = { accent = "#e11d48", surface = "#0b0b0f" }
+/// ```
+#[derive(Debug, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    /// Scope the variant's rules under this selector.
+    pub selector: Option<String>,
+    #[serde(default)]
+    define: toml::Table,
+}
+
+#[derive(Deserialize)]
+struct File {
+    #[serde(default)]
+    variant: Vec<Variant>,
+}
+
+impl Variant {
+    pub fn defines(&self) -> Vec<(String, String)> {
+        self.define
+            .iter()
+            .map(|(k, v)| {
+                let value = match v {
+                    toml::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (k.trim_start_matches('$').to_string(), value)
+            })
+            .collect()
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<Variant>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("read: {}", path.display()))?;
+    let file: File = toml::from_str(&text).with_context(|| format!("parse: {}", path.display()))?;
+    if file.variant.is_empty() {
+        bail!("{}: no [[variant]] entries", path.display());
+    }
+    for (i, v) in file.variant.iter().enumerate() {
+        if file.variant[..i].iter().any(|w| w.name == v.name) {
+            bail!("{}: duplicate variant {}", path.display(), v.name);
+        }
+    }
+    Ok(file.variant)
+}
+
+/// Parses a `--define '$name=value'` flag.
+pub fn parse_define(s: &str) -> Result<(String, String)> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim_start_matches('$').is_empty() => {
+            Ok((name.trim_start_matches('$').to_string(), value.to_string()))
+        }
+        _ => bail!("--define must look like $name=value: {}", s),
+    }
+}
+
+/// `out/app.css` -> `out/app.dark.css`.
+pub fn output_path(output: &Path, name: &str) -> PathBuf {
+    let stem = output.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let file = match output.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, name, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, name),
+    };
+    output.with_file_name(file)
+}
+
+/// Scopes every rule under `selector`. `:root` and `html` are compounded
+/// with it, so `[data-theme=dark]` on the root element matches them too.
+pub fn scope(sheet: &mut Stylesheet, selector: &str) {
+    scope_items(&mut sheet.items, selector);
+}
+
+fn scope_items(items: &mut [Item], selector: &str) {
+    for item in items {
+        match item {
+            Item::Rule(rule) => {
+                for sel in &mut rule.selectors {
+                    *sel = scope_selector(sel, selector);
+                }
+            }
+            Item::At(at) if !at.name.ends_with("keyframes") => {
+                if let Some(body) = &mut at.body {
+                    scope_items(body, selector);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn scope_selector(sel: &str, scope: &str) -> String {
+    for root in [":root", "html"] {
+        if let Some(rest) = sel.strip_prefix(root) {
+            let boundary = rest.chars().next().is_none_or(|c| !(c.is_alphanumeric() || c == '-' || c == '_'));
+            if boundary {
+                return format!("{}{}{}", root, scope, rest);
+            }
+        }
+    }
+    format!("{} {}", scope, sel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::css::{parse_stylesheet, to_css};
+
+    #[test]
+    fn scopes_rules_and_compounds_root() {
+        let mut sheet = parse_stylesheet(
+            ":root { --a: 1; } html.js { b: c; } htmlx, a { b: c; } @media print { p { b: c; } } @keyframes k { from { b: c; } }",
+        )
+        .unwrap();
+        scope(&mut sheet, "[data-theme=dark]");
+        let out = to_css(&sheet);
+        assert!(out.contains(":root[data-theme=dark]"), "{}", out);
+        assert!(out.contains("html[data-theme=dark].js"), "{}", out);
+        assert!(out.contains("[data-theme=dark] htmlx"), "{}", out);
+        assert!(out.contains("[data-theme=dark] a"), "{}", out);
+        assert!(out.contains("[data-theme=dark] p"), "{}", out);
+        assert!(!out.contains("[data-theme=dark] from"), "{}", out);
+    }
+
+    #[test]
+    fn defines_and_output_paths() {
+        assert_eq!(parse_define("$accent=#fff").unwrap(), ("accent".to_string(), "#fff".to_string()));
+        assert_eq!(parse_define("a=b=c").unwrap(), ("a".to_string(), "b=c".to_string()));
+        assert!(parse_define("$=1").is_err());
+        assert!(parse_define("accent").is_err());
+        assert_eq!(output_path(Path::new("out/app.css"), "dark"), PathBuf::from("out/app.dark.css"));
+        assert_eq!(output_path(Path::new("out/app"), "dark"), PathBuf::from("out/app.dark"));
+    }
+
+    #[test]
+    fn loads_variant_files() {
+        let dir = std::env::temp_dir().join(format!("stylepack-variants-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let good = dir.join("good.toml");
+        let dup = dir.join("dup.toml");
+        std::fs::write(&good, "[[variant]]\nname = \"light\"\n\n[[variant]]\nname = \"dark\"\ndefine = { \"$gap\" = 4, accent = \"#000\" }\n").unwrap();
+        std::fs::write(&dup, "[[variant]]\nname = \"a\"\n[[variant]]\nname = \"a\"\n").unwrap();
+        let variants = load(&good);
+        let dup_err = load(&dup).unwrap_err().to_string();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let variants = variants.unwrap();
+        assert_eq!(variants.len(), 2);
+        let mut defines = variants[1].defines();
+        defines.sort();
+        assert_eq!(defines, [("accent".to_string(), "#000".to_string()), ("gap".to_string(), "4".to_string())]);
+        assert!(dup_err.contains("duplicate variant a"), "{}", dup_err);
+    }
+}
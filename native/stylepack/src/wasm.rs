@@ -0,0 +1,69 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+
+//! `wasm-bindgen` exports for the `wasm` feature, for hosts with no
+//! filesystem such as the docs playground. Imports are served from an
+//! in-memory map instead.
+
+use js_sys::{Array, Map, Object, JSON};
+use serde_json::json;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::scss;
+
+/// `compileString(source, importers?, style?)`. `importers` is an object or
+/// `Map` from paths such as `_vars.scss` to their contents; `style` is
+/// `"expanded"` (the default) or `"compressed"`. Returns
+/// `{ css, loadedUrls, messages }` and throws on compile errors.
+#[wasm_bindgen(js_name = compileString)]
+pub fn compile_string(source: &str, importers: JsValue, style: Option<String>) -> Result<JsValue, JsError> {
+    let files = importer_map(&importers)?;
+    let compiled = scss::compile_string(source, &files, &scss::CompileOptions::default())
+        .map_err(|e| JsError::new(&format!("{:#}", e)))?;
+    let css = match style.as_deref() {
+        None | Some("expanded") => compiled.css,
+        Some("compressed") => scss::minify_css(&compiled.css),
+        Some(other) => return Err(JsError::new(&format!("unknown style: {}", other))),
+    };
+    let result = json!({
+        "css": css,
+        "loadedUrls": compiled.loaded.iter().skip(1).map(|p| p.display().to_string()).collect::<Vec<_>>(),
+        "messages": compiled.messages,
+    });
+    JSON::parse(&result.to_string()).map_err(|_| JsError::new("could not build result"))
+}
+
+fn importer_map(importers: &JsValue) -> Result<BTreeMap<String, String>, JsError> {
+    let mut files = BTreeMap::new();
+    if importers.is_undefined() || importers.is_null() {
+        return Ok(files);
+    }
+    let entries: Array = match importers.dyn_ref::<Map>() {
+        Some(map) => Array::from(&map.entries().into()),
+        None => Object::entries(importers.unchecked_ref()),
+    };
+    for entry in entries.iter() {
+        let pair: Array = entry.unchecked_into();
+        let (Some(path), Some(contents)) = (pair.get(0).as_string(), pair.get(1).as_string()) else {
+            return Err(JsError::new("importers must map paths to strings"));
+        };
+        files.insert(path, contents);
+    }
+    Ok(files)
+}
@@ -0,0 +1,249 @@
+# This file is part of Jen.js.
+# Copyright (C) 2026 oopsio
+# 
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+# 
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+# GNU General Public License for more details.
+# 
+# You should have received a copy of the GNU General Public License
+# along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Runs the HRX fixtures in `tests/spec` against the stylepack binary. The
+//! fixtures are hand-written for stylepack; they borrow sass-spec's archive
+//! layout but aren't taken from sass-spec.
+//!
+//! Each directory in an archive holding an `input.scss` is a case. It passes
+//! when the compiled CSS matches `output.css` up to formatting whitespace,
+//! or, for a case with an `error` file instead, when compilation fails with
+//! the message on that file's `Error:` line. A panic always fails the case.
+//! Cases listed in `tests/spec/allowlist.txt` are expected to fail; one that
+//! starts passing fails the run until it is removed, so the list only
+//! shrinks. Set `SPEC_FILTER` to run the cases whose id contains it.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SPEC_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/spec");
+
+struct Case {
+    /// `<archive>/<dir>`, e.g. `nesting/parent/suffix`.
+    id: String,
+    files: Vec<(String, String)>,
+}
+
+enum Outcome {
+    Pass,
+    Fail(String),
+}
+
+#[test]
+fn spec_fixtures() {
+    let allowlist = read_allowlist();
+    let filter = std::env::var("SPEC_FILTER").ok();
+    let scratch = std::env::temp_dir().join(format!("stylepack-spec-{}", std::process::id()));
+
+    let mut per_dir: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut problems = Vec::new();
+    let mut seen = Vec::new();
+
+    for case in load_cases() {
+        if filter.as_deref().is_some_and(|f| !case.id.contains(f)) {
+            continue;
+        }
+        seen.push(case.id.clone());
+        let outcome = run_case(&case, &scratch.join(&case.id));
+        let passed = matches!(outcome, Outcome::Pass);
+
+        let dir = case.id.splitn(3, '/').take(2).collect::<Vec<_>>().join("/");
+        let entry = per_dir.entry(dir).or_default();
+        if passed {
+            entry.0 += 1;
+        } else {
+            entry.1 += 1;
+        }
+
+        match (outcome, allowlist.contains(&case.id)) {
+            (Outcome::Pass, true) => problems.push(format!(
+                "{}: passes now; remove it from allowlist.txt",
+                case.id
+            )),
+            (Outcome::Fail(why), false) => problems.push(format!("{}: {}", case.id, why)),
+            _ => {}
+        }
+    }
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    for id in &allowlist {
+        if filter.is_none() && !seen.contains(id) {
+            problems.push(format!("{}: in allowlist.txt but no such case", id));
+        }
+    }
+
+    let (pass, fail) = per_dir.values().fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+    eprintln!("spec fixtures: {} passed, {} failed", pass, fail);
+    for (dir, (p, f)) in &per_dir {
+        eprintln!("  {:<40} {:>3} passed {:>3} failed", dir, p, f);
+    }
+    if !problems.is_empty() {
+        panic!("{} spec problem(s):\n\n{}", problems.len(), problems.join("\n\n"));
+    }
+}
+
+fn run_case(case: &Case, dir: &Path) -> Outcome {
+    let _ = std::fs::remove_dir_all(dir);
+    for (path, contents) in &case.files {
+        let p = dir.join(path);
+        std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+        std::fs::write(&p, contents).unwrap();
+    }
+    let result = Command::new(env!("CARGO_BIN_EXE_stylepack"))
+        .current_dir(dir)
+        .args(["-i", "input.scss", "-o", "out/output.css", "--no-cache"])
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .expect("run stylepack");
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    if stderr.contains("panicked at") {
+        return Outcome::Fail(format!("panicked:\n{}", stderr.trim_end()));
+    }
+
+    let expected = case.files.iter().find(|(p, _)| p == "output.css");
+    let error = case.files.iter().find(|(p, _)| p == "error");
+    match (expected, result.status.success()) {
+        (Some((_, want)), true) => {
+            let got = std::fs::read_to_string(dir.join("out/output.css")).unwrap_or_default();
+            if normalize(&got) == normalize(want) {
+                Outcome::Pass
+            } else {
+                Outcome::Fail(format!("expected:\n{}\ngot:\n{}", want.trim_end(), got.trim_end()))
+            }
+        }
+        (Some(_), false) => Outcome::Fail(format!("failed to compile:\n{}", stderr.trim_end())),
+        (None, false) => {
+            let want = error.and_then(|(_, e)| e.lines().next()).unwrap_or_default();
+            let got = format!("Error: {}", root_cause(&stderr));
+            if got == want {
+                Outcome::Pass
+            } else {
+                Outcome::Fail(format!("expected:\n{}\ngot:\n{}", want, stderr.trim_end()))
+            }
+        }
+        (None, true) => Outcome::Fail("compiled, but an error was expected".to_string()),
+    }
+}
+
+/// First line of the innermost cause in an anyhow report, which is where
+/// the compiler's own message ends up under the CLI's context lines.
+fn root_cause(stderr: &str) -> &str {
+    let Some((head, causes)) = stderr.split_once("\nCaused by:\n") else {
+        return stderr.lines().next().unwrap_or_default().trim_start_matches("Error: ");
+    };
+    let mut numbered = causes.lines().filter_map(|l| {
+        let rest = l.strip_prefix("    ")?;
+        let (n, msg) = rest.split_once(": ")?;
+        n.parse::<usize>().ok().map(|_| msg)
+    });
+    match numbered.next_back() {
+        Some(msg) => msg,
+        None => causes.lines().next().map(str::trim).unwrap_or(head),
+    }
+}
+
+/// Collapses whitespace that only affects formatting, and the optional
+/// semicolon before `}`.
+fn normalize(css: &str) -> String {
+    let tight = |c: char| matches!(c, '{' | '}' | ';' | ',' | '>');
+    let mut out = String::with_capacity(css.len());
+    let mut space = false;
+    for c in css.chars() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if space && !out.is_empty() && !tight(c) && !out.ends_with(tight) {
+            out.push(' ');
+        }
+        space = false;
+        if c == '}' && out.ends_with(';') {
+            out.pop();
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn load_cases() -> Vec<Case> {
+    let mut archives: Vec<PathBuf> = std::fs::read_dir(SPEC_DIR)
+        .unwrap()
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "hrx"))
+        .collect();
+    archives.sort();
+
+    let mut cases = Vec::new();
+    for archive in archives {
+        let name = archive.file_stem().unwrap().to_string_lossy().into_owned();
+        let text = std::fs::read_to_string(&archive).unwrap();
+        let files = parse_hrx(&text);
+        for (path, _) in &files {
+            let Some(dir) = path.strip_suffix("input.scss") else {
+                continue;
+            };
+            let case_files = files
+                .iter()
+                .filter_map(|(p, c)| p.strip_prefix(dir).map(|rel| (rel.to_string(), c.clone())))
+                .collect();
+            cases.push(Case {
+                id: format!("{}/{}", name, dir.trim_end_matches('/')),
+                files: case_files,
+            });
+        }
+    }
+    cases
+}
+
+/// Files in an HRX archive. The boundary is whatever `<=+>` the archive
+/// starts with; a boundary without a path opens a comment.
+fn parse_hrx(text: &str) -> Vec<(String, String)> {
+    let boundary: String = text.chars().take_while(|c| *c != ' ' && *c != '\n').collect();
+    let mut files = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    let mut finish = |cur: Option<(String, Vec<&str>)>| {
+        if let Some((path, lines)) = cur {
+            let mut body = lines.join("\n");
+            body.push('\n');
+            files.push((path, body));
+        }
+    };
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix(&boundary) {
+            finish(current.take());
+            let path = rest.trim();
+            if !path.is_empty() {
+                current = Some((path.to_string(), Vec::new()));
+            }
+            continue;
+        }
+        if let Some((_, lines)) = &mut current {
+            lines.push(line);
+        }
+    }
+    finish(current.take());
+    files
+}
+
+fn read_allowlist() -> Vec<String> {
+    let text = std::fs::read_to_string(Path::new(SPEC_DIR).join("allowlist.txt")).unwrap_or_default();
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}